@@ -0,0 +1,267 @@
+//! Password hashing, bearer tokens and the [`AuthUser`] extractor that guards
+//! every route needing a signed-in user. The extractor accepts both session
+//! tokens and personal API tokens.
+
+use actix_web::{
+    dev::Payload, http::header, http::Method, http::StatusCode, web, FromRequest, HttpRequest,
+    HttpResponse, ResponseError,
+};
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use futures::future::LocalBoxFuture;
+use rand::{rngs::OsRng, RngCore};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::models::{TokenScope, User};
+use crate::store::StoreError;
+use crate::AppState;
+
+pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(Argon2::default()
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string())
+}
+
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    PasswordHash::new(password_hash)
+        .map(|parsed| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &parsed)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+/// A hash of no one's password. Logins for unknown usernames are checked
+/// against it, so they take as long as logins for real ones.
+pub fn dummy_password_hash() -> &'static str {
+    static HASH: OnceLock<String> = OnceLock::new();
+    HASH.get_or_init(|| {
+        hash_password(&generate_token()).expect("hashing a random password succeeds")
+    })
+}
+
+/// Returns a new random bearer token: 256 bits, hex encoded.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Marks personal API tokens so the extractor knows where to look them up.
+pub const API_TOKEN_PREFIX: &str = "kbt_";
+
+pub fn generate_api_token() -> String {
+    format!("{}{}", API_TOKEN_PREFIX, generate_token())
+}
+
+/// Tokens are stored as their SHA-256 so a leaked database cannot be replayed.
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// The caller behind a request's `Authorization: Bearer <token>` header.
+/// Handlers that take this argument reject anonymous requests with 401.
+pub struct AuthUser {
+    pub user: User,
+    pub token_hash: String,
+    pub credential: Credential,
+}
+
+/// What the bearer token turned out to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Credential {
+    Session,
+    ApiToken(TokenScope),
+}
+
+impl AuthUser {
+    /// Acts as `user` outside of any request, for background work done on
+    /// their behalf.
+    pub fn background(user: User) -> Self {
+        AuthUser {
+            user,
+            token_hash: String::new(),
+            credential: Credential::Session,
+        }
+    }
+
+    pub fn is_session(&self) -> bool {
+        self.credential == Credential::Session
+    }
+}
+
+#[derive(Debug)]
+pub enum AuthError {
+    MissingToken,
+    InvalidToken,
+    /// A read-only API token was used for a request that changes data.
+    ReadOnlyToken,
+    Store(StoreError),
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::MissingToken => write!(f, "Authentication required"),
+            AuthError::InvalidToken => write!(f, "Invalid or expired token"),
+            AuthError::ReadOnlyToken => write!(f, "This API token only allows read requests"),
+            AuthError::Store(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl ResponseError for AuthError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AuthError::Store(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AuthError::ReadOnlyToken => StatusCode::FORBIDDEN,
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            AuthError::Store(e) => {
+                eprintln!("Error authenticating request: {}", e);
+                HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Failed to authenticate request"
+                }))
+            }
+            AuthError::ReadOnlyToken => HttpResponse::Forbidden().json(serde_json::json!({
+                "error": self.to_string(),
+                "missingScope": TokenScope::Write
+            })),
+            _ => HttpResponse::Unauthorized()
+                .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+                .json(serde_json::json!({
+                    "error": self.to_string()
+                })),
+        }
+    }
+}
+
+impl From<StoreError> for AuthError {
+    fn from(e: StoreError) -> Self {
+        AuthError::Store(e)
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenQuery {
+    access_token: String,
+}
+
+/// Routes whose browser clients cannot set headers: WebSockets and
+/// `EventSource`.
+const QUERY_TOKEN_ROUTES: [&str; 3] = ["/api/ws", "/api/boards/{board_id}/ws", "/api/events"];
+
+/// Reads the `Authorization: Bearer` header. Tokens in URLs end up in logs
+/// and `Referer` headers, so only `GET` requests to [`QUERY_TOKEN_ROUTES`]
+/// may pass `?access_token=` instead.
+fn bearer_token(req: &HttpRequest) -> Option<String> {
+    if let Some(value) = req.headers().get(header::AUTHORIZATION) {
+        let (scheme, token) = value.to_str().ok()?.split_once(' ')?;
+        if !scheme.eq_ignore_ascii_case("bearer") || token.trim().is_empty() {
+            return None;
+        }
+        return Some(token.trim().to_string());
+    }
+
+    let streaming = req
+        .match_pattern()
+        .is_some_and(|pattern| QUERY_TOKEN_ROUTES.contains(&pattern.as_str()));
+    if req.method() != Method::GET || !streaming {
+        return None;
+    }
+    web::Query::<TokenQuery>::from_query(req.query_string())
+        .ok()
+        .map(|query| query.into_inner().access_token)
+        .filter(|token| !token.is_empty())
+}
+
+async fn authenticate(data: &AppState, token: &str) -> Result<AuthUser, AuthError> {
+    if token.starts_with(API_TOKEN_PREFIX) {
+        return authenticate_api_token(data, token).await;
+    }
+
+    let token_hash = hash_token(token);
+    let session = data
+        .store
+        .get_session(&token_hash)
+        .await?
+        .ok_or(AuthError::InvalidToken)?;
+
+    if session.expires_at <= chrono::Utc::now() {
+        data.store.delete_session(&token_hash).await?;
+        return Err(AuthError::InvalidToken);
+    }
+
+    let user = data
+        .store
+        .get_user(&session.user_id)
+        .await?
+        .ok_or(AuthError::InvalidToken)?;
+    Ok(AuthUser {
+        user,
+        token_hash,
+        credential: Credential::Session,
+    })
+}
+
+async fn authenticate_api_token(data: &AppState, token: &str) -> Result<AuthUser, AuthError> {
+    let token_hash = hash_token(token);
+    let api_token = data
+        .store
+        .get_api_token(&token_hash)
+        .await?
+        .ok_or(AuthError::InvalidToken)?;
+
+    // Expired tokens are kept so they still show up in the owner's list.
+    if api_token
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= chrono::Utc::now())
+    {
+        return Err(AuthError::InvalidToken);
+    }
+
+    let user = data
+        .store
+        .get_user(&api_token.user_id)
+        .await?
+        .ok_or(AuthError::InvalidToken)?;
+    Ok(AuthUser {
+        user,
+        token_hash,
+        credential: Credential::ApiToken(api_token.scope),
+    })
+}
+
+impl FromRequest for AuthUser {
+    type Error = AuthError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let data = req
+            .app_data::<web::Data<AppState>>()
+            .cloned()
+            .expect("AppState is registered with the app");
+        let token = bearer_token(req);
+        let read_only_request = matches!(*req.method(), Method::GET | Method::HEAD);
+
+        Box::pin(async move {
+            let token = token.ok_or(AuthError::MissingToken)?;
+            let user = authenticate(&data, &token).await?;
+            if user.credential == Credential::ApiToken(TokenScope::Read) && !read_only_request {
+                return Err(AuthError::ReadOnlyToken);
+            }
+            Ok(user)
+        })
+    }
+}
@@ -0,0 +1,69 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use super::BlobStore;
+use crate::store::{StoreError, StoreResult};
+
+/// Keeps each blob as a file named after its key in one directory.
+pub struct LocalBlobStore {
+    root: PathBuf,
+}
+
+impl LocalBlobStore {
+    /// Creates the directory if it does not exist yet.
+    pub fn open(root: impl Into<PathBuf>) -> StoreResult<Self> {
+        let root = root.into();
+        std::fs::create_dir_all(&root).map_err(backend_error)?;
+        Ok(Self { root })
+    }
+
+    /// Keys are generated ids; anything that could leave the directory is
+    /// refused.
+    fn path(&self, key: &str) -> StoreResult<PathBuf> {
+        let valid = !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'));
+        if !valid {
+            return Err(StoreError::Backend(format!("invalid blob key {:?}", key)));
+        }
+        Ok(self.root.join(key))
+    }
+}
+
+fn backend_error(e: std::io::Error) -> StoreError {
+    StoreError::Backend(e.to_string())
+}
+
+#[async_trait]
+impl BlobStore for LocalBlobStore {
+    async fn put(&self, key: &str, _content_type: &str, data: Bytes) -> StoreResult<()> {
+        let path = self.path(key)?;
+        // Written under a temporary name first, so a crash never leaves a
+        // truncated blob behind the real key.
+        let partial = path.with_extension("partial");
+        tokio::fs::write(&partial, &data)
+            .await
+            .map_err(backend_error)?;
+        tokio::fs::rename(&partial, &path)
+            .await
+            .map_err(backend_error)
+    }
+
+    async fn get(&self, key: &str) -> StoreResult<Option<Bytes>> {
+        match tokio::fs::read(self.path(key)?).await {
+            Ok(data) => Ok(Some(Bytes::from(data))),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(backend_error(e)),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> StoreResult<()> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(backend_error(e)),
+            _ => Ok(()),
+        }
+    }
+}
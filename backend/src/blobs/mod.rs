@@ -0,0 +1,40 @@
+//! Storage for attachment contents. Attachment metadata lives on the task;
+//! the bytes live here under the attachment's id.
+
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use crate::models::Task;
+use crate::store::StoreResult;
+
+mod local;
+mod s3;
+
+pub use local::LocalBlobStore;
+pub use s3::{S3BlobStore, S3Config};
+
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    /// Stores `data` under `key`, replacing anything already there.
+    async fn put(&self, key: &str, content_type: &str, data: Bytes) -> StoreResult<()>;
+
+    /// Returns the blob, or `None` if nothing is stored under `key`.
+    async fn get(&self, key: &str) -> StoreResult<Option<Bytes>>;
+
+    /// Deleting a missing blob is not an error.
+    async fn delete(&self, key: &str) -> StoreResult<()>;
+}
+
+/// Deletes the contents of every attachment on tasks that were deleted for
+/// good. Failures are only logged: the tasks are already gone, and a
+/// leftover blob is harmless.
+pub async fn delete_attachments(blobs: &dyn BlobStore, tasks: &[Task]) {
+    for attachment in tasks.iter().flat_map(|t| &t.attachments) {
+        if let Err(e) = blobs.delete(&attachment.attachment_id).await {
+            eprintln!(
+                "Error deleting attachment {}: {}",
+                attachment.attachment_id, e
+            );
+        }
+    }
+}
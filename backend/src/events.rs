@@ -0,0 +1,66 @@
+//! Fan-out of task changes to connected clients.
+//!
+//! Handlers publish to the [`EventHub`] in `AppState` and every open
+//! connection subscribes to it. [`LocalHub`] only reaches clients of this
+//! process; running several instances needs a hub backed by a shared broker
+//! that implements the same trait.
+
+use tokio::sync::broadcast;
+
+use crate::models::{Task, TaskEvent, TaskEventKind};
+
+/// How many events a slow subscriber may fall behind before it starts
+/// missing them.
+const CHANNEL_CAPACITY: usize = 256;
+
+pub trait EventHub: Send + Sync {
+    fn publish(&self, event: TaskEvent);
+
+    fn subscribe(&self) -> broadcast::Receiver<TaskEvent>;
+}
+
+pub struct LocalHub {
+    sender: broadcast::Sender<TaskEvent>,
+}
+
+impl LocalHub {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+}
+
+impl EventHub for LocalHub {
+    fn publish(&self, event: TaskEvent) {
+        // Sending only fails when nobody is listening, which is fine.
+        let _ = self.sender.send(event);
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<TaskEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl TaskEvent {
+    /// Describes a change from `before` to `after`; creations have no
+    /// `before` and deletions no `after`.
+    pub fn new(
+        kind: TaskEventKind,
+        before: Option<&Task>,
+        after: Option<&Task>,
+        actor_id: &str,
+    ) -> Self {
+        let task = after.or(before).expect("a task event needs a task");
+        TaskEvent {
+            id: 0,
+            kind,
+            board_id: task.board_id.clone(),
+            task_id: task.task_id.clone(),
+            task: after.cloned(),
+            before: before.cloned(),
+            actor_id: actor_id.to_string(),
+            at: chrono::Utc::now(),
+            wip_override: None,
+        }
+    }
+}
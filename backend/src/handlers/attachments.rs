@@ -0,0 +1,572 @@
+use actix_multipart::{Multipart, MultipartError};
+use actix_web::http::header::{
+    Charset, ContentDisposition, DispositionParam, ExtendedValue, CONTENT_TYPE,
+};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use bytes::{Bytes, BytesMut};
+use futures::StreamExt;
+
+use crate::auth::AuthUser;
+use crate::models::{new_id, Attachment, Permission, TaskUpdate, DEFAULT_BOARD_ID};
+use crate::AppState;
+
+use super::tasks::{task_not_found, task_response, update_with_retry};
+use super::{require_access, server_error};
+
+/// Room for the multipart framing around the file itself.
+const FORM_OVERHEAD: usize = 64 * 1024;
+
+/// What uploads are accepted.
+pub struct AttachmentLimits {
+    /// Largest accepted file, in bytes.
+    pub max_bytes: usize,
+    /// Accepted MIME types; `type/*` accepts every subtype.
+    pub allowed_types: Vec<String>,
+}
+
+impl AttachmentLimits {
+    fn allows(&self, content_type: &mime::Mime) -> bool {
+        self.allowed_types
+            .iter()
+            .any(|allowed| match allowed.strip_suffix("/*") {
+                Some(type_) => content_type.type_() == type_,
+                None => content_type.essence_str() == allowed,
+            })
+    }
+}
+
+fn attachment_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": "Attachment not found"
+    }))
+}
+
+fn too_large(limits: &AttachmentLimits) -> HttpResponse {
+    HttpResponse::PayloadTooLarge().json(serde_json::json!({
+        "error": format!("Attachments may be at most {} bytes", limits.max_bytes),
+        "maxBytes": limits.max_bytes
+    }))
+}
+
+fn bad_upload(message: &str) -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "error": message
+    }))
+}
+
+/// Types clients commonly leave out or send as `application/octet-stream`,
+/// by file extension.
+fn guess_content_type(filename: &str) -> Option<mime::Mime> {
+    let (_, extension) = filename.rsplit_once('.')?;
+    let guessed = match extension.to_ascii_lowercase().as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "txt" | "log" => "text/plain",
+        "csv" => "text/csv",
+        "json" => "application/json",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        _ => return None,
+    };
+    guessed.parse().ok()
+}
+
+/// A file sent in the `file` field of a form.
+struct Upload {
+    filename: String,
+    content_type: Option<mime::Mime>,
+    data: Bytes,
+}
+
+/// The type the client declared for the file, or one guessed from its name
+/// when the declaration says nothing useful.
+fn content_type(upload: &Upload, filename: &str) -> Option<mime::Mime> {
+    let declared = upload
+        .content_type
+        .clone()
+        .filter(|mime| *mime != mime::APPLICATION_OCTET_STREAM);
+    declared.or_else(|| guess_content_type(filename))
+}
+
+fn malformed(_: MultipartError) -> HttpResponse {
+    bad_upload("The multipart body is malformed")
+}
+
+/// Reads a `multipart/form-data` body up to its `file` field. Other fields
+/// are skipped, and the body is refused as soon as the file or everything
+/// read so far grows past the limits.
+async fn read_upload(
+    req: &HttpRequest,
+    payload: web::Payload,
+    limits: &AttachmentLimits,
+) -> Result<Upload, HttpResponse> {
+    let is_form = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<mime::Mime>().ok())
+        .is_some_and(|mime| mime.essence_str() == "multipart/form-data");
+    if !is_form {
+        return Err(bad_upload("Upload the file as multipart/form-data"));
+    }
+
+    let mut form = Multipart::new(req.headers(), payload);
+    let mut body_len = 0;
+    while let Some(field) = form.next().await {
+        let mut field = field.map_err(malformed)?;
+        let filename = field.content_disposition().and_then(|disposition| {
+            disposition.get_filename().map(str::to_string).or_else(|| {
+                disposition
+                    .get_filename_ext()
+                    .map(|ext| String::from_utf8_lossy(&ext.value).into_owned())
+            })
+        });
+        let is_file = field.name() == Some("file") && filename.is_some();
+
+        let mut data = BytesMut::new();
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(malformed)?;
+            body_len += chunk.len();
+            if body_len > limits.max_bytes + FORM_OVERHEAD {
+                return Err(too_large(limits));
+            }
+            if is_file {
+                if data.len() + chunk.len() > limits.max_bytes {
+                    return Err(too_large(limits));
+                }
+                data.extend_from_slice(&chunk);
+            }
+        }
+        if let (true, Some(filename)) = (is_file, filename) {
+            return Ok(Upload {
+                filename,
+                content_type: field.content_type().cloned(),
+                data: data.freeze(),
+            });
+        }
+    }
+    Err(bad_upload("Send the file in a form field named 'file'"))
+}
+
+/// Keeps the last path component of the client's file name, without control
+/// characters.
+fn clean_filename(filename: &str) -> String {
+    let name = filename.rsplit(['/', '\\']).next().unwrap_or_default();
+    let name: String = name.chars().filter(|c| !c.is_control()).take(255).collect();
+    match name.trim() {
+        "" => "attachment".to_string(),
+        name => name.to_string(),
+    }
+}
+
+/// Offers the original file name for saving, with an ASCII fallback for
+/// clients that do not understand `filename*`.
+fn disposition(filename: &str) -> ContentDisposition {
+    let mut disposition =
+        ContentDisposition::attachment(filename.replace(|c: char| !c.is_ascii(), "_"));
+    if !filename.is_ascii() {
+        disposition
+            .parameters
+            .push(DispositionParam::FilenameExt(ExtendedValue {
+                charset: Charset::Ext("UTF-8".to_string()),
+                language_tag: None,
+                value: filename.as_bytes().to_vec(),
+            }));
+    }
+    disposition
+}
+
+async fn list_attachments(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_id: &str,
+) -> HttpResponse {
+    if let Err(response) = require_access(data, user, board_id, Permission::ViewBoard).await {
+        return response;
+    }
+
+    match data.store.get_task(board_id, task_id).await {
+        Ok(Some(task)) => HttpResponse::Ok().json(task.attachments),
+        Ok(None) => task_not_found(),
+        Err(e) => server_error("Error fetching task", "Failed to fetch attachments", e),
+    }
+}
+
+/// Stores the file sent in the `file` field of a `multipart/form-data` body
+/// and attaches it to the task.
+async fn upload_attachment(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_id: &str,
+    req: &HttpRequest,
+    payload: web::Payload,
+) -> HttpResponse {
+    if let Err(response) = require_access(data, user, board_id, Permission::EditTasks).await {
+        return response;
+    }
+    match data.store.get_task(board_id, task_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return task_not_found(),
+        Err(e) => return server_error("Error fetching task", "Failed to upload attachment", e),
+    }
+
+    let limits = &data.attachment_limits;
+    let upload = match read_upload(req, payload, limits).await {
+        Ok(upload) => upload,
+        Err(response) => return response,
+    };
+    if upload.data.is_empty() {
+        return bad_upload("The file is empty");
+    }
+
+    let filename = clean_filename(&upload.filename);
+    let content_type = match content_type(&upload, &filename) {
+        Some(content_type) if limits.allows(&content_type) => content_type,
+        _ => {
+            return HttpResponse::UnsupportedMediaType().json(serde_json::json!({
+                "error": "This type of file cannot be attached",
+                "allowedTypes": limits.allowed_types
+            }))
+        }
+    };
+    let content_type = match content_type.get_param(mime::CHARSET) {
+        Some(charset) => format!("{}; charset={}", content_type.essence_str(), charset),
+        None => content_type.essence_str().to_string(),
+    };
+
+    let attachment = Attachment {
+        attachment_id: new_id("attachment"),
+        filename,
+        content_type,
+        size: upload.data.len() as u64,
+        uploaded_by: user.user.user_id.clone(),
+        uploaded_at: chrono::Utc::now(),
+    };
+    if let Err(e) = data
+        .blobs
+        .put(
+            &attachment.attachment_id,
+            &attachment.content_type,
+            upload.data,
+        )
+        .await
+    {
+        return server_error("Error storing attachment", "Failed to upload attachment", e);
+    }
+
+    let result = update_with_retry(
+        data,
+        user,
+        board_id,
+        task_id,
+        "Failed to upload attachment",
+        task_not_found,
+        |task| {
+            let mut attachments = task.attachments.clone();
+            attachments.push(attachment.clone());
+            Some(TaskUpdate {
+                attachments: Some(attachments),
+                ..Default::default()
+            })
+        },
+    )
+    .await;
+    match result {
+        Ok(task) => task_response(HttpResponse::Created(), &task),
+        Err(response) => {
+            if let Err(e) = data.blobs.delete(&attachment.attachment_id).await {
+                eprintln!(
+                    "Error deleting attachment {}: {}",
+                    attachment.attachment_id, e
+                );
+            }
+            response
+        }
+    }
+}
+
+/// Sends the file back with the type it was uploaded as.
+async fn download_attachment(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_id: &str,
+    attachment_id: &str,
+) -> HttpResponse {
+    if let Err(response) = require_access(data, user, board_id, Permission::ViewBoard).await {
+        return response;
+    }
+    let task = match data.store.get_task(board_id, task_id).await {
+        Ok(Some(task)) => task,
+        Ok(None) => return task_not_found(),
+        Err(e) => return server_error("Error fetching task", "Failed to fetch attachment", e),
+    };
+    let Some(attachment) = task
+        .attachments
+        .iter()
+        .find(|a| a.attachment_id == attachment_id)
+    else {
+        return attachment_not_found();
+    };
+
+    match data.blobs.get(attachment_id).await {
+        Ok(Some(contents)) => HttpResponse::Ok()
+            .content_type(attachment.content_type.as_str())
+            .insert_header(disposition(&attachment.filename))
+            .insert_header(("X-Content-Type-Options", "nosniff"))
+            .body(contents),
+        Ok(None) => {
+            eprintln!("Contents of attachment {} are missing", attachment_id);
+            attachment_not_found()
+        }
+        Err(e) => server_error("Error reading attachment", "Failed to fetch attachment", e),
+    }
+}
+
+async fn delete_attachment(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_id: &str,
+    attachment_id: &str,
+) -> HttpResponse {
+    if let Err(response) = require_access(data, user, board_id, Permission::EditTasks).await {
+        return response;
+    }
+
+    let result = update_with_retry(
+        data,
+        user,
+        board_id,
+        task_id,
+        "Failed to delete attachment",
+        attachment_not_found,
+        |task| {
+            let mut attachments = task.attachments.clone();
+            let index = attachments
+                .iter()
+                .position(|a| a.attachment_id == attachment_id)?;
+            attachments.remove(index);
+            Some(TaskUpdate {
+                attachments: Some(attachments),
+                ..Default::default()
+            })
+        },
+    )
+    .await;
+    let task = match result {
+        Ok(task) => task,
+        Err(response) => return response,
+    };
+
+    // The task no longer points at the blob, so failing to delete it only
+    // leaves an orphan behind.
+    if let Err(e) = data.blobs.delete(attachment_id).await {
+        eprintln!("Error deleting attachment {}: {}", attachment_id, e);
+    }
+    task_response(HttpResponse::Ok(), &task)
+}
+
+pub async fn get_board_attachments(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (board_id, task_id) = path.into_inner();
+    list_attachments(&data, &user, &board_id, &task_id).await
+}
+
+pub async fn upload_board_attachment(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+    req: HttpRequest,
+    payload: web::Payload,
+) -> impl Responder {
+    let (board_id, task_id) = path.into_inner();
+    upload_attachment(&data, &user, &board_id, &task_id, &req, payload).await
+}
+
+pub async fn download_board_attachment(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String, String)>,
+) -> impl Responder {
+    let (board_id, task_id, attachment_id) = path.into_inner();
+    download_attachment(&data, &user, &board_id, &task_id, &attachment_id).await
+}
+
+pub async fn delete_board_attachment(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String, String)>,
+) -> impl Responder {
+    let (board_id, task_id, attachment_id) = path.into_inner();
+    delete_attachment(&data, &user, &board_id, &task_id, &attachment_id).await
+}
+
+// The routes below predate boards and act on the default board.
+
+pub async fn get_default_attachments(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    task_id: web::Path<String>,
+) -> impl Responder {
+    list_attachments(&data, &user, DEFAULT_BOARD_ID, &task_id).await
+}
+
+pub async fn upload_default_attachment(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    task_id: web::Path<String>,
+    req: HttpRequest,
+    payload: web::Payload,
+) -> impl Responder {
+    upload_attachment(&data, &user, DEFAULT_BOARD_ID, &task_id, &req, payload).await
+}
+
+pub async fn download_default_attachment(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (task_id, attachment_id) = path.into_inner();
+    download_attachment(&data, &user, DEFAULT_BOARD_ID, &task_id, &attachment_id).await
+}
+
+pub async fn delete_default_attachment(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (task_id, attachment_id) = path.into_inner();
+    delete_attachment(&data, &user, DEFAULT_BOARD_ID, &task_id, &attachment_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+    use actix_web::FromRequest;
+
+    const BOUNDARY: &str = "X-BOUNDARY";
+
+    fn limits() -> AttachmentLimits {
+        AttachmentLimits {
+            max_bytes: 16,
+            allowed_types: vec!["text/plain".to_string()],
+        }
+    }
+
+    /// Reads `body` the way an upload request with `content_type` would be.
+    async fn read(content_type: &str, body: &str) -> Result<Upload, HttpResponse> {
+        let (req, mut payload) = TestRequest::post()
+            .insert_header((CONTENT_TYPE, content_type))
+            .set_payload(body.replace('\n', "\r\n"))
+            .to_http_parts();
+        let payload = web::Payload::from_request(&req, &mut payload)
+            .await
+            .unwrap();
+        read_upload(&req, payload, &limits()).await
+    }
+
+    async fn read_form(body: &str) -> Result<Upload, HttpResponse> {
+        read(&format!("multipart/form-data; boundary={}", BOUNDARY), body).await
+    }
+
+    fn status(result: Result<Upload, HttpResponse>) -> u16 {
+        match result {
+            Ok(_) => 200,
+            Err(response) => response.status().as_u16(),
+        }
+    }
+
+    #[actix_web::test]
+    async fn reads_the_file_field() {
+        let upload = read_form(
+            "--X-BOUNDARY
+Content-Disposition: form-data; name=\"note\"
+
+skipped
+--X-BOUNDARY
+Content-Disposition: form-data; name=\"file\"; filename=\"notes.txt\"
+Content-Type: text/plain; charset=utf-8
+
+hello
+--X-BOUNDARY--
+",
+        )
+        .await
+        .unwrap_or_else(|_| panic!("upload refused"));
+        assert_eq!(upload.filename, "notes.txt");
+        assert_eq!(upload.data, "hello");
+        let content_type = upload.content_type.unwrap();
+        assert_eq!(content_type.essence_str(), "text/plain");
+        assert_eq!(content_type.get_param(mime::CHARSET).unwrap(), "utf-8");
+    }
+
+    #[actix_web::test]
+    async fn refuses_bodies_that_are_not_forms() {
+        assert_eq!(status(read("text/plain", "hello").await), 400);
+        assert_eq!(status(read("multipart/mixed; boundary=X", "").await), 400);
+        assert_eq!(status(read("multipart/form-data", "").await), 400);
+    }
+
+    #[actix_web::test]
+    async fn refuses_truncated_and_malformed_forms() {
+        let truncated = "--X-BOUNDARY
+Content-Disposition: form-data; name=\"file\"; filename=\"notes.txt\"
+
+hel";
+        assert_eq!(status(read_form(truncated).await), 400);
+        let cut_in_headers = "--X-BOUNDARY
+Content-Disposition: form-da";
+        assert_eq!(status(read_form(cut_in_headers).await), 400);
+        let no_disposition = "--X-BOUNDARY
+Content-Type: text/plain
+
+hello
+--X-BOUNDARY--
+";
+        assert_eq!(status(read_form(no_disposition).await), 400);
+        let wrong_boundary = "--OTHER
+Content-Disposition: form-data; name=\"file\"; filename=\"notes.txt\"
+
+hello
+--OTHER--
+";
+        assert_eq!(status(read_form(wrong_boundary).await), 400);
+    }
+
+    #[actix_web::test]
+    async fn needs_a_file_field() {
+        let no_file = "--X-BOUNDARY
+Content-Disposition: form-data; name=\"file\"
+
+not a file
+--X-BOUNDARY--
+";
+        assert_eq!(status(read_form(no_file).await), 400);
+        assert_eq!(status(read_form("--X-BOUNDARY--\n").await), 400);
+    }
+
+    #[actix_web::test]
+    async fn refuses_files_over_the_limit() {
+        let large = format!(
+            "--X-BOUNDARY
+Content-Disposition: form-data; name=\"file\"; filename=\"notes.txt\"
+
+{}
+--X-BOUNDARY--
+",
+            "x".repeat(17)
+        );
+        assert_eq!(status(read_form(&large).await), 413);
+        let exact = large.replace(&"x".repeat(17), &"x".repeat(16));
+        assert_eq!(status(read_form(&exact).await), 200);
+    }
+}
@@ -0,0 +1,151 @@
+use actix_web::{http::StatusCode, web, HttpResponse, Responder};
+
+use crate::auth::{self, AuthUser};
+use crate::models::{
+    new_id, LoginRequest, RegisterRequest, Session, SessionResponse, User, UserProfile,
+};
+use crate::store::StoreError;
+use crate::AppState;
+
+use super::server_error;
+
+const MIN_PASSWORD_LEN: usize = 8;
+
+/// Usernames are 3-32 characters of lowercase letters, digits, `_`, `.` or
+/// `-`, so they can be mentioned as `@username` in plain text.
+fn normalize_username(username: &str) -> Option<String> {
+    let username = username.trim().to_lowercase();
+    let valid = (3..=32).contains(&username.len())
+        && username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+    valid.then_some(username)
+}
+
+fn invalid_credentials() -> HttpResponse {
+    HttpResponse::Unauthorized().json(serde_json::json!({
+        "error": "Invalid username or password"
+    }))
+}
+
+/// Issues a new bearer token for the user.
+async fn start_session(data: &AppState, user: &User, status: StatusCode) -> HttpResponse {
+    let token = auth::generate_token();
+    let now = chrono::Utc::now();
+    let session = Session {
+        token_hash: auth::hash_token(&token),
+        user_id: user.user_id.clone(),
+        created_at: now,
+        expires_at: now + data.session_ttl,
+    };
+
+    match data.store.create_session(session).await {
+        Ok(session) => HttpResponse::build(status).json(SessionResponse {
+            token,
+            expires_at: session.expires_at,
+            user: UserProfile::from(user),
+        }),
+        Err(e) => server_error("Error creating session", "Failed to sign in", e),
+    }
+}
+
+pub async fn register(
+    data: web::Data<AppState>,
+    register_data: web::Json<RegisterRequest>,
+) -> impl Responder {
+    let register_data = register_data.into_inner();
+    let Some(username) = normalize_username(&register_data.username) else {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Username must be 3-32 characters of letters, digits, '_', '.' or '-'"
+        }));
+    };
+    if register_data.password.chars().count() < MIN_PASSWORD_LEN {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Password must be at least {} characters", MIN_PASSWORD_LEN)
+        }));
+    }
+
+    let password = register_data.password;
+    let hashed = web::block(move || auth::hash_password(&password))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result.map_err(|e| e.to_string()));
+    let password_hash = match hashed {
+        Ok(hash) => hash,
+        Err(e) => {
+            eprintln!("Error hashing password: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to register user"
+            }));
+        }
+    };
+
+    let new_user = User {
+        user_id: new_id("user"),
+        username,
+        password_hash,
+        created_at: chrono::Utc::now(),
+    };
+
+    match data.store.create_user(new_user).await {
+        Ok(user) => start_session(&data, &user, StatusCode::CREATED).await,
+        Err(StoreError::Conflict(_)) => HttpResponse::Conflict().json(serde_json::json!({
+            "error": "Username is already taken"
+        })),
+        Err(e) => server_error("Error creating user", "Failed to register user", e),
+    }
+}
+
+pub async fn login(
+    data: web::Data<AppState>,
+    login_data: web::Json<LoginRequest>,
+) -> impl Responder {
+    let login_data = login_data.into_inner();
+    let Some(username) = normalize_username(&login_data.username) else {
+        return invalid_credentials();
+    };
+
+    let user = match data.store.get_user_by_username(&username).await {
+        Ok(user) => user,
+        Err(e) => return server_error("Error fetching user", "Failed to sign in", e),
+    };
+
+    let password = login_data.password;
+    let password_hash = user.as_ref().map(|user| user.password_hash.clone());
+    let verified = web::block(move || match password_hash {
+        Some(password_hash) => auth::verify_password(&password, &password_hash),
+        None => {
+            auth::verify_password(&password, auth::dummy_password_hash());
+            false
+        }
+    });
+    match (verified.await, user) {
+        (Ok(true), Some(user)) => start_session(&data, &user, StatusCode::OK).await,
+        (Ok(_), _) => invalid_credentials(),
+        (Err(e), _) => {
+            eprintln!("Error verifying password: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to sign in"
+            }))
+        }
+    }
+}
+
+pub async fn logout(data: web::Data<AppState>, user: AuthUser) -> impl Responder {
+    if !user.is_session() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "API tokens are revoked through /api/tokens"
+        }));
+    }
+
+    match data.store.delete_session(&user.token_hash).await {
+        Ok(_) => HttpResponse::Ok().json(serde_json::json!({
+            "message": "Signed out successfully"
+        })),
+        Err(e) => server_error("Error deleting session", "Failed to sign out", e),
+    }
+}
+
+pub async fn me(user: AuthUser) -> impl Responder {
+    HttpResponse::Ok().json(UserProfile::from(&user.user))
+}
@@ -0,0 +1,302 @@
+use actix_web::{web, HttpResponse, Responder};
+use std::collections::{HashMap, HashSet};
+
+use crate::auth::AuthUser;
+use crate::models::{
+    BlockerRequest, BlockersResponse, Permission, Task, TaskEventKind, TaskUpdate, DEFAULT_BOARD_ID,
+};
+use crate::AppState;
+
+use super::tasks::{
+    record, task_busy, task_not_found, task_response, update_with_retry, MAX_ATTEMPTS,
+};
+use super::{require_access, server_error};
+
+fn blocker_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": "The task is not blocked by that task"
+    }))
+}
+
+/// Whether `blocker` already waits, directly or through other tasks, on
+/// `task_id`, so that blocking `task_id` by it would close a cycle.
+fn creates_cycle(tasks: &[Task], task_id: &str, blocker: &str) -> bool {
+    let blocked_by: HashMap<&str, &[String]> = tasks
+        .iter()
+        .map(|t| (t.task_id.as_str(), t.blocked_by.as_slice()))
+        .collect();
+
+    let mut seen = HashSet::new();
+    let mut pending = vec![blocker];
+    while let Some(current) = pending.pop() {
+        if current == task_id {
+            return true;
+        }
+        if seen.insert(current) {
+            if let Some(next) = blocked_by.get(current) {
+                pending.extend(next.iter().map(String::as_str));
+            }
+        }
+    }
+    false
+}
+
+/// The blockers of `task` that already wait, directly or through other
+/// tasks, on it. A task coming back from the trash must not keep these.
+pub(super) fn closing_blockers(tasks: &[Task], task: &Task) -> Vec<String> {
+    task.blocked_by
+        .iter()
+        .filter(|blocker| creates_cycle(tasks, &task.task_id, blocker))
+        .cloned()
+        .collect()
+}
+
+/// The tasks blocking this one and the tasks it blocks. Blockers that were
+/// deleted are left out.
+async fn get_blockers(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_id: &str,
+) -> HttpResponse {
+    if let Err(response) = require_access(data, user, board_id, Permission::ViewBoard).await {
+        return response;
+    }
+
+    let tasks = match data.store.list_tasks(board_id).await {
+        Ok(tasks) => tasks,
+        Err(e) => return server_error("Error fetching tasks", "Failed to fetch blockers", e),
+    };
+    let Some(task) = tasks.iter().find(|t| t.task_id == task_id) else {
+        return task_not_found();
+    };
+
+    HttpResponse::Ok().json(BlockersResponse {
+        blocked_by: tasks
+            .iter()
+            .filter(|t| task.blocked_by.contains(&t.task_id))
+            .cloned()
+            .collect(),
+        blocks: tasks
+            .iter()
+            .filter(|t| t.blocked_by.iter().any(|id| id == task_id))
+            .cloned()
+            .collect(),
+    })
+}
+
+fn cycle(blocker: &str) -> HttpResponse {
+    HttpResponse::Conflict().json(serde_json::json!({
+        "error": format!("Task '{}' already waits on this task", blocker)
+    }))
+}
+
+fn concurrent_cycle() -> HttpResponse {
+    HttpResponse::Conflict().json(serde_json::json!({
+        "error": "Another blocker added at the same time would close a cycle with this one"
+    }))
+}
+
+/// The update taking `blocker` off the task, or `None` if it is not there.
+fn without_blocker(task: &Task, blocker: &str) -> Option<TaskUpdate> {
+    task.blocked_by
+        .iter()
+        .any(|id| id == blocker)
+        .then(|| TaskUpdate {
+            blocked_by: Some(
+                task.blocked_by
+                    .iter()
+                    .filter(|id| *id != blocker)
+                    .cloned()
+                    .collect(),
+            ),
+            ..Default::default()
+        })
+}
+
+/// Blocks the task by another one on the board. Like [`update_with_retry`],
+/// the save is conditional on the task's version, but every attempt checks
+/// for a cycle against a fresh copy of the whole board.
+async fn add_blocker(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_id: &str,
+    blocker_data: BlockerRequest,
+) -> HttpResponse {
+    if let Err(response) = require_access(data, user, board_id, Permission::EditTasks).await {
+        return response;
+    }
+    let blocker = blocker_data.task_id;
+    if blocker == task_id {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "A task cannot block itself"
+        }));
+    }
+
+    let mut saved = None;
+    for _ in 0..MAX_ATTEMPTS {
+        let tasks = match data.store.list_tasks(board_id).await {
+            Ok(tasks) => tasks,
+            Err(e) => return server_error("Error fetching tasks", "Failed to add blocker", e),
+        };
+        let Some(task) = tasks.iter().find(|t| t.task_id == task_id) else {
+            return task_not_found();
+        };
+        if !tasks.iter().any(|t| t.task_id == blocker) {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("Task '{}' does not exist on this board", blocker)
+            }));
+        }
+        if task.blocked_by.contains(&blocker) {
+            return task_response(HttpResponse::Ok(), task);
+        }
+        if creates_cycle(&tasks, task_id, &blocker) {
+            return cycle(&blocker);
+        }
+
+        let mut blocked_by = task.blocked_by.clone();
+        blocked_by.push(blocker.clone());
+        let update = TaskUpdate {
+            blocked_by: Some(blocked_by),
+            ..Default::default()
+        };
+        match data
+            .store
+            .update_task(board_id, task_id, &update, Some(task.version))
+            .await
+        {
+            Ok(Some(updated)) => {
+                record(
+                    data,
+                    user,
+                    TaskEventKind::Updated,
+                    Some(task),
+                    Some(&updated),
+                )
+                .await;
+                saved = Some(updated);
+                break;
+            }
+            Ok(None) => continue,
+            Err(e) => return server_error("Error updating task", "Failed to add blocker", e),
+        }
+    }
+    let Some(task) = saved else {
+        return task_busy();
+    };
+
+    // The version only guards this task, so a blocker added to another task
+    // at the same time can still have closed a cycle. Look again, and take
+    // the new blocker back off if it did.
+    match data.store.list_tasks(board_id).await {
+        Ok(tasks) if creates_cycle(&tasks, task_id, &blocker) => {
+            let undone = update_with_retry(
+                data,
+                user,
+                board_id,
+                task_id,
+                "Failed to add blocker",
+                concurrent_cycle,
+                |task| without_blocker(task, &blocker),
+            )
+            .await;
+            match undone {
+                Ok(_) => concurrent_cycle(),
+                Err(response) => response,
+            }
+        }
+        Ok(_) => task_response(HttpResponse::Created(), &task),
+        Err(e) => server_error("Error fetching tasks", "Failed to add blocker", e),
+    }
+}
+
+async fn remove_blocker(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_id: &str,
+    blocker: &str,
+) -> HttpResponse {
+    if let Err(response) = require_access(data, user, board_id, Permission::EditTasks).await {
+        return response;
+    }
+
+    let result = update_with_retry(
+        data,
+        user,
+        board_id,
+        task_id,
+        "Failed to remove blocker",
+        blocker_not_found,
+        |task| without_blocker(task, blocker),
+    )
+    .await;
+    match result {
+        Ok(task) => task_response(HttpResponse::Ok(), &task),
+        Err(response) => response,
+    }
+}
+
+pub async fn get_board_blockers(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (board_id, task_id) = path.into_inner();
+    get_blockers(&data, &user, &board_id, &task_id).await
+}
+
+pub async fn add_board_blocker(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+    blocker_data: web::Json<BlockerRequest>,
+) -> impl Responder {
+    let (board_id, task_id) = path.into_inner();
+    add_blocker(&data, &user, &board_id, &task_id, blocker_data.into_inner()).await
+}
+
+pub async fn remove_board_blocker(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String, String)>,
+) -> impl Responder {
+    let (board_id, task_id, blocker) = path.into_inner();
+    remove_blocker(&data, &user, &board_id, &task_id, &blocker).await
+}
+
+// The routes below predate boards and act on the default board.
+
+pub async fn get_default_blockers(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    task_id: web::Path<String>,
+) -> impl Responder {
+    get_blockers(&data, &user, DEFAULT_BOARD_ID, &task_id).await
+}
+
+pub async fn add_default_blocker(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    task_id: web::Path<String>,
+    blocker_data: web::Json<BlockerRequest>,
+) -> impl Responder {
+    add_blocker(
+        &data,
+        &user,
+        DEFAULT_BOARD_ID,
+        &task_id,
+        blocker_data.into_inner(),
+    )
+    .await
+}
+
+pub async fn remove_default_blocker(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (task_id, blocker) = path.into_inner();
+    remove_blocker(&data, &user, DEFAULT_BOARD_ID, &task_id, &blocker).await
+}
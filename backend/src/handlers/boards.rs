@@ -0,0 +1,177 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use crate::auth::AuthUser;
+use crate::blobs;
+use crate::models::{
+    default_columns, new_id, Board, BoardUpdate, Column, CreateBoardRequest, Member, Permission,
+    Role, UpdateBoardRequest, DEFAULT_BOARD_ID,
+};
+use crate::AppState;
+
+use super::require_access;
+
+pub async fn get_boards(data: web::Data<AppState>, user: AuthUser) -> impl Responder {
+    match data.store.list_boards().await {
+        Ok(boards) => {
+            let visible: Vec<Board> = boards
+                .into_iter()
+                .filter(|board| board.role_of(&user.user.user_id).is_some())
+                .collect();
+            HttpResponse::Ok().json(visible)
+        }
+        Err(e) => {
+            eprintln!("Error fetching boards: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch boards"
+            }))
+        }
+    }
+}
+
+pub async fn get_board(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    board_id: web::Path<String>,
+) -> impl Responder {
+    match require_access(&data, &user, &board_id, Permission::ViewBoard).await {
+        Ok(board) => HttpResponse::Ok().json(board),
+        Err(response) => response,
+    }
+}
+
+pub async fn create_board(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    board_data: web::Json<CreateBoardRequest>,
+) -> impl Responder {
+    let board_data = board_data.into_inner();
+    let name = board_data.name.trim();
+    if name.is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Board name must not be empty"
+        }));
+    }
+
+    let columns = match board_data.columns {
+        None => default_columns(),
+        Some(names) => {
+            let names: Vec<&str> = names.iter().map(|name| name.trim()).collect();
+            if names.is_empty() || names.iter().any(|name| name.is_empty()) {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": "A board needs at least one column and column names must not be empty"
+                }));
+            }
+            names
+                .into_iter()
+                .map(|name| Column {
+                    column_id: new_id("col"),
+                    name: name.to_string(),
+                    wip_limit: None,
+                    wip_limit_per_assignee: None,
+                    done: false,
+                })
+                .collect()
+        }
+    };
+
+    let mut new_board = Board {
+        board_id: new_id("board"),
+        name: name.to_string(),
+        description: board_data.description.unwrap_or_default(),
+        created_at: chrono::Utc::now(),
+        columns,
+        members: vec![Member {
+            user_id: user.user.user_id.clone(),
+            role: Role::Owner,
+        }],
+        labels: Vec::new(),
+        version: 1,
+    };
+    new_board.pin_done_column();
+
+    match data.store.create_board(new_board).await {
+        Ok(board) => HttpResponse::Created().json(board),
+        Err(e) => {
+            eprintln!("Error creating board: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to create board"
+            }))
+        }
+    }
+}
+
+pub async fn update_board(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    board_id: web::Path<String>,
+    board_data: web::Json<UpdateBoardRequest>,
+) -> impl Responder {
+    if let Err(response) = require_access(&data, &user, &board_id, Permission::ManageBoard).await {
+        return response;
+    }
+
+    let board_data = board_data.into_inner();
+    let update = BoardUpdate {
+        name: board_data.name.map(|name| name.trim().to_string()),
+        description: board_data.description,
+        ..Default::default()
+    };
+
+    if update.is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "No fields to update"
+        }));
+    }
+    if update.name.as_deref() == Some("") {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Board name must not be empty"
+        }));
+    }
+
+    match data.store.update_board(&board_id, &update, None).await {
+        Ok(Some(board)) => HttpResponse::Ok().json(board),
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Board not found"
+        })),
+        Err(e) => {
+            eprintln!("Error updating board: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to update board"
+            }))
+        }
+    }
+}
+
+pub async fn delete_board(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    board_id: web::Path<String>,
+) -> impl Responder {
+    if board_id.as_str() == DEFAULT_BOARD_ID {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "The default board cannot be deleted"
+        }));
+    }
+    if let Err(response) = require_access(&data, &user, &board_id, Permission::ManageBoard).await {
+        return response;
+    }
+
+    match data.store.delete_board(&board_id).await {
+        Ok(Some(deleted_tasks)) => {
+            blobs::delete_attachments(data.blobs.as_ref(), &deleted_tasks).await;
+            HttpResponse::Ok().json(serde_json::json!({
+                "message": "Board deleted successfully",
+                "deletedTasks": deleted_tasks.len()
+            }))
+        }
+        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Board not found"
+        })),
+        Err(e) => {
+            eprintln!("Error deleting board: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to delete board"
+            }))
+        }
+    }
+}
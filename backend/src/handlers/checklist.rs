@@ -0,0 +1,241 @@
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse, Responder};
+
+use crate::auth::AuthUser;
+use crate::models::{
+    new_id, ChecklistItem, CreateChecklistItemRequest, Permission, TaskUpdate,
+    UpdateChecklistItemRequest, DEFAULT_BOARD_ID,
+};
+use crate::AppState;
+
+use super::require_access;
+use super::tasks::{task_response, update_with_retry};
+
+fn item_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": "Checklist item not found"
+    }))
+}
+
+pub(super) fn empty_text() -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "error": "Checklist item text must not be empty"
+    }))
+}
+
+/// Applies `change` to the task's checklist and saves the whole list;
+/// `change` returns `false` when the item it targets does not exist.
+/// Concurrent checklist edits are retried rather than lost; see
+/// [`update_with_retry`].
+async fn change_checklist<F>(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_id: &str,
+    status: StatusCode,
+    change: F,
+) -> HttpResponse
+where
+    F: Fn(&mut Vec<ChecklistItem>) -> bool,
+{
+    if let Err(response) = require_access(data, user, board_id, Permission::EditTasks).await {
+        return response;
+    }
+
+    let result = update_with_retry(
+        data,
+        user,
+        board_id,
+        task_id,
+        "Failed to update checklist",
+        item_not_found,
+        |task| {
+            let mut checklist = task.checklist.clone();
+            change(&mut checklist).then(|| TaskUpdate {
+                checklist: Some(checklist),
+                ..Default::default()
+            })
+        },
+    )
+    .await;
+    match result {
+        Ok(task) => task_response(HttpResponse::build(status), &task),
+        Err(response) => response,
+    }
+}
+
+async fn add_item(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_id: &str,
+    item_data: CreateChecklistItemRequest,
+) -> HttpResponse {
+    let text = item_data.text.trim().to_string();
+    if text.is_empty() {
+        return empty_text();
+    }
+
+    let item_id = new_id("item");
+    change_checklist(
+        data,
+        user,
+        board_id,
+        task_id,
+        StatusCode::CREATED,
+        |checklist| {
+            let position = item_data
+                .position
+                .unwrap_or(checklist.len())
+                .min(checklist.len());
+            checklist.insert(
+                position,
+                ChecklistItem {
+                    item_id: item_id.clone(),
+                    text: text.clone(),
+                    done: false,
+                },
+            );
+            true
+        },
+    )
+    .await
+}
+
+/// Edits the text, toggles `done` or moves the item to another position.
+async fn update_item(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_id: &str,
+    item_id: &str,
+    item_data: UpdateChecklistItemRequest,
+) -> HttpResponse {
+    if item_data.text.is_none() && item_data.done.is_none() && item_data.position.is_none() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "No fields to update"
+        }));
+    }
+    let text = item_data.text.as_deref().map(str::trim);
+    if text == Some("") {
+        return empty_text();
+    }
+
+    change_checklist(data, user, board_id, task_id, StatusCode::OK, |checklist| {
+        let Some(index) = checklist.iter().position(|i| i.item_id == item_id) else {
+            return false;
+        };
+        if let Some(text) = text {
+            checklist[index].text = text.to_string();
+        }
+        if let Some(done) = item_data.done {
+            checklist[index].done = done;
+        }
+        if let Some(position) = item_data.position {
+            let item = checklist.remove(index);
+            let position = position.min(checklist.len());
+            checklist.insert(position, item);
+        }
+        true
+    })
+    .await
+}
+
+async fn delete_item(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_id: &str,
+    item_id: &str,
+) -> HttpResponse {
+    change_checklist(data, user, board_id, task_id, StatusCode::OK, |checklist| {
+        let Some(index) = checklist.iter().position(|i| i.item_id == item_id) else {
+            return false;
+        };
+        checklist.remove(index);
+        true
+    })
+    .await
+}
+
+pub async fn add_board_item(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+    item_data: web::Json<CreateChecklistItemRequest>,
+) -> impl Responder {
+    let (board_id, task_id) = path.into_inner();
+    add_item(&data, &user, &board_id, &task_id, item_data.into_inner()).await
+}
+
+pub async fn update_board_item(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String, String)>,
+    item_data: web::Json<UpdateChecklistItemRequest>,
+) -> impl Responder {
+    let (board_id, task_id, item_id) = path.into_inner();
+    update_item(
+        &data,
+        &user,
+        &board_id,
+        &task_id,
+        &item_id,
+        item_data.into_inner(),
+    )
+    .await
+}
+
+pub async fn delete_board_item(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String, String)>,
+) -> impl Responder {
+    let (board_id, task_id, item_id) = path.into_inner();
+    delete_item(&data, &user, &board_id, &task_id, &item_id).await
+}
+
+// The routes below predate boards and act on the default board.
+
+pub async fn add_default_item(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    task_id: web::Path<String>,
+    item_data: web::Json<CreateChecklistItemRequest>,
+) -> impl Responder {
+    add_item(
+        &data,
+        &user,
+        DEFAULT_BOARD_ID,
+        &task_id,
+        item_data.into_inner(),
+    )
+    .await
+}
+
+pub async fn update_default_item(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+    item_data: web::Json<UpdateChecklistItemRequest>,
+) -> impl Responder {
+    let (task_id, item_id) = path.into_inner();
+    update_item(
+        &data,
+        &user,
+        DEFAULT_BOARD_ID,
+        &task_id,
+        &item_id,
+        item_data.into_inner(),
+    )
+    .await
+}
+
+pub async fn delete_default_item(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (task_id, item_id) = path.into_inner();
+    delete_item(&data, &user, DEFAULT_BOARD_ID, &task_id, &item_id).await
+}
@@ -0,0 +1,303 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use crate::auth::AuthUser;
+use crate::models::{
+    new_id, Column, CreateColumnRequest, DeleteColumnQuery, Permission, TaskEventKind,
+    UpdateColumnRequest,
+};
+use crate::AppState;
+
+use super::tasks::record;
+use super::{require_access, update_board_with_retry};
+
+/// WIP limits must let at least one task in; no limit is written as `null`.
+pub(super) fn valid_limits(limits: &[Option<u32>]) -> bool {
+    !limits.contains(&Some(0))
+}
+
+pub(super) fn invalid_limit() -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "error": "WIP limits must be at least 1"
+    }))
+}
+
+fn column_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": "Column not found"
+    }))
+}
+
+fn empty_name() -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "error": "Column name must not be empty"
+    }))
+}
+
+fn last_column() -> HttpResponse {
+    HttpResponse::Conflict().json(serde_json::json!({
+        "error": "A board must keep at least one column"
+    }))
+}
+
+fn done_column_required() -> HttpResponse {
+    HttpResponse::Conflict().json(serde_json::json!({
+        "error": "A board must keep a done column; mark another column as done first"
+    }))
+}
+
+fn unknown_target(target: &str) -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "error": format!("Column '{}' does not exist on this board", target)
+    }))
+}
+
+pub async fn get_columns(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    board_id: web::Path<String>,
+) -> impl Responder {
+    match require_access(&data, &user, &board_id, Permission::ViewBoard).await {
+        Ok(board) => HttpResponse::Ok().json(board.columns),
+        Err(response) => response,
+    }
+}
+
+pub async fn create_column(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    board_id: web::Path<String>,
+    column_data: web::Json<CreateColumnRequest>,
+) -> impl Responder {
+    let column = Column {
+        column_id: new_id("col"),
+        name: column_data.name.trim().to_string(),
+        wip_limit: column_data.wip_limit,
+        wip_limit_per_assignee: column_data.wip_limit_per_assignee,
+        done: column_data.done,
+    };
+
+    let result = update_board_with_retry(
+        &data,
+        &user,
+        &board_id,
+        Permission::ManageBoard,
+        "Failed to update columns",
+        |board| {
+            if column.name.is_empty() {
+                return Some(empty_name());
+            }
+            if !valid_limits(&[column.wip_limit, column.wip_limit_per_assignee]) {
+                return Some(invalid_limit());
+            }
+            board.pin_done_column();
+            if column.done {
+                board.set_done_column(&column.column_id);
+            }
+            let columns = &mut board.columns;
+            let position = column_data
+                .position
+                .unwrap_or(columns.len())
+                .min(columns.len());
+            columns.insert(position, column.clone());
+            None
+        },
+    )
+    .await;
+
+    match result {
+        Ok(_) => HttpResponse::Created().json(column),
+        Err(response) => response,
+    }
+}
+
+pub async fn update_column(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+    column_data: web::Json<UpdateColumnRequest>,
+) -> impl Responder {
+    let (board_id, column_id) = path.into_inner();
+
+    let result = update_board_with_retry(
+        &data,
+        &user,
+        &board_id,
+        Permission::ManageBoard,
+        "Failed to update columns",
+        |board| {
+            if column_data.name.is_none()
+                && column_data.position.is_none()
+                && column_data.wip_limit.is_none()
+                && column_data.wip_limit_per_assignee.is_none()
+                && column_data.done.is_none()
+            {
+                return Some(HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": "No fields to update"
+                })));
+            }
+            if !valid_limits(&[
+                column_data.wip_limit.flatten(),
+                column_data.wip_limit_per_assignee.flatten(),
+            ]) {
+                return Some(invalid_limit());
+            }
+
+            board.pin_done_column();
+            let Some(index) = board.columns.iter().position(|c| c.column_id == column_id) else {
+                return Some(column_not_found());
+            };
+            match column_data.done {
+                Some(true) => board.set_done_column(&column_id),
+                Some(false) if board.columns[index].done => return Some(done_column_required()),
+                _ => {}
+            }
+
+            let columns = &mut board.columns;
+
+            if let Some(name) = &column_data.name {
+                let name = name.trim();
+                if name.is_empty() {
+                    return Some(empty_name());
+                }
+                columns[index].name = name.to_string();
+            }
+            if let Some(wip_limit) = column_data.wip_limit {
+                columns[index].wip_limit = wip_limit;
+            }
+            if let Some(wip_limit) = column_data.wip_limit_per_assignee {
+                columns[index].wip_limit_per_assignee = wip_limit;
+            }
+
+            if let Some(position) = column_data.position {
+                let column = columns.remove(index);
+                let position = position.min(columns.len());
+                columns.insert(position, column);
+            }
+            None
+        },
+    )
+    .await;
+
+    match result {
+        Ok(board) => HttpResponse::Ok().json(board.columns),
+        Err(response) => response,
+    }
+}
+
+pub async fn delete_column(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+    query: web::Query<DeleteColumnQuery>,
+) -> impl Responder {
+    let (board_id, column_id) = path.into_inner();
+    let board = match require_access(&data, &user, &board_id, Permission::ManageBoard).await {
+        Ok(board) => board,
+        Err(response) => return response,
+    };
+
+    if board.column(&column_id).is_none() {
+        return column_not_found();
+    }
+    if board.columns.len() == 1 {
+        return last_column();
+    }
+    if board
+        .done_column()
+        .is_some_and(|done| done.column_id == column_id)
+    {
+        return done_column_required();
+    }
+
+    let task_count = match data.store.list_tasks(&board_id).await {
+        Ok(tasks) => tasks.iter().filter(|t| t.column == column_id).count(),
+        Err(e) => {
+            eprintln!("Error fetching tasks: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to delete column"
+            }));
+        }
+    };
+
+    let mut moved_tasks = 0;
+    match &query.move_to {
+        Some(target) if *target == column_id => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "moveTo must name a different column"
+            }));
+        }
+        Some(target) if board.column(target).is_none() => return unknown_target(target),
+        Some(target) => {
+            moved_tasks = match data
+                .store
+                .move_column_tasks(&board_id, &column_id, target)
+                .await
+            {
+                Ok(moved) => {
+                    for (before, after) in &moved {
+                        record(
+                            &data,
+                            &user,
+                            TaskEventKind::Moved,
+                            Some(before),
+                            Some(after),
+                        )
+                        .await;
+                    }
+                    moved.len()
+                }
+                Err(e) => {
+                    eprintln!("Error moving tasks: {}", e);
+                    return HttpResponse::InternalServerError().json(serde_json::json!({
+                        "error": "Failed to move the column's tasks"
+                    }));
+                }
+            };
+        }
+        None if task_count > 0 => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Column still has tasks; pass moveTo with the column that should receive them",
+                "taskCount": task_count
+            }));
+        }
+        None => {}
+    }
+
+    // The board may have changed while the tasks were moved; the checks are
+    // repeated against the copy the column is removed from.
+    let result = update_board_with_retry(
+        &data,
+        &user,
+        &board_id,
+        Permission::ManageBoard,
+        "Failed to update columns",
+        |board| {
+            board.pin_done_column();
+            let Some(index) = board.columns.iter().position(|c| c.column_id == column_id) else {
+                return Some(column_not_found());
+            };
+            if board.columns.len() == 1 {
+                return Some(last_column());
+            }
+            if board.columns[index].done {
+                return Some(done_column_required());
+            }
+            if let Some(target) = query.move_to.as_deref() {
+                if board.column(target).is_none() {
+                    return Some(unknown_target(target));
+                }
+            }
+            board.columns.remove(index);
+            None
+        },
+    )
+    .await;
+
+    match result {
+        Ok(_) => HttpResponse::Ok().json(serde_json::json!({
+            "message": "Column deleted successfully",
+            "movedTasks": moved_tasks
+        })),
+        Err(response) => response,
+    }
+}
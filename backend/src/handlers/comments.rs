@@ -0,0 +1,385 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use crate::auth::AuthUser;
+use crate::models::{
+    new_id, Board, Comment, CommentQuery, CommentRequest, CommentUpdate, CommentsResponse, Mention,
+    Permission, DEFAULT_BOARD_ID,
+};
+use crate::store::StoreResult;
+use crate::AppState;
+
+use super::tasks::task_not_found;
+use super::{require_access, server_error};
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 200;
+const MAX_BODY_LEN: usize = 10_000;
+
+fn comment_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": "Comment not found"
+    }))
+}
+
+fn not_author() -> HttpResponse {
+    HttpResponse::Forbidden().json(serde_json::json!({
+        "error": "Only the author can change a comment"
+    }))
+}
+
+/// Trims the body, or explains why it cannot be used.
+fn comment_body(body: &str) -> Result<String, String> {
+    let body = body.trim();
+    if body.is_empty() {
+        return Err("Comment body must not be empty".to_string());
+    }
+    if body.chars().count() > MAX_BODY_LEN {
+        return Err(format!(
+            "Comment body must be at most {} characters",
+            MAX_BODY_LEN
+        ));
+    }
+    Ok(body.to_string())
+}
+
+/// Usernames written as `@username` in `body`, lowercased and in order of
+/// first appearance. An `@` inside a word, as in an email address, does not
+/// start a mention, and a trailing `.` is taken as punctuation.
+fn mentioned_usernames(body: &str) -> Vec<String> {
+    let is_username_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-');
+
+    let mut usernames: Vec<String> = Vec::new();
+    for (at, _) in body.match_indices('@') {
+        if body[..at].chars().next_back().is_some_and(is_username_char) {
+            continue;
+        }
+        let rest = &body[at + 1..];
+        let end = rest.find(|c| !is_username_char(c)).unwrap_or(rest.len());
+        let username = rest[..end].trim_end_matches('.').to_lowercase();
+        if (3..=32).contains(&username.len()) && !usernames.contains(&username) {
+            usernames.push(username);
+        }
+    }
+    usernames
+}
+
+/// Resolves the mentions in `body` to users who can see the board. Names
+/// that match nobody, or someone outside the board, stay plain text.
+async fn resolve_mentions(data: &AppState, board: &Board, body: &str) -> StoreResult<Vec<Mention>> {
+    let mut mentions = Vec::new();
+    for username in mentioned_usernames(body) {
+        if let Some(user) = data.store.get_user_by_username(&username).await? {
+            if board.role_of(&user.user_id).is_some() {
+                mentions.push(Mention {
+                    user_id: user.user_id,
+                    username: user.username,
+                });
+            }
+        }
+    }
+    Ok(mentions)
+}
+
+/// Checks that the task exists outside the trash.
+async fn require_task(
+    data: &AppState,
+    board_id: &str,
+    task_id: &str,
+    message: &str,
+) -> Result<(), HttpResponse> {
+    match data.store.get_task(board_id, task_id).await {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(task_not_found()),
+        Err(e) => Err(server_error("Error fetching task", message, e)),
+    }
+}
+
+/// Loads a comment the user wrote.
+async fn require_own_comment(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_id: &str,
+    comment_id: &str,
+    message: &str,
+) -> Result<Comment, HttpResponse> {
+    match data.store.get_comment(board_id, task_id, comment_id).await {
+        Ok(Some(comment)) if comment.author_id == user.user.user_id => Ok(comment),
+        Ok(Some(_)) => Err(not_author()),
+        Ok(None) => Err(comment_not_found()),
+        Err(e) => Err(server_error("Error fetching comment", message, e)),
+    }
+}
+
+async fn list_comments(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_id: &str,
+    query: CommentQuery,
+) -> HttpResponse {
+    if let Err(response) = require_access(data, user, board_id, Permission::ViewBoard).await {
+        return response;
+    }
+    if let Err(response) = require_task(data, board_id, task_id, "Failed to fetch comments").await {
+        return response;
+    }
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    match data
+        .store
+        .list_comments(board_id, task_id, query.after.as_deref(), limit)
+        .await
+    {
+        Ok(comments) => {
+            let next_cursor = if comments.len() == limit {
+                comments.last().map(|c| c.comment_id.clone())
+            } else {
+                None
+            };
+            HttpResponse::Ok().json(CommentsResponse {
+                comments,
+                next_cursor,
+            })
+        }
+        Err(e) => server_error("Error fetching comments", "Failed to fetch comments", e),
+    }
+}
+
+async fn create_comment(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_id: &str,
+    comment_data: CommentRequest,
+) -> HttpResponse {
+    let board = match require_access(data, user, board_id, Permission::Comment).await {
+        Ok(board) => board,
+        Err(response) => return response,
+    };
+    if let Err(response) = require_task(data, board_id, task_id, "Failed to create comment").await {
+        return response;
+    }
+    let body = match comment_body(&comment_data.body) {
+        Ok(body) => body,
+        Err(error) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({ "error": error }))
+        }
+    };
+    let mentions = match resolve_mentions(data, &board, &body).await {
+        Ok(mentions) => mentions,
+        Err(e) => return server_error("Error resolving mentions", "Failed to create comment", e),
+    };
+
+    let comment = Comment {
+        comment_id: new_id("comment"),
+        board_id: board_id.to_string(),
+        task_id: task_id.to_string(),
+        author_id: user.user.user_id.clone(),
+        body,
+        mentions,
+        created_at: chrono::Utc::now(),
+        edited_at: None,
+    };
+    match data.store.create_comment(comment).await {
+        Ok(comment) => HttpResponse::Created().json(comment),
+        Err(e) => server_error("Error creating comment", "Failed to create comment", e),
+    }
+}
+
+async fn update_comment(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_id: &str,
+    comment_id: &str,
+    comment_data: CommentRequest,
+) -> HttpResponse {
+    let board = match require_access(data, user, board_id, Permission::Comment).await {
+        Ok(board) => board,
+        Err(response) => return response,
+    };
+    if let Err(response) = require_task(data, board_id, task_id, "Failed to update comment").await {
+        return response;
+    }
+    if let Err(response) = require_own_comment(
+        data,
+        user,
+        board_id,
+        task_id,
+        comment_id,
+        "Failed to update comment",
+    )
+    .await
+    {
+        return response;
+    }
+    let body = match comment_body(&comment_data.body) {
+        Ok(body) => body,
+        Err(error) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({ "error": error }))
+        }
+    };
+    let mentions = match resolve_mentions(data, &board, &body).await {
+        Ok(mentions) => mentions,
+        Err(e) => return server_error("Error resolving mentions", "Failed to update comment", e),
+    };
+
+    let update = CommentUpdate {
+        body,
+        mentions,
+        edited_at: chrono::Utc::now(),
+    };
+    match data
+        .store
+        .update_comment(board_id, task_id, comment_id, &update)
+        .await
+    {
+        Ok(Some(comment)) => HttpResponse::Ok().json(comment),
+        Ok(None) => comment_not_found(),
+        Err(e) => server_error("Error updating comment", "Failed to update comment", e),
+    }
+}
+
+async fn delete_comment(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_id: &str,
+    comment_id: &str,
+) -> HttpResponse {
+    if let Err(response) = require_access(data, user, board_id, Permission::Comment).await {
+        return response;
+    }
+    if let Err(response) = require_task(data, board_id, task_id, "Failed to delete comment").await {
+        return response;
+    }
+    if let Err(response) = require_own_comment(
+        data,
+        user,
+        board_id,
+        task_id,
+        comment_id,
+        "Failed to delete comment",
+    )
+    .await
+    {
+        return response;
+    }
+
+    match data
+        .store
+        .delete_comment(board_id, task_id, comment_id)
+        .await
+    {
+        Ok(true) => HttpResponse::Ok().json(serde_json::json!({
+            "message": "Comment deleted successfully"
+        })),
+        Ok(false) => comment_not_found(),
+        Err(e) => server_error("Error deleting comment", "Failed to delete comment", e),
+    }
+}
+
+pub async fn get_board_comments(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+    query: web::Query<CommentQuery>,
+) -> impl Responder {
+    let (board_id, task_id) = path.into_inner();
+    list_comments(&data, &user, &board_id, &task_id, query.into_inner()).await
+}
+
+pub async fn create_board_comment(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+    comment_data: web::Json<CommentRequest>,
+) -> impl Responder {
+    let (board_id, task_id) = path.into_inner();
+    create_comment(&data, &user, &board_id, &task_id, comment_data.into_inner()).await
+}
+
+pub async fn update_board_comment(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String, String)>,
+    comment_data: web::Json<CommentRequest>,
+) -> impl Responder {
+    let (board_id, task_id, comment_id) = path.into_inner();
+    update_comment(
+        &data,
+        &user,
+        &board_id,
+        &task_id,
+        &comment_id,
+        comment_data.into_inner(),
+    )
+    .await
+}
+
+pub async fn delete_board_comment(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String, String)>,
+) -> impl Responder {
+    let (board_id, task_id, comment_id) = path.into_inner();
+    delete_comment(&data, &user, &board_id, &task_id, &comment_id).await
+}
+
+// The routes below predate boards and act on the default board.
+
+pub async fn get_default_comments(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    task_id: web::Path<String>,
+    query: web::Query<CommentQuery>,
+) -> impl Responder {
+    list_comments(&data, &user, DEFAULT_BOARD_ID, &task_id, query.into_inner()).await
+}
+
+pub async fn create_default_comment(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    task_id: web::Path<String>,
+    comment_data: web::Json<CommentRequest>,
+) -> impl Responder {
+    create_comment(
+        &data,
+        &user,
+        DEFAULT_BOARD_ID,
+        &task_id,
+        comment_data.into_inner(),
+    )
+    .await
+}
+
+pub async fn update_default_comment(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+    comment_data: web::Json<CommentRequest>,
+) -> impl Responder {
+    let (task_id, comment_id) = path.into_inner();
+    update_comment(
+        &data,
+        &user,
+        DEFAULT_BOARD_ID,
+        &task_id,
+        &comment_id,
+        comment_data.into_inner(),
+    )
+    .await
+}
+
+pub async fn delete_default_comment(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (task_id, comment_id) = path.into_inner();
+    delete_comment(&data, &user, DEFAULT_BOARD_ID, &task_id, &comment_id).await
+}
@@ -0,0 +1,202 @@
+use actix_web::{http::header, web, HttpRequest, HttpResponse, Responder};
+use futures::channel::mpsc;
+use futures::SinkExt;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::auth::AuthUser;
+use crate::models::{EventStreamQuery, Permission, TaskEvent};
+use crate::AppState;
+
+use super::{require_access, ACCESS_RECHECK};
+
+/// Events read from the log per query while a client catches up.
+const REPLAY_BATCH: usize = 500;
+/// Comment lines keep proxies from closing an idle stream and let us notice
+/// clients that went away.
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+type Frames = mpsc::Sender<Result<web::Bytes, actix_web::Error>>;
+
+/// Error raised when the client has disconnected.
+struct Disconnected;
+
+/// One client's event stream: which events it may see and how far it got.
+struct Feed {
+    data: web::Data<AppState>,
+    user_id: String,
+    board_id: Option<String>,
+    /// Whether the user may see each board, forgotten every
+    /// [`ACCESS_RECHECK`].
+    visible_boards: HashMap<String, bool>,
+    last_id: Option<u64>,
+    frames: Frames,
+}
+
+impl Feed {
+    async fn can_see(&mut self, event: &TaskEvent) -> bool {
+        if self
+            .board_id
+            .as_ref()
+            .is_some_and(|board_id| *board_id != event.board_id)
+        {
+            return false;
+        }
+        if let Some(&visible) = self.visible_boards.get(&event.board_id) {
+            return visible;
+        }
+
+        let Some(visible) = self.look_up(&event.board_id).await else {
+            return false;
+        };
+        self.visible_boards.insert(event.board_id.clone(), visible);
+        visible
+    }
+
+    /// Whether the user may see the board, or `None` if that could not be
+    /// found out.
+    async fn look_up(&self, board_id: &str) -> Option<bool> {
+        match self.data.store.get_board(board_id).await {
+            Ok(board) => Some(board.is_some_and(|board| board.role_of(&self.user_id).is_some())),
+            Err(e) => {
+                eprintln!("Error fetching board: {}", e);
+                None
+            }
+        }
+    }
+
+    async fn send(&mut self, event: TaskEvent) -> Result<(), Disconnected> {
+        let already_sent = self.last_id.is_some_and(|last_id| event.id <= last_id);
+        if already_sent || !self.can_see(&event).await {
+            self.last_id = self.last_id.max(Some(event.id));
+            return Ok(());
+        }
+        self.last_id = Some(event.id);
+
+        let data = match serde_json::to_string(&event) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error serializing task event: {}", e);
+                return Ok(());
+            }
+        };
+        let frame = format!(
+            "id: {}\nevent: {}\ndata: {}\n\n",
+            event.id,
+            event.kind.as_str(),
+            data
+        );
+        self.write(frame).await
+    }
+
+    async fn write(&mut self, frame: String) -> Result<(), Disconnected> {
+        self.frames
+            .send(Ok(web::Bytes::from(frame)))
+            .await
+            .map_err(|_| Disconnected)
+    }
+
+    /// Sends everything in the log after the last event the client saw.
+    async fn replay(&mut self) -> Result<(), Disconnected> {
+        let Some(mut after) = self.last_id else {
+            return Ok(());
+        };
+        loop {
+            let batch = match self.data.store.list_events_after(after, REPLAY_BATCH).await {
+                Ok(batch) => batch,
+                Err(e) => {
+                    eprintln!("Error reading the event log: {}", e);
+                    return Ok(());
+                }
+            };
+            let done = batch.len() < REPLAY_BATCH;
+            for event in batch {
+                after = event.id;
+                self.send(event).await?;
+            }
+            if done {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn run(mut self, mut live: broadcast::Receiver<TaskEvent>) -> Result<(), Disconnected> {
+        self.replay().await?;
+
+        let mut keep_alive = tokio::time::interval(KEEP_ALIVE);
+        let first_recheck = tokio::time::Instant::now() + ACCESS_RECHECK;
+        let mut access_recheck = tokio::time::interval_at(first_recheck, ACCESS_RECHECK);
+        loop {
+            tokio::select! {
+                event = live.recv() => match event {
+                    // Events that could not be logged have no id to resume from.
+                    Ok(event) if event.id == 0 => {}
+                    Ok(event) => {
+                        // Concurrent writers can publish out of order; fill
+                        // any gap from the log first.
+                        if self.last_id.is_some_and(|last_id| event.id > last_id + 1) {
+                            self.replay().await?;
+                        }
+                        self.send(event).await?;
+                    }
+                    Err(RecvError::Lagged(_)) => self.replay().await?,
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+                _ = keep_alive.tick() => self.write(": keep-alive\n\n".to_string()).await?,
+                _ = access_recheck.tick() => {
+                    self.visible_boards.clear();
+                    // A stream of one board ends once the user loses it.
+                    if let Some(board_id) = self.board_id.clone() {
+                        if self.look_up(&board_id).await == Some(false) {
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Parses the cursor a reconnecting `EventSource` sends.
+fn last_event_id(req: &HttpRequest) -> Option<u64> {
+    req.headers()
+        .get("Last-Event-ID")?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+pub async fn stream_events(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    user: AuthUser,
+    query: web::Query<EventStreamQuery>,
+) -> impl Responder {
+    let query = query.into_inner();
+    if let Some(board_id) = &query.board_id {
+        if let Err(response) = require_access(&data, &user, board_id, Permission::ViewBoard).await {
+            return response;
+        }
+    }
+
+    // Subscribe before replaying so nothing published in between is lost.
+    let live = data.events.subscribe();
+    let (frames, body) = mpsc::channel(32);
+    let feed = Feed {
+        data: data.clone(),
+        user_id: user.user.user_id,
+        board_id: query.board_id,
+        visible_boards: HashMap::new(),
+        last_id: last_event_id(&req).or(query.last_event_id),
+        frames,
+    };
+    actix_web::rt::spawn(feed.run(live));
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        .streaming(body)
+}
@@ -0,0 +1,104 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use crate::auth::AuthUser;
+use crate::models::{EventFilter, HistoryQuery, HistoryResponse, Permission, DEFAULT_BOARD_ID};
+use crate::AppState;
+
+use super::{require_access, server_error};
+
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 1000;
+
+/// Answers with one page of the board's history. Deleted tasks keep their
+/// history, so `task_id` does not have to exist any more.
+async fn history(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_id: Option<&str>,
+    query: HistoryQuery,
+) -> HttpResponse {
+    if let Err(response) = require_access(data, user, board_id, Permission::ViewBoard).await {
+        return response;
+    }
+
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from >= to {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "from must be before to"
+            }));
+        }
+    }
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let filter = EventFilter {
+        board_id: board_id.to_string(),
+        task_id: task_id.map(str::to_string),
+        from: query.from,
+        to: query.to,
+        after: query.after,
+        limit,
+    };
+    match data.store.find_events(&filter).await {
+        Ok(events) => {
+            let next_cursor = if events.len() == limit {
+                events.last().map(|event| event.id)
+            } else {
+                None
+            };
+            HttpResponse::Ok().json(HistoryResponse {
+                events,
+                next_cursor,
+            })
+        }
+        Err(e) => server_error("Error fetching history", "Failed to fetch history", e),
+    }
+}
+
+pub async fn get_board_task_history(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+    query: web::Query<HistoryQuery>,
+) -> impl Responder {
+    let (board_id, task_id) = path.into_inner();
+    history(&data, &user, &board_id, Some(&task_id), query.into_inner()).await
+}
+
+pub async fn get_board_audit(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    board_id: web::Path<String>,
+    query: web::Query<HistoryQuery>,
+) -> impl Responder {
+    history(&data, &user, &board_id, None, query.into_inner()).await
+}
+
+// Counterparts of the legacy `/api/tasks` routes on the default board.
+
+pub async fn get_default_task_history(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    task_id: web::Path<String>,
+    query: web::Query<HistoryQuery>,
+) -> impl Responder {
+    history(
+        &data,
+        &user,
+        DEFAULT_BOARD_ID,
+        Some(&task_id),
+        query.into_inner(),
+    )
+    .await
+}
+
+pub async fn get_default_audit(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    query: web::Query<HistoryQuery>,
+) -> impl Responder {
+    history(&data, &user, DEFAULT_BOARD_ID, None, query.into_inner()).await
+}
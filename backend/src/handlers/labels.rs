@@ -0,0 +1,215 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use crate::auth::AuthUser;
+use crate::models::{
+    new_id, CreateLabelRequest, Label, Permission, TaskEventKind, UpdateLabelRequest,
+};
+use crate::AppState;
+
+use super::tasks::record;
+use super::{require_access, server_error, update_board_with_retry};
+
+fn label_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": "Label not found"
+    }))
+}
+
+/// Accepts `#rrggbb` hex colors and returns them in lowercase.
+pub(super) fn normalize_color(color: &str) -> Option<String> {
+    let hex = color.trim().strip_prefix('#')?;
+    let valid = hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit());
+    valid.then(|| format!("#{}", hex.to_ascii_lowercase()))
+}
+
+pub(super) fn invalid_color() -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "error": "Label color must be a hex color such as #d73a4a"
+    }))
+}
+
+fn empty_name() -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "error": "Label name must not be empty"
+    }))
+}
+
+/// Label names are unique per board, ignoring case.
+fn name_taken(labels: &[Label], name: &str, except: Option<&str>) -> bool {
+    labels
+        .iter()
+        .any(|l| l.name.eq_ignore_ascii_case(name) && Some(l.label_id.as_str()) != except)
+}
+
+fn duplicate_name(name: &str) -> HttpResponse {
+    HttpResponse::Conflict().json(serde_json::json!({
+        "error": format!("A label named '{}' already exists on this board", name)
+    }))
+}
+
+pub async fn get_labels(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    board_id: web::Path<String>,
+) -> impl Responder {
+    match require_access(&data, &user, &board_id, Permission::ViewBoard).await {
+        Ok(board) => HttpResponse::Ok().json(board.labels),
+        Err(response) => response,
+    }
+}
+
+pub async fn create_label(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    board_id: web::Path<String>,
+    label_data: web::Json<CreateLabelRequest>,
+) -> impl Responder {
+    let name = label_data.name.trim();
+    let color = normalize_color(&label_data.color);
+    let label_id = new_id("label");
+
+    let result = update_board_with_retry(
+        &data,
+        &user,
+        &board_id,
+        Permission::ManageBoard,
+        "Failed to update labels",
+        |board| {
+            if name.is_empty() {
+                return Some(empty_name());
+            }
+            let Some(color) = color.clone() else {
+                return Some(invalid_color());
+            };
+            if name_taken(&board.labels, name, None) {
+                return Some(duplicate_name(name));
+            }
+            board.labels.push(Label {
+                label_id: label_id.clone(),
+                name: name.to_string(),
+                color,
+            });
+            None
+        },
+    )
+    .await;
+
+    match result {
+        Ok(board) => match board.labels.into_iter().find(|l| l.label_id == label_id) {
+            Some(label) => HttpResponse::Created().json(label),
+            None => label_not_found(),
+        },
+        Err(response) => response,
+    }
+}
+
+pub async fn update_label(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+    label_data: web::Json<UpdateLabelRequest>,
+) -> impl Responder {
+    let (board_id, label_id) = path.into_inner();
+
+    let result = update_board_with_retry(
+        &data,
+        &user,
+        &board_id,
+        Permission::ManageBoard,
+        "Failed to update labels",
+        |board| {
+            if label_data.name.is_none() && label_data.color.is_none() {
+                return Some(HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": "No fields to update"
+                })));
+            }
+
+            let Some(index) = board.labels.iter().position(|l| l.label_id == label_id) else {
+                return Some(label_not_found());
+            };
+
+            if let Some(name) = &label_data.name {
+                let name = name.trim();
+                if name.is_empty() {
+                    return Some(empty_name());
+                }
+                if name_taken(&board.labels, name, Some(&label_id)) {
+                    return Some(duplicate_name(name));
+                }
+                board.labels[index].name = name.to_string();
+            }
+            if let Some(color) = &label_data.color {
+                let Some(color) = normalize_color(color) else {
+                    return Some(invalid_color());
+                };
+                board.labels[index].color = color;
+            }
+            None
+        },
+    )
+    .await;
+
+    match result {
+        Ok(board) => match board.labels.into_iter().find(|l| l.label_id == label_id) {
+            Some(label) => HttpResponse::Ok().json(label),
+            None => label_not_found(),
+        },
+        Err(response) => response,
+    }
+}
+
+/// Deletes the label and takes it off every task that carried it.
+pub async fn delete_label(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (board_id, label_id) = path.into_inner();
+
+    // Drop the label from the catalogue first, so edits that read the board
+    // from here on refuse it. An edit that checked its labels against an
+    // earlier copy of the board can still save the id after the sweep
+    // below, leaving the task with an id that names no label.
+    let result = update_board_with_retry(
+        &data,
+        &user,
+        &board_id,
+        Permission::ManageBoard,
+        "Failed to delete label",
+        |board| {
+            let Some(index) = board.labels.iter().position(|l| l.label_id == label_id) else {
+                return Some(label_not_found());
+            };
+            board.labels.remove(index);
+            None
+        },
+    )
+    .await;
+    if let Err(response) = result {
+        return response;
+    }
+
+    match data.store.remove_label(&board_id, &label_id).await {
+        Ok(removed) => {
+            for (before, after) in &removed {
+                record(
+                    &data,
+                    &user,
+                    TaskEventKind::Updated,
+                    Some(before),
+                    Some(after),
+                )
+                .await;
+            }
+            HttpResponse::Ok().json(serde_json::json!({
+                "message": "Label deleted successfully",
+                "updatedTasks": removed.len()
+            }))
+        }
+        Err(e) => server_error(
+            "Error removing label from tasks",
+            "Failed to delete label",
+            e,
+        ),
+    }
+}
@@ -0,0 +1,203 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use crate::auth::AuthUser;
+use crate::models::{
+    AddMemberRequest, Member, MemberResponse, Permission, Role, UpdateMemberRequest,
+};
+use crate::AppState;
+
+use super::{require_access, server_error, update_board_with_retry};
+
+fn member_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": "Member not found"
+    }))
+}
+
+fn has_owner(members: &[Member]) -> bool {
+    members.iter().any(|m| m.role == Role::Owner)
+}
+
+fn last_owner() -> HttpResponse {
+    HttpResponse::Conflict().json(serde_json::json!({
+        "error": "A board must keep at least one owner"
+    }))
+}
+
+pub async fn get_members(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    board_id: web::Path<String>,
+) -> impl Responder {
+    let board = match require_access(&data, &user, &board_id, Permission::ViewBoard).await {
+        Ok(board) => board,
+        Err(response) => return response,
+    };
+
+    let mut members = Vec::with_capacity(board.members.len());
+    for member in board.members {
+        match data.store.get_user(&member.user_id).await {
+            Ok(Some(account)) => members.push(MemberResponse {
+                user_id: member.user_id,
+                username: account.username,
+                role: member.role,
+            }),
+            Ok(None) => {}
+            Err(e) => return server_error("Error fetching user", "Failed to fetch members", e),
+        }
+    }
+    HttpResponse::Ok().json(members)
+}
+
+pub async fn add_member(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    board_id: web::Path<String>,
+    member_data: web::Json<AddMemberRequest>,
+) -> impl Responder {
+    if let Err(response) = require_access(&data, &user, &board_id, Permission::ManageMembers).await
+    {
+        return response;
+    }
+
+    let username = member_data.username.trim().to_lowercase();
+    let account = match data.store.get_user_by_username(&username).await {
+        Ok(Some(account)) => account,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "error": format!("User '{}' not found", username)
+            }))
+        }
+        Err(e) => return server_error("Error fetching user", "Failed to add member", e),
+    };
+
+    let result = update_board_with_retry(
+        &data,
+        &user,
+        &board_id,
+        Permission::ManageMembers,
+        "Failed to update members",
+        |board| {
+            if board.members.iter().any(|m| m.user_id == account.user_id) {
+                return Some(HttpResponse::Conflict().json(serde_json::json!({
+                    "error": format!("'{}' is already a member of this board", account.username)
+                })));
+            }
+            // Inviting someone to a shared board makes it members-only; the
+            // board admin doing so stays on as its owner.
+            if board.members.is_empty() && account.user_id != user.user.user_id {
+                board.members.push(Member {
+                    user_id: user.user.user_id.clone(),
+                    role: Role::Owner,
+                });
+            }
+            board.members.push(Member {
+                user_id: account.user_id.clone(),
+                role: member_data.role,
+            });
+            if !has_owner(&board.members) {
+                return Some(last_owner());
+            }
+            None
+        },
+    )
+    .await;
+
+    match result {
+        Ok(_) => HttpResponse::Created().json(MemberResponse {
+            user_id: account.user_id,
+            username: account.username,
+            role: member_data.role,
+        }),
+        Err(response) => response,
+    }
+}
+
+pub async fn update_member(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+    member_data: web::Json<UpdateMemberRequest>,
+) -> impl Responder {
+    let (board_id, member_id) = path.into_inner();
+    if let Err(response) = require_access(&data, &user, &board_id, Permission::ManageMembers).await
+    {
+        return response;
+    }
+
+    let account = match data.store.get_user(&member_id).await {
+        Ok(Some(account)) => account,
+        Ok(None) => return member_not_found(),
+        Err(e) => return server_error("Error fetching user", "Failed to update member", e),
+    };
+
+    // The owner check runs on the same copy of the board that is saved, so
+    // two demotions at once cannot both pass it.
+    let result = update_board_with_retry(
+        &data,
+        &user,
+        &board_id,
+        Permission::ManageMembers,
+        "Failed to update members",
+        |board| {
+            let Some(member) = board.members.iter_mut().find(|m| m.user_id == member_id) else {
+                return Some(member_not_found());
+            };
+            member.role = member_data.role;
+            if !has_owner(&board.members) {
+                return Some(last_owner());
+            }
+            None
+        },
+    )
+    .await;
+
+    match result {
+        Ok(_) => HttpResponse::Ok().json(MemberResponse {
+            user_id: member_id,
+            username: account.username,
+            role: member_data.role,
+        }),
+        Err(response) => response,
+    }
+}
+
+/// Owners may remove anyone; every member may remove themselves.
+pub async fn remove_member(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (board_id, member_id) = path.into_inner();
+    let permission = if member_id == user.user.user_id {
+        Permission::ViewBoard
+    } else {
+        Permission::ManageMembers
+    };
+
+    let result = update_board_with_retry(
+        &data,
+        &user,
+        &board_id,
+        permission,
+        "Failed to update members",
+        |board| {
+            let Some(index) = board.members.iter().position(|m| m.user_id == member_id) else {
+                return Some(member_not_found());
+            };
+            board.members.remove(index);
+            if !has_owner(&board.members) {
+                return Some(last_owner());
+            }
+            None
+        },
+    )
+    .await;
+
+    match result {
+        Ok(_) => HttpResponse::Ok().json(serde_json::json!({
+            "message": "Member removed successfully"
+        })),
+        Err(response) => response,
+    }
+}
@@ -0,0 +1,146 @@
+use actix_web::HttpResponse;
+use std::time::Duration;
+
+use crate::auth::AuthUser;
+use crate::models::{Board, BoardUpdate, Permission, Role};
+use crate::store::StoreError;
+use crate::AppState;
+
+pub mod attachments;
+pub mod auth;
+pub mod blockers;
+pub mod boards;
+pub mod checklist;
+pub mod columns;
+pub mod comments;
+pub mod feed;
+pub mod history;
+pub mod labels;
+pub mod members;
+pub mod tasks;
+pub mod templates;
+#[cfg(test)]
+mod tests;
+pub mod tokens;
+pub mod trash;
+pub mod ws;
+
+/// Membership changes are not published as events, so open WebSocket and
+/// event streams look up the user's access again this often.
+const ACCESS_RECHECK: Duration = Duration::from_secs(30);
+
+/// Logs a storage failure and answers with a generic 500.
+fn server_error(context: &str, message: &str, e: StoreError) -> HttpResponse {
+    eprintln!("{}: {}", context, e);
+    HttpResponse::InternalServerError().json(serde_json::json!({
+        "error": message
+    }))
+}
+
+/// Loads a board or produces the response to return when it cannot be used.
+async fn require_board(data: &AppState, board_id: &str) -> Result<Board, HttpResponse> {
+    match data.store.get_board(board_id).await {
+        Ok(Some(board)) => Ok(board),
+        Ok(None) => Err(board_not_found()),
+        Err(e) => {
+            eprintln!("Error fetching board: {}", e);
+            Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch board"
+            })))
+        }
+    }
+}
+
+fn board_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": "Board not found"
+    }))
+}
+
+fn forbidden(role: Role, permission: Permission) -> HttpResponse {
+    HttpResponse::Forbidden().json(serde_json::json!({
+        "error": format!(
+            "The {} role does not have the '{}' permission on this board",
+            role.as_str(),
+            permission.as_str()
+        ),
+        "missingPermission": permission,
+        "role": role
+    }))
+}
+
+/// The user's role on the board. Shared boards have no members and grant
+/// everyone the editor role, except the configured board admins, who own
+/// them.
+fn role_on(data: &AppState, board: &Board, user: &AuthUser) -> Option<Role> {
+    if board.members.is_empty() && data.board_admins.contains(&user.user.username) {
+        return Some(Role::Owner);
+    }
+    board.role_of(&user.user.user_id)
+}
+
+/// Loads a board the user may act on with `permission`. Boards the user is
+/// not a member of are reported as missing rather than forbidden.
+async fn require_access(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    permission: Permission,
+) -> Result<Board, HttpResponse> {
+    let board = require_board(data, board_id).await?;
+    match role_on(data, &board, user) {
+        None => Err(board_not_found()),
+        Some(role) if !role.allows(permission) => Err(forbidden(role, permission)),
+        Some(_) => Ok(board),
+    }
+}
+
+/// Attempts at saving a board change before giving up on a board that
+/// keeps changing underneath us.
+const MAX_BOARD_ATTEMPTS: usize = 5;
+
+/// Lets `change` edit a copy of the current board, saves the copy's
+/// columns, labels and members and returns the saved board. Each save is
+/// conditional on the version that was read, and access and `change` are
+/// re-run on a fresh copy if someone else saved the board in between, so
+/// every check `change` makes holds for the board it is applied to.
+/// `change` returns the response to refuse the change with, if any.
+async fn update_board_with_retry<F>(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    permission: Permission,
+    message: &str,
+    change: F,
+) -> Result<Board, HttpResponse>
+where
+    F: Fn(&mut Board) -> Option<HttpResponse>,
+{
+    for _ in 0..MAX_BOARD_ATTEMPTS {
+        let mut board = require_access(data, user, board_id, permission).await?;
+        let version = board.version;
+        if let Some(response) = change(&mut board) {
+            return Err(response);
+        }
+        let update = BoardUpdate {
+            columns: Some(board.columns),
+            labels: Some(board.labels),
+            members: Some(board.members),
+            ..Default::default()
+        };
+
+        match data
+            .store
+            .update_board(board_id, &update, Some(version))
+            .await
+        {
+            Ok(Some(updated)) => return Ok(updated),
+            Ok(None) => continue,
+            Err(e) => return Err(server_error("Error updating board", message, e)),
+        }
+    }
+
+    Err(HttpResponse::Conflict().json(serde_json::json!({
+        "error": "The board is being changed by someone else; try again"
+    })))
+}
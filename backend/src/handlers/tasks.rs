@@ -0,0 +1,1223 @@
+use actix_web::http::header::{ETag, EntityTag, Header, IfMatch};
+use actix_web::{web, HttpRequest, HttpResponse, HttpResponseBuilder, Responder};
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use crate::auth::AuthUser;
+use crate::markdown;
+use crate::models::{
+    new_id, Blocking, Board, BoardTasks, ChecklistItem, ColumnTasks, CreateTaskRequest,
+    MoveTaskRequest, Permission, SortOrder, Task, TaskCard, TaskEvent, TaskEventKind, TaskFilter,
+    TaskQuery, TaskSort, TaskUpdate, TasksResponse, UpdateTaskRequest, WipExceeded,
+    DEFAULT_BOARD_ID, MAX_ESTIMATE,
+};
+use crate::rank;
+use crate::store::StoreResult;
+use crate::AppState;
+
+use super::checklist::empty_text;
+use super::{require_access, server_error};
+
+/// Tasks of one column in display order, leaving out `exclude`.
+pub(super) async fn column_tasks(
+    data: &AppState,
+    board_id: &str,
+    column: &str,
+    exclude: Option<&str>,
+) -> StoreResult<Vec<Task>> {
+    let mut tasks: Vec<Task> = data
+        .store
+        .list_tasks(board_id)
+        .await?
+        .into_iter()
+        .filter(|t| t.column == column && Some(t.task_id.as_str()) != exclude)
+        .collect();
+    tasks.sort_by(|a, b| a.rank.cmp(&b.rank));
+    Ok(tasks)
+}
+
+/// Appends the change to the event log and pushes it to connected clients.
+/// The change itself has already been saved, so a failure to log it is only
+/// reported.
+pub(super) async fn record(
+    data: &AppState,
+    user: &AuthUser,
+    kind: TaskEventKind,
+    before: Option<&Task>,
+    after: Option<&Task>,
+) {
+    record_event(
+        data,
+        TaskEvent::new(kind, before, after, &user.user.user_id),
+    )
+    .await;
+}
+
+/// Like [`record`], for events that carry more than the change itself.
+async fn record_event(data: &AppState, event: TaskEvent) {
+    let event = match data.store.append_event(event.clone()).await {
+        Ok(event) => event,
+        Err(e) => {
+            eprintln!("Error recording task event: {}", e);
+            event
+        }
+    };
+    data.events.publish(event);
+}
+
+/// Picks the rank for a task inserted at `position` in `tasks`, which must
+/// be one column in display order. When the neighbours leave no room, the
+/// column is renumbered first; otherwise no other task is touched.
+pub(super) async fn rank_at(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    tasks: &mut [Task],
+    position: usize,
+) -> StoreResult<String> {
+    let candidate = |tasks: &[Task]| {
+        let lo = position
+            .checked_sub(1)
+            .map_or("", |i| tasks[i].rank.as_str());
+        let hi = tasks.get(position).map(|t| t.rank.as_str());
+        if hi.is_some_and(|hi| lo >= hi) {
+            return None;
+        }
+        Some(rank::between(lo, hi)).filter(|rank| rank.len() <= rank::MAX_LEN)
+    };
+
+    if let Some(rank) = candidate(tasks) {
+        return Ok(rank);
+    }
+
+    let ranks = rank::spread(tasks.len());
+    for (task, rank) in tasks.iter_mut().zip(ranks) {
+        if task.rank != rank {
+            let update = TaskUpdate {
+                rank: Some(rank.clone()),
+                ..Default::default()
+            };
+            if let Some(updated) = data
+                .store
+                .update_task(board_id, &task.task_id, &update, None)
+                .await?
+            {
+                record(data, user, TaskEventKind::Moved, Some(task), Some(&updated)).await;
+            }
+            task.rank = rank;
+        }
+    }
+    Ok(candidate(tasks).expect("renumbered ranks leave room between neighbours"))
+}
+
+/// Attempts at saving a change before giving up on a task that keeps
+/// changing underneath us.
+pub(super) const MAX_ATTEMPTS: usize = 5;
+
+/// Saves the update `change` derives from the current task and returns the
+/// result. Each save is conditional on the version that was read, and
+/// `change` is re-run on a fresh copy if someone else saved the task in
+/// between, so concurrent edits to different parts of a task are never lost.
+/// `change` returns `None` when the part of the task it targets does not
+/// exist, which is answered with `missing()`.
+pub(super) async fn update_with_retry<F>(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_id: &str,
+    message: &str,
+    missing: fn() -> HttpResponse,
+    change: F,
+) -> Result<Task, HttpResponse>
+where
+    F: Fn(&Task) -> Option<TaskUpdate>,
+{
+    for _ in 0..MAX_ATTEMPTS {
+        let task = match data.store.get_task(board_id, task_id).await {
+            Ok(Some(task)) => task,
+            Ok(None) => return Err(task_not_found()),
+            Err(e) => return Err(server_error("Error fetching task", message, e)),
+        };
+        let Some(update) = change(&task) else {
+            return Err(missing());
+        };
+
+        match data
+            .store
+            .update_task(board_id, task_id, &update, Some(task.version))
+            .await
+        {
+            Ok(Some(updated)) => {
+                record(
+                    data,
+                    user,
+                    TaskEventKind::Updated,
+                    Some(&task),
+                    Some(&updated),
+                )
+                .await;
+                return Ok(updated);
+            }
+            Ok(None) => continue,
+            Err(e) => return Err(server_error("Error updating task", message, e)),
+        }
+    }
+
+    Err(task_busy())
+}
+
+/// Answers a change that lost [`MAX_ATTEMPTS`] races in a row.
+pub(super) fn task_busy() -> HttpResponse {
+    HttpResponse::Conflict().json(serde_json::json!({
+        "error": "The task is being changed by someone else; try again"
+    }))
+}
+
+pub(super) fn task_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": "Task not found"
+    }))
+}
+
+/// A task must start before it is due.
+fn valid_schedule(start_at: Option<DateTime<Utc>>, due_at: Option<DateTime<Utc>>) -> bool {
+    !matches!((start_at, due_at), (Some(start), Some(due)) if start >= due)
+}
+
+/// Longest accepted title, in characters.
+const MAX_TITLE_LEN: usize = 200;
+/// Longest accepted description, in characters.
+const MAX_DESCRIPTION_LEN: usize = 20_000;
+
+/// Trims the title and checks its length.
+pub(super) fn task_title(title: &str) -> Result<String, String> {
+    let title = title.trim();
+    if title.is_empty() {
+        return Err("Task title must not be empty".to_string());
+    }
+    if title.chars().count() > MAX_TITLE_LEN {
+        return Err(format!(
+            "Task title must be at most {} characters",
+            MAX_TITLE_LEN
+        ));
+    }
+    Ok(title.to_string())
+}
+
+pub(super) fn valid_description(description: &str) -> bool {
+    description.chars().count() <= MAX_DESCRIPTION_LEN
+}
+
+pub(super) fn bad_request(message: &str) -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "error": message
+    }))
+}
+
+pub(super) fn invalid_description() -> HttpResponse {
+    bad_request(&format!(
+        "Task description must be at most {} characters",
+        MAX_DESCRIPTION_LEN
+    ))
+}
+
+/// Estimates are story points between 0 and [`MAX_ESTIMATE`].
+pub(super) fn valid_estimate(estimate: Option<f64>) -> bool {
+    !matches!(estimate, Some(e) if !(0.0..=MAX_ESTIMATE).contains(&e))
+}
+
+/// Checks that every id names a label of the board and drops duplicates.
+/// The first unknown id is returned as the error.
+pub(super) fn board_labels(board: &Board, labels: Vec<String>) -> Result<Vec<String>, String> {
+    let mut checked: Vec<String> = Vec::with_capacity(labels.len());
+    for label in labels {
+        if board.label(&label).is_none() {
+            return Err(label);
+        }
+        if !checked.contains(&label) {
+            checked.push(label);
+        }
+    }
+    Ok(checked)
+}
+
+/// Trims the new task's title and checklist texts and returns the response
+/// to refuse the task with, if any of its fields is invalid. Templates run
+/// the tasks they hold through this when they are saved.
+pub(super) fn invalid_new_task(task_data: &mut CreateTaskRequest) -> Option<HttpResponse> {
+    if !valid_schedule(task_data.start_at, task_data.due_at) {
+        return Some(invalid_schedule());
+    }
+    if !valid_estimate(task_data.estimate) {
+        return Some(invalid_estimate());
+    }
+    match task_title(&task_data.title) {
+        Ok(title) => task_data.title = title,
+        Err(message) => return Some(bad_request(&message)),
+    }
+    if !valid_description(&task_data.description) {
+        return Some(invalid_description());
+    }
+    for text in &mut task_data.checklist {
+        *text = text.trim().to_string();
+        if text.is_empty() {
+            return Some(empty_text());
+        }
+    }
+    None
+}
+
+/// Returns the first user id that does not belong to a member of the
+/// board. Shared boards have no member list, so any existing user qualifies.
+async fn first_non_member(
+    data: &AppState,
+    board: &Board,
+    user_ids: &[String],
+) -> StoreResult<Option<String>> {
+    for user_id in user_ids {
+        let member = if board.members.is_empty() {
+            data.store.get_user(user_id).await?.is_some()
+        } else {
+            board.role_of(user_id).is_some()
+        };
+        if !member {
+            return Ok(Some(user_id.clone()));
+        }
+    }
+    Ok(None)
+}
+
+pub(super) fn unknown_label(label: &str) -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "error": format!("Label '{}' does not exist on this board", label)
+    }))
+}
+
+fn invalid_schedule() -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "error": "startAt must be before dueAt"
+    }))
+}
+
+pub(super) fn invalid_estimate() -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "error": format!("estimate must be between 0 and {}", MAX_ESTIMATE)
+    }))
+}
+
+/// Refuses to move a task into the done column while any of its blockers
+/// is still open, unless the caller chose to `ignore` them.
+async fn check_blockers(
+    data: &AppState,
+    board: &Board,
+    task: &Task,
+    column: &str,
+    ignore: bool,
+) -> Result<(), HttpResponse> {
+    let finishing = board
+        .done_column()
+        .is_some_and(|done| done.column_id == column)
+        && task.column != column;
+    if ignore || !finishing || task.blocked_by.is_empty() {
+        return Ok(());
+    }
+
+    let tasks = match data.store.list_tasks(&board.board_id).await {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            return Err(server_error(
+                "Error fetching tasks",
+                "Failed to move task",
+                e,
+            ))
+        }
+    };
+    let open = Blocking::new(board, &tasks).open_blockers(task);
+    if open.is_empty() {
+        return Ok(());
+    }
+    Err(HttpResponse::Conflict().json(serde_json::json!({
+        "error": "The task is blocked by tasks that are not finished yet",
+        "openBlockers": open
+    })))
+}
+
+/// Checks the WIP limits of `column_id` for the task ending up there with
+/// `assignees`. A task already in the column only counts against the limits
+/// of assignees it did not have yet. Going past a limit is refused unless
+/// `override_wip` is set, in which case the limit is returned so the
+/// override can be recorded. The count is taken from the column as it is
+/// now, so two different tasks entering it at the same moment can both pass.
+pub(super) async fn check_wip(
+    data: &AppState,
+    board: &Board,
+    task: &Task,
+    column_id: &str,
+    assignees: &[String],
+    override_wip: bool,
+) -> Result<Option<WipExceeded>, HttpResponse> {
+    let Some(column) = board.column(column_id) else {
+        return Ok(None);
+    };
+    let entering = task.column != column_id;
+    let new_assignees: Vec<&String> = assignees
+        .iter()
+        .filter(|a| entering || !task.assignees.contains(a))
+        .collect();
+    let column_limit = column.wip_limit.filter(|_| entering);
+    let assignee_limit = column
+        .wip_limit_per_assignee
+        .filter(|_| !new_assignees.is_empty());
+    if column_limit.is_none() && assignee_limit.is_none() {
+        return Ok(None);
+    }
+
+    let tasks = match column_tasks(data, &board.board_id, column_id, Some(&task.task_id)).await {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            return Err(server_error(
+                "Error fetching tasks",
+                "Failed to move task",
+                e,
+            ))
+        }
+    };
+    let mut exceeded = column_limit
+        .filter(|limit| tasks.len() >= *limit as usize)
+        .map(|limit| WipExceeded {
+            column_id: column_id.to_string(),
+            assignee: None,
+            limit,
+            count: tasks.len(),
+        });
+    if let (None, Some(limit)) = (&exceeded, assignee_limit) {
+        exceeded = new_assignees.into_iter().find_map(|assignee| {
+            let count = tasks
+                .iter()
+                .filter(|t| t.assignees.contains(assignee))
+                .count();
+            (count >= limit as usize).then(|| WipExceeded {
+                column_id: column_id.to_string(),
+                assignee: Some(assignee.clone()),
+                limit,
+                count,
+            })
+        });
+    }
+
+    match exceeded {
+        Some(exceeded) if !override_wip => {
+            let error = match &exceeded.assignee {
+                Some(_) => format!(
+                    "An assignee already has {} task(s) in '{}', its per-assignee WIP limit",
+                    exceeded.count, column.name
+                ),
+                None => format!(
+                    "Column '{}' already holds {} task(s), its WIP limit",
+                    column.name, exceeded.count
+                ),
+            };
+            let mut body = serde_json::json!(exceeded);
+            body["error"] = error.into();
+            Err(HttpResponse::Conflict().json(body))
+        }
+        exceeded => Ok(exceeded),
+    }
+}
+
+/// Answers with the task and its version as a strong `ETag`.
+pub(super) fn task_response(mut response: HttpResponseBuilder, task: &Task) -> HttpResponse {
+    response
+        .insert_header(ETag(EntityTag::new_strong(task.version.to_string())))
+        .json(task)
+}
+
+/// Why a change was refused before it reached the store.
+enum PreconditionError {
+    /// Neither `If-Match` nor a `version` was sent.
+    Missing,
+    /// `If-Match` was not a single version, or disagreed with `version`.
+    Invalid,
+}
+
+impl PreconditionError {
+    fn response(self) -> HttpResponse {
+        match self {
+            PreconditionError::Missing => {
+                HttpResponse::PreconditionRequired().json(serde_json::json!({
+                    "error": "Send the task's ETag in If-Match, or its version, to change it"
+                }))
+            }
+            PreconditionError::Invalid => HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "If-Match must be a single task ETag that agrees with version"
+            })),
+        }
+    }
+}
+
+/// Reads the version the client last saw from `If-Match` or the request
+/// body. `If-Match: *` accepts any version and yields `None`.
+fn expected_version(
+    req: &HttpRequest,
+    body_version: Option<u64>,
+) -> Result<Option<u64>, PreconditionError> {
+    let header_version = match IfMatch::parse(req) {
+        Ok(IfMatch::Any) => return Ok(None),
+        Ok(IfMatch::Items(tags)) => match tags.as_slice() {
+            [] => None,
+            [tag] if !tag.weak => match tag.tag().parse() {
+                Ok(version) => Some(version),
+                Err(_) => return Err(PreconditionError::Invalid),
+            },
+            _ => return Err(PreconditionError::Invalid),
+        },
+        Err(_) => return Err(PreconditionError::Invalid),
+    };
+    match (header_version, body_version) {
+        (Some(header), Some(body)) if header != body => Err(PreconditionError::Invalid),
+        (Some(version), _) | (None, Some(version)) => Ok(Some(version)),
+        (None, None) => Err(PreconditionError::Missing),
+    }
+}
+
+/// Answers a change made against an outdated version with the current task,
+/// so the client can merge and retry.
+fn precondition_failed(current: &Task) -> HttpResponse {
+    HttpResponse::PreconditionFailed()
+        .insert_header(ETag(EntityTag::new_strong(current.version.to_string())))
+        .json(serde_json::json!({
+            "error": "The task was changed since you loaded it",
+            "task": current
+        }))
+}
+
+/// Explains why a conditional write matched nothing: the task is either gone
+/// or was changed concurrently.
+async fn stale_or_missing(data: &AppState, board_id: &str, task_id: &str) -> HttpResponse {
+    match data.store.get_task(board_id, task_id).await {
+        Ok(Some(current)) => precondition_failed(&current),
+        Ok(None) => task_not_found(),
+        Err(e) => server_error("Error fetching task", "Failed to update task", e),
+    }
+}
+
+/// Sorts the tasks into the board's columns, each in display order.
+fn group_by_column(
+    board: &Board,
+    mut all_tasks: Vec<Task>,
+    comment_counts: &HashMap<String, u64>,
+    blocking: &Blocking,
+) -> TasksResponse {
+    all_tasks.sort_by(|a, b| a.rank.cmp(&b.rank));
+
+    let mut tasks = TasksResponse {
+        columns: board
+            .columns
+            .iter()
+            .map(|column| ColumnTasks {
+                column_id: column.column_id.clone(),
+                name: column.name.clone(),
+                tasks: Vec::new(),
+                estimate_total: 0.0,
+            })
+            .collect(),
+        unassigned: Vec::new(),
+    };
+
+    for task in all_tasks {
+        let comment_count = comment_counts.get(&task.task_id).copied().unwrap_or(0);
+        let card = TaskCard::new(task, comment_count, blocking);
+        match tasks
+            .columns
+            .iter_mut()
+            .find(|c| c.column_id == card.task.column)
+        {
+            Some(column) => {
+                column.estimate_total += card.task.estimate.unwrap_or(0.0);
+                column.tasks.push(card);
+            }
+            None => tasks.unassigned.push(card),
+        }
+    }
+    for column in &mut tasks.columns {
+        // Fractional points add up with float noise; two decimals is plenty.
+        column.estimate_total = (column.estimate_total * 100.0).round() / 100.0;
+    }
+    tasks
+}
+
+/// Reorders cards that are already in rank order, which stays the tie
+/// breaker.
+fn sort_cards(cards: &mut [TaskCard], sort: TaskSort, order: SortOrder) {
+    let directed = |ordering: Ordering| match order {
+        SortOrder::Asc => ordering,
+        SortOrder::Desc => ordering.reverse(),
+    };
+    match sort {
+        TaskSort::Rank => {
+            if order == SortOrder::Desc {
+                cards.reverse();
+            }
+        }
+        TaskSort::Priority => {
+            cards.sort_by(|a, b| directed(a.task.priority.cmp(&b.task.priority)));
+        }
+        TaskSort::Estimate => cards.sort_by(|a, b| match (a.task.estimate, b.task.estimate) {
+            (Some(a), Some(b)) => directed(a.total_cmp(&b)),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }),
+    }
+}
+
+async fn get_tasks(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    query: TaskQuery,
+) -> HttpResponse {
+    let board = match require_access(data, user, board_id, Permission::ViewBoard).await {
+        Ok(board) => board,
+        Err(response) => return response,
+    };
+
+    let label = match &query.label {
+        Some(label) => match board
+            .labels
+            .iter()
+            .find(|l| l.label_id == *label || l.name.eq_ignore_ascii_case(label))
+        {
+            Some(label) => Some(label.label_id.clone()),
+            None => return unknown_label(label),
+        },
+        None => None,
+    };
+    let mut filter = TaskFilter {
+        due_before: query.due_before,
+        label,
+    };
+    if query.overdue {
+        let now = chrono::Utc::now();
+        filter.due_before = Some(filter.due_before.map_or(now, |before| before.min(now)));
+    }
+
+    let filtered = filter.due_before.is_some() || filter.label.is_some();
+    let mut all_tasks = match data.store.find_tasks(board_id, &filter).await {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            eprintln!("Error fetching tasks: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to fetch tasks"
+            }));
+        }
+    };
+    // Blocking relations reach across the filter, so they are worked out
+    // from every task of the board.
+    let blocking = if filtered {
+        match data.store.list_tasks(board_id).await {
+            Ok(tasks) => Blocking::new(&board, &tasks),
+            Err(e) => return server_error("Error fetching tasks", "Failed to fetch tasks", e),
+        }
+    } else {
+        Blocking::new(&board, &all_tasks)
+    };
+    if query.overdue {
+        if let Some(done) = board.done_column() {
+            all_tasks.retain(|t| t.column != done.column_id);
+        }
+    }
+    let comment_counts = match data.store.count_comments(board_id).await {
+        Ok(counts) => counts,
+        Err(e) => return server_error("Error counting comments", "Failed to fetch tasks", e),
+    };
+    let mut tasks = group_by_column(&board, all_tasks, &comment_counts, &blocking);
+    if query.html {
+        let cards = tasks.columns.iter_mut().flat_map(|c| c.tasks.iter_mut());
+        for card in cards.chain(tasks.unassigned.iter_mut()) {
+            card.description_html = Some(markdown::render(&card.task.description));
+        }
+    }
+    if let Some(sort) = query.sort {
+        for column in &mut tasks.columns {
+            sort_cards(&mut column.tasks, sort, query.order);
+        }
+        sort_cards(&mut tasks.unassigned, sort, query.order);
+    }
+    HttpResponse::Ok().json(tasks)
+}
+
+async fn create_task(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_data: CreateTaskRequest,
+) -> HttpResponse {
+    match insert_task(data, user, board_id, task_data, new_id("task"), None, None).await {
+        Ok(task) => task_response(HttpResponse::Created(), &task),
+        Err(response) => response,
+    }
+}
+
+/// Checks `task_data` and adds the task under `task_id` at the end of
+/// `column`, or of the board's first column. Tasks made from a template
+/// carry its `template_id`.
+pub(crate) async fn insert_task(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    mut task_data: CreateTaskRequest,
+    task_id: String,
+    template_id: Option<String>,
+    column: Option<&str>,
+) -> Result<Task, HttpResponse> {
+    let board = require_access(data, user, board_id, Permission::EditTasks).await?;
+    if let Some(response) = invalid_new_task(&mut task_data) {
+        return Err(response);
+    }
+    let labels = board_labels(&board, task_data.labels).map_err(|label| unknown_label(&label))?;
+    let column = match column {
+        Some(column_id) => board.column(column_id).ok_or_else(|| {
+            bad_request(&format!(
+                "Column '{}' does not exist on this board",
+                column_id
+            ))
+        })?,
+        None => board.columns.first().ok_or_else(|| {
+            HttpResponse::Conflict().json(serde_json::json!({
+                "error": "Board has no columns"
+            }))
+        })?,
+    };
+
+    let rank = match column_tasks(data, board_id, &column.column_id, None).await {
+        Ok(mut tasks) => {
+            let end = tasks.len();
+            rank_at(data, user, board_id, &mut tasks, end).await
+        }
+        Err(e) => Err(e),
+    };
+    let rank = rank.map_err(|e| server_error("Error ranking task", "Failed to create task", e))?;
+
+    let new_task = Task {
+        id: None,
+        task_id,
+        board_id: board_id.to_string(),
+        title: task_data.title,
+        description: task_data.description,
+        column: column.column_id.clone(),
+        rank,
+        deleted_at: None,
+        start_at: task_data.start_at,
+        due_at: task_data.due_at,
+        labels,
+        assignees: Vec::new(),
+        priority: task_data.priority,
+        estimate: task_data.estimate,
+        blocked_by: Vec::new(),
+        checklist: task_data
+            .checklist
+            .into_iter()
+            .map(|text| ChecklistItem {
+                item_id: new_id("item"),
+                text,
+                done: false,
+            })
+            .collect(),
+        attachments: Vec::new(),
+        template_id,
+        version: 1,
+    };
+
+    match data.store.create_task(new_task).await {
+        Ok(task) => {
+            record(data, user, TaskEventKind::Created, None, Some(&task)).await;
+            Ok(task)
+        }
+        Err(e) => {
+            eprintln!("Error creating task: {}", e);
+            Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Failed to create task"
+            })))
+        }
+    }
+}
+
+async fn update_task(
+    data: &AppState,
+    user: &AuthUser,
+    req: &HttpRequest,
+    board_id: &str,
+    task_id: &str,
+    task_data: UpdateTaskRequest,
+) -> HttpResponse {
+    let board = match require_access(data, user, board_id, Permission::EditTasks).await {
+        Ok(board) => board,
+        Err(response) => return response,
+    };
+
+    let mut update = TaskUpdate {
+        title: task_data.title,
+        description: task_data.description,
+        column: task_data.column,
+        start_at: task_data.start_at,
+        due_at: task_data.due_at,
+        labels: task_data.labels,
+        assignees: task_data.assignees,
+        priority: task_data.priority,
+        estimate: task_data.estimate,
+        ..Default::default()
+    };
+
+    if update.is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "No fields to update"
+        }));
+    }
+    let expected_version = match expected_version(req, task_data.version) {
+        Ok(version) => version,
+        Err(e) => return e.response(),
+    };
+    if !valid_estimate(update.estimate.flatten()) {
+        return invalid_estimate();
+    }
+    if let Some(title) = &mut update.title {
+        match task_title(title) {
+            Ok(trimmed) => *title = trimmed,
+            Err(message) => return bad_request(&message),
+        }
+    }
+    if update
+        .description
+        .as_deref()
+        .is_some_and(|d| !valid_description(d))
+    {
+        return invalid_description();
+    }
+    if let Some(labels) = update.labels.take() {
+        match board_labels(&board, labels) {
+            Ok(labels) => update.labels = Some(labels),
+            Err(label) => return unknown_label(&label),
+        }
+    }
+    if let Some(assignees) = &mut update.assignees {
+        match first_non_member(data, &board, assignees).await {
+            Ok(None) => {}
+            Ok(Some(user_id)) => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": format!("User '{}' is not a member of this board", user_id)
+                }))
+            }
+            Err(e) => return server_error("Error fetching user", "Failed to update task", e),
+        }
+        let mut seen = HashSet::new();
+        assignees.retain(|user_id| seen.insert(user_id.clone()));
+    }
+
+    for _ in 0..MAX_ATTEMPTS {
+        let task = match data.store.get_task(board_id, task_id).await {
+            Ok(Some(task)) => task,
+            Ok(None) => return task_not_found(),
+            Err(e) => return server_error("Error fetching task", "Failed to update task", e),
+        };
+        if matches!(expected_version, Some(version) if version != task.version) {
+            return precondition_failed(&task);
+        }
+        if !valid_schedule(
+            update.start_at.unwrap_or(task.start_at),
+            update.due_at.unwrap_or(task.due_at),
+        ) {
+            return invalid_schedule();
+        }
+
+        let mut update = update.clone();
+        let wip_override = if update.column.is_some() || update.assignees.is_some() {
+            let check = check_wip(
+                data,
+                &board,
+                &task,
+                update.column.as_deref().unwrap_or(&task.column),
+                update.assignees.as_deref().unwrap_or(&task.assignees),
+                task_data.override_wip,
+            );
+            match check.await {
+                Ok(exceeded) => exceeded,
+                Err(response) => return response,
+            }
+        } else {
+            None
+        };
+
+        let mut kind = TaskEventKind::Updated;
+        if let Some(column) = &update.column {
+            if board.column(column).is_none() {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": format!("Column '{}' does not exist on this board", column)
+                }));
+            }
+            if let Err(response) =
+                check_blockers(data, &board, &task, column, task_data.ignore_blockers).await
+            {
+                return response;
+            }
+
+            // A task moved to another column without an explicit position
+            // goes to the end of that column.
+            if task.column != *column {
+                kind = TaskEventKind::Moved;
+                let rank = match column_tasks(data, board_id, column, Some(task_id)).await {
+                    Ok(mut tasks) => {
+                        let end = tasks.len();
+                        rank_at(data, user, board_id, &mut tasks, end).await
+                    }
+                    Err(e) => Err(e),
+                };
+                match rank {
+                    Ok(rank) => update.rank = Some(rank),
+                    Err(e) => {
+                        return server_error("Error ranking task", "Failed to update task", e)
+                    }
+                }
+            }
+        }
+
+        // `If-Match: *` saves against the version checked above too, and checks
+        // again if the task changed in between.
+        match data
+            .store
+            .update_task(board_id, task_id, &update, Some(task.version))
+            .await
+        {
+            Ok(Some(updated)) => {
+                let mut event =
+                    TaskEvent::new(kind, Some(&task), Some(&updated), &user.user.user_id);
+                event.wip_override = wip_override;
+                record_event(data, event).await;
+                return task_response(HttpResponse::Ok(), &updated);
+            }
+            Ok(None) if expected_version.is_none() => continue,
+            Ok(None) => return stale_or_missing(data, board_id, task_id).await,
+            Err(e) => {
+                eprintln!("Error updating task: {}", e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": "Failed to update task"
+                }));
+            }
+        }
+    }
+    task_busy()
+}
+
+/// Moves the task to a position in `column`. The save is conditional on the
+/// version the checks ran against, and they run again if the task changed
+/// in between.
+async fn move_task(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_id: &str,
+    move_data: MoveTaskRequest,
+) -> HttpResponse {
+    let board = match require_access(data, user, board_id, Permission::EditTasks).await {
+        Ok(board) => board,
+        Err(response) => return response,
+    };
+    let column = move_data.column;
+    if board.column(&column).is_none() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Column '{}' does not exist on this board", column)
+        }));
+    }
+
+    for _ in 0..MAX_ATTEMPTS {
+        let task = match data.store.get_task(board_id, task_id).await {
+            Ok(Some(task)) => task,
+            Ok(None) => return task_not_found(),
+            Err(e) => return server_error("Error fetching task", "Failed to move task", e),
+        };
+        if let Err(response) =
+            check_blockers(data, &board, &task, &column, move_data.ignore_blockers).await
+        {
+            return response;
+        }
+        let wip_override = match check_wip(
+            data,
+            &board,
+            &task,
+            &column,
+            &task.assignees,
+            move_data.override_wip,
+        )
+        .await
+        {
+            Ok(exceeded) => exceeded,
+            Err(response) => return response,
+        };
+
+        let mut tasks = match column_tasks(data, board_id, &column, Some(task_id)).await {
+            Ok(tasks) => tasks,
+            Err(e) => return server_error("Error fetching tasks", "Failed to move task", e),
+        };
+
+        let index_of = |id: &str| tasks.iter().position(|t| t.task_id == id);
+        let not_in_column = |id: &str| {
+            HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("Task '{}' is not in column '{}'", id, column)
+            }))
+        };
+        let position = match (move_data.after.as_deref(), move_data.before.as_deref()) {
+            (None, None) => tasks.len(),
+            (Some(after), None) => match index_of(after) {
+                Some(index) => index + 1,
+                None => return not_in_column(after),
+            },
+            (None, Some(before)) => match index_of(before) {
+                Some(index) => index,
+                None => return not_in_column(before),
+            },
+            (Some(after), Some(before)) => match (index_of(after), index_of(before)) {
+                (Some(a), Some(b)) if a + 1 == b => b,
+                (Some(_), Some(_)) => {
+                    return HttpResponse::BadRequest().json(serde_json::json!({
+                        "error": "after and before must be neighbours in the column"
+                    }))
+                }
+                (None, _) => return not_in_column(after),
+                (_, None) => return not_in_column(before),
+            },
+        };
+
+        let rank = match rank_at(data, user, board_id, &mut tasks, position).await {
+            Ok(rank) => rank,
+            Err(e) => return server_error("Error ranking task", "Failed to move task", e),
+        };
+
+        let update = TaskUpdate {
+            column: Some(column.clone()),
+            rank: Some(rank),
+            ..Default::default()
+        };
+        match data
+            .store
+            .update_task(board_id, task_id, &update, Some(task.version))
+            .await
+        {
+            Ok(Some(moved)) => {
+                let mut event = TaskEvent::new(
+                    TaskEventKind::Moved,
+                    Some(&task),
+                    Some(&moved),
+                    &user.user.user_id,
+                );
+                event.wip_override = wip_override;
+                record_event(data, event).await;
+                return task_response(HttpResponse::Ok(), &moved);
+            }
+            // The checks ran on an older copy of the task; run them again.
+            Ok(None) => continue,
+            Err(e) => return server_error("Error moving task", "Failed to move task", e),
+        }
+    }
+    task_busy()
+}
+
+async fn delete_task(
+    data: &AppState,
+    user: &AuthUser,
+    req: &HttpRequest,
+    board_id: &str,
+    task_id: &str,
+) -> HttpResponse {
+    if let Err(response) = require_access(data, user, board_id, Permission::EditTasks).await {
+        return response;
+    }
+    let expected_version = match expected_version(req, None) {
+        Ok(version) => version,
+        Err(e) => return e.response(),
+    };
+
+    let trashed = match data.store.get_task(board_id, task_id).await {
+        Ok(Some(task)) => task,
+        Ok(None) => return task_not_found(),
+        Err(e) => return server_error("Error fetching task", "Failed to delete task", e),
+    };
+    if matches!(expected_version, Some(version) if version != trashed.version) {
+        return precondition_failed(&trashed);
+    }
+
+    match data
+        .store
+        .trash_task(board_id, task_id, chrono::Utc::now(), expected_version)
+        .await
+    {
+        Ok(Some(task)) => {
+            record(data, user, TaskEventKind::Deleted, Some(&trashed), None).await;
+            HttpResponse::Ok().json(serde_json::json!({
+                "message": "Task moved to the trash",
+                "task": task
+            }))
+        }
+        Ok(None) => stale_or_missing(data, board_id, task_id).await,
+        Err(e) => server_error("Error deleting task", "Failed to delete task", e),
+    }
+}
+
+/// Everything assigned to the caller on the boards they can still see,
+/// grouped by board and column.
+pub async fn get_my_tasks(data: web::Data<AppState>, user: AuthUser) -> impl Responder {
+    let mut assigned = match data.store.list_assigned_tasks(&user.user.user_id).await {
+        Ok(tasks) => tasks,
+        Err(e) => return server_error("Error fetching tasks", "Failed to fetch tasks", e),
+    };
+    let boards = match data.store.list_boards().await {
+        Ok(boards) => boards,
+        Err(e) => return server_error("Error fetching boards", "Failed to fetch tasks", e),
+    };
+
+    let mut response = Vec::new();
+    for board in boards {
+        if board.role_of(&user.user.user_id).is_none() {
+            continue;
+        }
+        let (tasks, rest) = assigned
+            .into_iter()
+            .partition(|t: &Task| t.board_id == board.board_id);
+        assigned = rest;
+        if tasks.is_empty() {
+            continue;
+        }
+        let comment_counts = match data.store.count_comments(&board.board_id).await {
+            Ok(counts) => counts,
+            Err(e) => return server_error("Error counting comments", "Failed to fetch tasks", e),
+        };
+        let blocking = match data.store.list_tasks(&board.board_id).await {
+            Ok(board_tasks) => Blocking::new(&board, &board_tasks),
+            Err(e) => return server_error("Error fetching tasks", "Failed to fetch tasks", e),
+        };
+        response.push(BoardTasks {
+            board_id: board.board_id.clone(),
+            name: board.name.clone(),
+            tasks: group_by_column(&board, tasks, &comment_counts, &blocking),
+        });
+    }
+    HttpResponse::Ok().json(response)
+}
+
+pub async fn get_board_tasks(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    board_id: web::Path<String>,
+    query: web::Query<TaskQuery>,
+) -> impl Responder {
+    get_tasks(&data, &user, &board_id, query.into_inner()).await
+}
+
+pub async fn create_board_task(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    board_id: web::Path<String>,
+    task_data: web::Json<CreateTaskRequest>,
+) -> impl Responder {
+    create_task(&data, &user, &board_id, task_data.into_inner()).await
+}
+
+pub async fn update_board_task(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+    task_data: web::Json<UpdateTaskRequest>,
+) -> impl Responder {
+    let (board_id, task_id) = path.into_inner();
+    update_task(
+        &data,
+        &user,
+        &req,
+        &board_id,
+        &task_id,
+        task_data.into_inner(),
+    )
+    .await
+}
+
+pub async fn move_board_task(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+    move_data: web::Json<MoveTaskRequest>,
+) -> impl Responder {
+    let (board_id, task_id) = path.into_inner();
+    move_task(&data, &user, &board_id, &task_id, move_data.into_inner()).await
+}
+
+pub async fn delete_board_task(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (board_id, task_id) = path.into_inner();
+    delete_task(&data, &user, &req, &board_id, &task_id).await
+}
+
+// The routes below predate boards and act on the default board.
+
+pub async fn get_default_tasks(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    query: web::Query<TaskQuery>,
+) -> impl Responder {
+    get_tasks(&data, &user, DEFAULT_BOARD_ID, query.into_inner()).await
+}
+
+pub async fn create_default_task(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    task_data: web::Json<CreateTaskRequest>,
+) -> impl Responder {
+    create_task(&data, &user, DEFAULT_BOARD_ID, task_data.into_inner()).await
+}
+
+pub async fn update_default_task(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    user: AuthUser,
+    task_id: web::Path<String>,
+    task_data: web::Json<UpdateTaskRequest>,
+) -> impl Responder {
+    update_task(
+        &data,
+        &user,
+        &req,
+        DEFAULT_BOARD_ID,
+        &task_id,
+        task_data.into_inner(),
+    )
+    .await
+}
+
+pub async fn move_default_task(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    task_id: web::Path<String>,
+    move_data: web::Json<MoveTaskRequest>,
+) -> impl Responder {
+    move_task(
+        &data,
+        &user,
+        DEFAULT_BOARD_ID,
+        &task_id,
+        move_data.into_inner(),
+    )
+    .await
+}
+
+pub async fn delete_default_task(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    user: AuthUser,
+    task_id: web::Path<String>,
+) -> impl Responder {
+    delete_task(&data, &user, &req, DEFAULT_BOARD_ID, &task_id).await
+}
@@ -0,0 +1,590 @@
+use actix_web::{web, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+use crate::auth::AuthUser;
+use crate::models::{
+    new_id, Board, BoardTemplate, Column, CreateBoardTemplateRequest, CreateTaskRequest,
+    CreateTemplateRequest, InstantiateBoardRequest, Label, Member, Permission, RecurrenceRequest,
+    Role, TaskEventKind, TaskTemplate, TemplateUpdate, UpdateTemplateRequest,
+};
+use crate::recurrence::{self, Recurrence};
+use crate::AppState;
+
+use super::columns::{invalid_limit, valid_limits};
+use super::labels::{invalid_color, normalize_color};
+use super::tasks::{
+    bad_request, board_labels, insert_task, invalid_new_task, record, task_response, unknown_label,
+};
+use super::{require_access, server_error};
+
+fn template_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": "Template not found"
+    }))
+}
+
+/// Turns the request into a checked rule. Start times are cut to whole
+/// seconds so they survive storage unchanged.
+fn recurrence_rule(request: RecurrenceRequest, now: DateTime<Utc>) -> Result<Recurrence, String> {
+    let start_at = request.start_at.map_or(now, recurrence::whole_seconds);
+    let Some(rrule) = request.rrule else {
+        let rule = Recurrence {
+            frequency: request
+                .frequency
+                .ok_or("recurrence needs a frequency or an rrule")?,
+            interval: request.interval.unwrap_or(1),
+            by_day: request.by_day,
+            by_month_day: request.by_month_day,
+            start_at,
+        };
+        rule.validate()?;
+        return Ok(rule);
+    };
+    if request.frequency.is_some()
+        || request.interval.is_some()
+        || !request.by_day.is_empty()
+        || request.by_month_day.is_some()
+    {
+        return Err(
+            "Give either an rrule or frequency, interval, byDay and byMonthDay".to_string(),
+        );
+    }
+    Recurrence::parse_rrule(&rrule, start_at)
+}
+
+/// The first occurrence from `now` on. A rule that started in the past picks
+/// up with its next occurrence instead of catching up.
+fn first_run(rule: &Recurrence, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    rule.next_from(now)
+}
+
+pub async fn get_templates(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    board_id: web::Path<String>,
+) -> impl Responder {
+    if let Err(response) = require_access(&data, &user, &board_id, Permission::ViewBoard).await {
+        return response;
+    }
+
+    match data.store.list_templates(&board_id).await {
+        Ok(templates) => HttpResponse::Ok().json(templates),
+        Err(e) => server_error("Error fetching templates", "Failed to fetch templates", e),
+    }
+}
+
+pub async fn create_template(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    board_id: web::Path<String>,
+    template_data: web::Json<CreateTemplateRequest>,
+) -> impl Responder {
+    let board = match require_access(&data, &user, &board_id, Permission::EditTasks).await {
+        Ok(board) => board,
+        Err(response) => return response,
+    };
+    let template_data = template_data.into_inner();
+
+    // Checked the way the tasks it makes will be.
+    let mut task_data = CreateTaskRequest {
+        title: template_data.title,
+        description: template_data.description,
+        start_at: None,
+        due_at: None,
+        labels: template_data.labels,
+        priority: template_data.priority,
+        estimate: template_data.estimate,
+        checklist: template_data.checklist,
+    };
+    if let Some(response) = invalid_new_task(&mut task_data) {
+        return response;
+    }
+    let labels = match board_labels(&board, task_data.labels) {
+        Ok(labels) => labels,
+        Err(label) => return unknown_label(&label),
+    };
+    let now = recurrence::whole_seconds(Utc::now());
+    let recurrence = match template_data.recurrence.map(|r| recurrence_rule(r, now)) {
+        Some(Ok(rule)) => Some(rule),
+        Some(Err(message)) => return bad_request(&message),
+        None => None,
+    };
+
+    let template = TaskTemplate {
+        template_id: new_id("template"),
+        board_id: board_id.to_string(),
+        title: task_data.title,
+        description: task_data.description,
+        labels,
+        priority: task_data.priority,
+        estimate: task_data.estimate,
+        checklist: task_data.checklist,
+        next_run_at: recurrence.as_ref().and_then(|rule| first_run(rule, now)),
+        recurrence,
+        created_by: user.user.user_id.clone(),
+        created_at: Utc::now(),
+    };
+
+    match data.store.create_template(template).await {
+        Ok(template) => HttpResponse::Created().json(template),
+        Err(e) => server_error("Error creating template", "Failed to create template", e),
+    }
+}
+
+pub async fn update_template(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+    template_data: web::Json<UpdateTemplateRequest>,
+) -> impl Responder {
+    let (board_id, template_id) = path.into_inner();
+    let board = match require_access(&data, &user, &board_id, Permission::EditTasks).await {
+        Ok(board) => board,
+        Err(response) => return response,
+    };
+    let template_data = template_data.into_inner();
+
+    let mut update = TemplateUpdate {
+        title: template_data.title,
+        description: template_data.description,
+        labels: template_data.labels,
+        priority: template_data.priority,
+        estimate: template_data.estimate,
+        checklist: template_data.checklist,
+        ..Default::default()
+    };
+    if update.is_empty() && template_data.recurrence.is_none() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "No fields to update"
+        }));
+    }
+    if let Some(labels) = update.labels.take() {
+        match board_labels(&board, labels) {
+            Ok(labels) => update.labels = Some(labels),
+            Err(label) => return unknown_label(&label),
+        }
+    }
+    let mut template = match data.store.get_template(&board_id, &template_id).await {
+        Ok(Some(template)) => template,
+        Ok(None) => return template_not_found(),
+        Err(e) => return server_error("Error fetching template", "Failed to update template", e),
+    };
+    update.apply(&mut template);
+    let mut task_data = template.task_request(&board);
+    if let Some(response) = invalid_new_task(&mut task_data) {
+        return response;
+    }
+    if update.title.is_some() {
+        update.title = Some(task_data.title);
+    }
+    if update.checklist.is_some() {
+        update.checklist = Some(task_data.checklist);
+    }
+    // A new rule starts over from now; clearing it stops the template.
+    match template_data.recurrence {
+        Some(Some(request)) => {
+            let now = recurrence::whole_seconds(Utc::now());
+            match recurrence_rule(request, now) {
+                Ok(rule) => {
+                    update.next_run_at = Some(first_run(&rule, now));
+                    update.recurrence = Some(Some(rule));
+                }
+                Err(message) => return bad_request(&message),
+            }
+        }
+        Some(None) => {
+            update.recurrence = Some(None);
+            update.next_run_at = Some(None);
+        }
+        None => {}
+    }
+
+    match data
+        .store
+        .update_template(&board_id, &template_id, &update)
+        .await
+    {
+        Ok(Some(template)) => HttpResponse::Ok().json(template),
+        Ok(None) => template_not_found(),
+        Err(e) => server_error("Error updating template", "Failed to update template", e),
+    }
+}
+
+pub async fn delete_template(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (board_id, template_id) = path.into_inner();
+    if let Err(response) = require_access(&data, &user, &board_id, Permission::EditTasks).await {
+        return response;
+    }
+
+    match data.store.delete_template(&board_id, &template_id).await {
+        Ok(true) => HttpResponse::Ok().json(serde_json::json!({
+            "message": "Template deleted successfully"
+        })),
+        Ok(false) => template_not_found(),
+        Err(e) => server_error("Error deleting template", "Failed to delete template", e),
+    }
+}
+
+/// Creates a task from the template right away, whether or not it recurs.
+pub async fn instantiate_template(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (board_id, template_id) = path.into_inner();
+    let board = match require_access(&data, &user, &board_id, Permission::EditTasks).await {
+        Ok(board) => board,
+        Err(response) => return response,
+    };
+    let template = match data.store.get_template(&board_id, &template_id).await {
+        Ok(Some(template)) => template,
+        Ok(None) => return template_not_found(),
+        Err(e) => return server_error("Error fetching template", "Failed to create task", e),
+    };
+
+    let task_data = template.task_request(&board);
+    match insert_task(
+        &data,
+        &user,
+        &board_id,
+        task_data,
+        new_id("task"),
+        Some(template_id),
+        None,
+    )
+    .await
+    {
+        Ok(task) => task_response(HttpResponse::Created(), &task),
+        Err(response) => response,
+    }
+}
+
+fn board_template_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": "Board template not found"
+    }))
+}
+
+/// Trims and checks the template's names, colors and cards, returning the
+/// response to refuse it with, if any. Cards go through the same checks as
+/// new tasks, and a column may not start out over its WIP limit.
+fn invalid_board_template(template: &mut CreateBoardTemplateRequest) -> Option<HttpResponse> {
+    template.name = template.name.trim().to_string();
+    if template.name.is_empty() {
+        return Some(bad_request("Template name must not be empty"));
+    }
+    for column in &mut template.columns {
+        column.name = column.name.trim().to_string();
+    }
+    if template.columns.is_empty() || template.columns.iter().any(|c| c.name.is_empty()) {
+        return Some(bad_request(
+            "A board needs at least one column and column names must not be empty",
+        ));
+    }
+    for (i, column) in template.columns.iter().enumerate() {
+        if template.columns[..i]
+            .iter()
+            .any(|c| c.name.eq_ignore_ascii_case(&column.name))
+        {
+            return Some(bad_request(&format!(
+                "Column '{}' appears more than once",
+                column.name
+            )));
+        }
+        if !valid_limits(&[column.wip_limit, column.wip_limit_per_assignee]) {
+            return Some(invalid_limit());
+        }
+    }
+    if template.columns.iter().filter(|c| c.done).count() > 1 {
+        return Some(bad_request("Only one column can be the done column"));
+    }
+
+    for i in 0..template.labels.len() {
+        let label = &mut template.labels[i];
+        label.name = label.name.trim().to_string();
+        if label.name.is_empty() {
+            return Some(bad_request("Label name must not be empty"));
+        }
+        match normalize_color(&label.color) {
+            Some(color) => label.color = color,
+            None => return Some(invalid_color()),
+        }
+        let name = &template.labels[i].name;
+        if template.labels[..i]
+            .iter()
+            .any(|l| l.name.eq_ignore_ascii_case(name))
+        {
+            return Some(HttpResponse::Conflict().json(serde_json::json!({
+                "error": format!("A label named '{}' already exists on this template", name)
+            })));
+        }
+    }
+
+    let mut counts = vec![0u32; template.columns.len()];
+    for card in &mut template.cards {
+        let mut task_data = card.task_request(Vec::new());
+        if let Some(response) = invalid_new_task(&mut task_data) {
+            return Some(response);
+        }
+        card.title = task_data.title;
+        card.checklist = task_data.checklist;
+
+        let column = match &card.column {
+            None => 0,
+            Some(name) => match template
+                .columns
+                .iter()
+                .position(|c| c.name.eq_ignore_ascii_case(name.trim()))
+            {
+                Some(column) => column,
+                None => {
+                    return Some(bad_request(&format!(
+                        "Column '{}' does not exist on this template",
+                        name
+                    )))
+                }
+            },
+        };
+        counts[column] += 1;
+        if template.columns[column]
+            .wip_limit
+            .is_some_and(|limit| counts[column] > limit)
+        {
+            return Some(bad_request(&format!(
+                "Column '{}' has more cards than its WIP limit",
+                template.columns[column].name
+            )));
+        }
+        if let Some(name) = card.labels.iter().find(|name| {
+            !template
+                .labels
+                .iter()
+                .any(|l| l.name.eq_ignore_ascii_case(name))
+        }) {
+            return Some(bad_request(&format!(
+                "Label '{}' does not exist on this template",
+                name
+            )));
+        }
+    }
+    None
+}
+
+pub async fn get_board_templates(data: web::Data<AppState>, user: AuthUser) -> impl Responder {
+    match data.store.list_board_templates(&user.user.user_id).await {
+        Ok(templates) => HttpResponse::Ok().json(templates),
+        Err(e) => server_error(
+            "Error fetching board templates",
+            "Failed to fetch board templates",
+            e,
+        ),
+    }
+}
+
+pub async fn get_board_template(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    template_id: web::Path<String>,
+) -> impl Responder {
+    match data
+        .store
+        .get_board_template(&user.user.user_id, &template_id)
+        .await
+    {
+        Ok(Some(template)) => HttpResponse::Ok().json(template),
+        Ok(None) => board_template_not_found(),
+        Err(e) => server_error(
+            "Error fetching board template",
+            "Failed to fetch board template",
+            e,
+        ),
+    }
+}
+
+pub async fn create_board_template(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    template_data: web::Json<CreateBoardTemplateRequest>,
+) -> impl Responder {
+    let mut template_data = template_data.into_inner();
+    if let Some(response) = invalid_board_template(&mut template_data) {
+        return response;
+    }
+
+    let template = BoardTemplate {
+        template_id: new_id("boardtemplate"),
+        name: template_data.name,
+        description: template_data.description,
+        columns: template_data.columns,
+        labels: template_data.labels,
+        cards: template_data.cards,
+        created_by: user.user.user_id.clone(),
+        created_at: Utc::now(),
+    };
+
+    match data.store.create_board_template(template).await {
+        Ok(template) => HttpResponse::Created().json(template),
+        Err(e) => server_error(
+            "Error creating board template",
+            "Failed to create board template",
+            e,
+        ),
+    }
+}
+
+pub async fn delete_board_template(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    template_id: web::Path<String>,
+) -> impl Responder {
+    match data
+        .store
+        .delete_board_template(&user.user.user_id, &template_id)
+        .await
+    {
+        Ok(true) => HttpResponse::Ok().json(serde_json::json!({
+            "message": "Board template deleted successfully"
+        })),
+        Ok(false) => board_template_not_found(),
+        Err(e) => server_error(
+            "Error deleting board template",
+            "Failed to delete board template",
+            e,
+        ),
+    }
+}
+
+/// Creates a board owned by the user with the template's columns and
+/// labels, then adds its cards through the regular task creation path.
+pub async fn instantiate_board_template(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    template_id: web::Path<String>,
+    board_data: Option<web::Json<InstantiateBoardRequest>>,
+) -> impl Responder {
+    let board_data = board_data.map(|b| b.into_inner()).unwrap_or_default();
+    let template = match data
+        .store
+        .get_board_template(&user.user.user_id, &template_id)
+        .await
+    {
+        Ok(Some(template)) => template,
+        Ok(None) => return board_template_not_found(),
+        Err(e) => {
+            return server_error("Error fetching board template", "Failed to create board", e)
+        }
+    };
+    let name = match board_data.name {
+        Some(name) => name.trim().to_string(),
+        None => template.name.clone(),
+    };
+    if name.is_empty() {
+        return bad_request("Board name must not be empty");
+    }
+
+    let columns: Vec<Column> = template
+        .columns
+        .iter()
+        .map(|column| Column {
+            column_id: new_id("col"),
+            name: column.name.clone(),
+            wip_limit: column.wip_limit,
+            wip_limit_per_assignee: column.wip_limit_per_assignee,
+            done: column.done,
+        })
+        .collect();
+    let labels: Vec<Label> = template
+        .labels
+        .iter()
+        .map(|label| Label {
+            label_id: new_id("label"),
+            name: label.name.clone(),
+            color: label.color.clone(),
+        })
+        .collect();
+    // Names are matched ignoring ASCII case only, as validation did.
+    let column_ids: HashMap<String, String> = columns
+        .iter()
+        .map(|c| (c.name.to_ascii_lowercase(), c.column_id.clone()))
+        .collect();
+    let label_ids: HashMap<String, String> = labels
+        .iter()
+        .map(|l| (l.name.to_ascii_lowercase(), l.label_id.clone()))
+        .collect();
+
+    let mut board = Board {
+        board_id: new_id("board"),
+        name,
+        description: board_data
+            .description
+            .unwrap_or_else(|| template.description.clone()),
+        created_at: Utc::now(),
+        columns,
+        members: vec![Member {
+            user_id: user.user.user_id.clone(),
+            role: Role::Owner,
+        }],
+        labels,
+        version: 1,
+    };
+    // Cards were checked when the template was saved. Checking them again
+    // before anything is written leaves only store failures to roll back.
+    let mut cards = Vec::with_capacity(template.cards.len());
+    for card in &template.cards {
+        let labels = card
+            .labels
+            .iter()
+            .filter_map(|name| label_ids.get(&name.to_ascii_lowercase()).cloned())
+            .collect();
+        let column = card
+            .column
+            .as_ref()
+            .and_then(|name| column_ids.get(&name.trim().to_ascii_lowercase()));
+        let mut task_data = card.task_request(labels);
+        if let Some(response) = invalid_new_task(&mut task_data) {
+            return response;
+        }
+        cards.push((task_data, column));
+    }
+
+    board.pin_done_column();
+    let board = match data.store.create_board(board).await {
+        Ok(board) => board,
+        Err(e) => return server_error("Error creating board", "Failed to create board", e),
+    };
+
+    let mut created = Vec::with_capacity(cards.len());
+    for (task_data, column) in cards {
+        match insert_task(
+            &data,
+            &user,
+            &board.board_id,
+            task_data,
+            new_id("task"),
+            None,
+            column.map(String::as_str),
+        )
+        .await
+        {
+            Ok(task) => created.push(task),
+            Err(response) => {
+                // Leave no half-filled board behind. The log keeps the cards
+                // that were created, so it is told they are gone again.
+                for task in &created {
+                    record(&data, &user, TaskEventKind::Deleted, Some(task), None).await;
+                }
+                if let Err(e) = data.store.delete_board(&board.board_id).await {
+                    eprintln!("Error removing board {}: {}", board.board_id, e);
+                }
+                return response;
+            }
+        }
+    }
+
+    HttpResponse::Created().json(board)
+}
@@ -0,0 +1,525 @@
+//! Handler checks that go through the real routes, backed by an in-memory
+//! store.
+
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::test::{self, TestRequest};
+use actix_web::{web, App};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::auth;
+use crate::blobs::LocalBlobStore;
+use crate::events::LocalHub;
+use crate::handlers::attachments::AttachmentLimits;
+use crate::models::{new_id, Session, User};
+use crate::store::MemoryStore;
+use crate::AppState;
+
+struct TestApp {
+    state: web::Data<AppState>,
+}
+
+/// What a request was answered with.
+struct Reply {
+    status: u16,
+    etag: Option<String>,
+    body: Value,
+}
+
+impl TestApp {
+    async fn new() -> Self {
+        let store = Arc::new(MemoryStore::new());
+        crate::ensure_default_board(store.as_ref()).await;
+        let blobs = LocalBlobStore::open(std::env::temp_dir().join("kanban-handler-tests"))
+            .expect("temporary blob directory");
+        TestApp {
+            state: web::Data::new(AppState {
+                store,
+                blobs: Arc::new(blobs),
+                events: Arc::new(LocalHub::new()),
+                session_ttl: chrono::Duration::hours(1),
+                attachment_limits: AttachmentLimits {
+                    max_bytes: 1024,
+                    allowed_types: vec!["text/plain".to_string()],
+                },
+                board_admins: Vec::new(),
+            }),
+        }
+    }
+
+    /// Adds a user and returns a session token for them. Passwords are
+    /// never checked here, so none is hashed.
+    async fn sign_up(&self, username: &str) -> String {
+        let user = self
+            .state
+            .store
+            .create_user(User {
+                user_id: new_id("user"),
+                username: username.to_string(),
+                password_hash: String::new(),
+                created_at: chrono::Utc::now(),
+            })
+            .await
+            .unwrap();
+        let token = auth::generate_token();
+        let now = chrono::Utc::now();
+        self.state
+            .store
+            .create_session(Session {
+                token_hash: auth::hash_token(&token),
+                user_id: user.user_id,
+                created_at: now,
+                expires_at: now + chrono::Duration::hours(1),
+            })
+            .await
+            .unwrap();
+        token
+    }
+
+    async fn send(&self, token: &str, request: TestRequest) -> Reply {
+        let app = test::init_service(
+            App::new()
+                .app_data(self.state.clone())
+                .configure(crate::routes),
+        )
+        .await;
+        let request = request
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        let status = response.status().as_u16();
+        let etag = response
+            .headers()
+            .get(header::ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let bytes = test::read_body(response).await;
+        Reply {
+            status,
+            etag,
+            body: serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        }
+    }
+
+    /// The status of a `GET` sent without an `Authorization` header. The body
+    /// is left unread, since streams never end.
+    async fn get_status(&self, uri: &str) -> u16 {
+        let app = test::init_service(
+            App::new()
+                .app_data(self.state.clone())
+                .configure(crate::routes),
+        )
+        .await;
+        let request = TestRequest::get().uri(uri).to_request();
+        test::call_service(&app, request).await.status().as_u16()
+    }
+
+    async fn post(&self, token: &str, uri: &str, body: Value) -> Reply {
+        self.send(token, TestRequest::post().uri(uri).set_json(body))
+            .await
+    }
+
+    async fn create_board(&self, token: &str) -> String {
+        let reply = self
+            .post(token, "/api/boards", json!({ "name": "Board" }))
+            .await;
+        assert_eq!(reply.status, 201, "{}", reply.body);
+        reply.body["boardId"].as_str().unwrap().to_string()
+    }
+
+    async fn create_task(&self, token: &str, board_id: &str, title: &str) -> String {
+        let uri = format!("/api/boards/{}/tasks", board_id);
+        let reply = self.post(token, &uri, json!({ "title": title })).await;
+        assert_eq!(reply.status, 201, "{}", reply.body);
+        reply.body["taskId"].as_str().unwrap().to_string()
+    }
+
+    async fn block(&self, token: &str, board_id: &str, task_id: &str, blocker: &str) -> Reply {
+        let uri = format!("/api/boards/{}/tasks/{}/blockers", board_id, task_id);
+        self.post(token, &uri, json!({ "taskId": blocker })).await
+    }
+
+    async fn trash(&self, token: &str, board_id: &str, task_id: &str) -> Reply {
+        let uri = format!("/api/boards/{}/tasks/{}", board_id, task_id);
+        let request = TestRequest::delete()
+            .uri(&uri)
+            .insert_header((header::IF_MATCH, HeaderValue::from_static("*")));
+        self.send(token, request).await
+    }
+
+    async fn put(&self, token: &str, uri: &str, body: Value) -> Reply {
+        self.send(token, TestRequest::put().uri(uri).set_json(body))
+            .await
+    }
+
+    async fn update_column(&self, token: &str, board_id: &str, column_id: &str, body: Value) {
+        let uri = format!("/api/boards/{}/columns/{}", board_id, column_id);
+        let reply = self.put(token, &uri, body).await;
+        assert_eq!(reply.status, 200, "{}", reply.body);
+    }
+
+    async fn user_id(&self, token: &str) -> String {
+        let token_hash = auth::hash_token(token);
+        let session = self.state.store.get_session(&token_hash).await.unwrap();
+        session.unwrap().user_id
+    }
+
+    /// Edits the task, sending `if_match` as the `If-Match` header if given.
+    async fn edit(
+        &self,
+        token: &str,
+        board_id: &str,
+        task_id: &str,
+        if_match: Option<&str>,
+        body: Value,
+    ) -> Reply {
+        let uri = format!("/api/boards/{}/tasks/{}", board_id, task_id);
+        let mut request = TestRequest::put().uri(&uri).set_json(body);
+        if let Some(if_match) = if_match {
+            request = request.insert_header((header::IF_MATCH, if_match));
+        }
+        self.send(token, request).await
+    }
+
+    async fn move_task(&self, token: &str, board_id: &str, task_id: &str, body: Value) -> Reply {
+        let uri = format!("/api/boards/{}/tasks/{}/move", board_id, task_id);
+        self.post(token, &uri, body).await
+    }
+
+    /// The task's events, oldest first.
+    async fn history(&self, token: &str, board_id: &str, task_id: &str) -> Vec<Value> {
+        let uri = format!("/api/boards/{}/tasks/{}/history", board_id, task_id);
+        let reply = self.send(token, TestRequest::get().uri(&uri)).await;
+        assert_eq!(reply.status, 200, "{}", reply.body);
+        reply.body["events"].as_array().unwrap().clone()
+    }
+
+    async fn restore(&self, token: &str, board_id: &str, task_id: &str) -> Reply {
+        let uri = format!("/api/boards/{}/tasks/{}/restore", board_id, task_id);
+        self.send(token, TestRequest::post().uri(&uri)).await
+    }
+}
+
+#[actix_web::test]
+async fn restoring_a_task_drops_blockers_that_would_close_a_cycle() {
+    let app = TestApp::new().await;
+    let token = app.sign_up("alice").await;
+    let board = app.create_board(&token).await;
+    let a = app.create_task(&token, &board, "A").await;
+    let b = app.create_task(&token, &board, "B").await;
+    let c = app.create_task(&token, &board, "C").await;
+
+    assert_eq!(app.block(&token, &board, &a, &b).await.status, 201);
+    assert_eq!(app.block(&token, &board, &c, &a).await.status, 201);
+    assert_eq!(app.trash(&token, &board, &a).await.status, 200);
+    // With A in the trash, nothing stops B from waiting on C.
+    assert_eq!(app.block(&token, &board, &b, &c).await.status, 201);
+
+    let restored = app.restore(&token, &board, &a).await;
+    assert_eq!(restored.status, 200, "{}", restored.body);
+    assert_eq!(restored.body["blockedBy"], json!([]));
+    // The other blockers are untouched, and A can still be blocked by a
+    // task outside the chain.
+    let d = app.create_task(&token, &board, "D").await;
+    assert_eq!(app.block(&token, &board, &a, &d).await.status, 201);
+    assert_eq!(app.block(&token, &board, &a, &b).await.status, 409);
+}
+
+#[actix_web::test]
+async fn restoring_a_task_respects_wip_limits() {
+    let app = TestApp::new().await;
+    let token = app.sign_up("alice").await;
+    let board = app.create_board(&token).await;
+    let trashed = app.create_task(&token, &board, "Trashed").await;
+    assert_eq!(app.trash(&token, &board, &trashed).await.status, 200);
+    app.create_task(&token, &board, "Kept").await;
+    app.update_column(&token, &board, "todo", json!({ "wipLimit": 1 }))
+        .await;
+
+    let refused = app.restore(&token, &board, &trashed).await;
+    assert_eq!(refused.status, 409);
+    assert_eq!(refused.body["columnId"], "todo");
+    assert_eq!(refused.body["limit"], 1);
+    assert_eq!(refused.body["count"], 1);
+
+    app.update_column(&token, &board, "todo", json!({ "wipLimit": 2 }))
+        .await;
+    assert_eq!(app.restore(&token, &board, &trashed).await.status, 200);
+}
+
+#[actix_web::test]
+async fn only_streams_take_the_token_from_the_query() {
+    let app = TestApp::new().await;
+    let token = app.sign_up("alice").await;
+
+    let boards = format!("/api/boards?access_token={}", token);
+    assert_eq!(app.get_status(&boards).await, 401);
+    let events = format!("/api/events?access_token={}", token);
+    assert_eq!(app.get_status(&events).await, 200);
+    assert_eq!(app.get_status("/api/events").await, 401);
+}
+
+#[actix_web::test]
+async fn moves_past_a_wip_limit_are_refused_unless_overridden() {
+    let app = TestApp::new().await;
+    let token = app.sign_up("alice").await;
+    let board = app.create_board(&token).await;
+    let first = app.create_task(&token, &board, "First").await;
+    let second = app.create_task(&token, &board, "Second").await;
+    app.update_column(&token, &board, "active", json!({ "wipLimit": 1 }))
+        .await;
+
+    let active = json!({ "column": "active" });
+    assert_eq!(
+        app.move_task(&token, &board, &first, active).await.status,
+        200
+    );
+    let refused = app
+        .move_task(&token, &board, &second, json!({ "column": "active" }))
+        .await;
+    assert_eq!(refused.status, 409);
+    assert_eq!(refused.body["columnId"], "active");
+    assert_eq!(refused.body["limit"], 1);
+    assert_eq!(refused.body["count"], 1);
+    assert!(refused.body.get("assignee").is_none());
+    assert!(refused.body["error"].as_str().unwrap().contains("Active"));
+
+    let overridden = app
+        .move_task(
+            &token,
+            &board,
+            &second,
+            json!({ "column": "active", "overrideWip": true }),
+        )
+        .await;
+    assert_eq!(overridden.status, 200, "{}", overridden.body);
+    let history = app.history(&token, &board, &second).await;
+    let moved = history.last().unwrap();
+    assert_eq!(moved["type"], "task.moved");
+    assert_eq!(
+        moved["wipOverride"],
+        json!({ "columnId": "active", "limit": 1, "count": 1 })
+    );
+    let first_history = app.history(&token, &board, &first).await;
+    assert!(first_history.last().unwrap().get("wipOverride").is_none());
+}
+
+#[actix_web::test]
+async fn assignees_past_their_wip_limit_are_refused() {
+    let app = TestApp::new().await;
+    let token = app.sign_up("alice").await;
+    let alice = app.user_id(&token).await;
+    let board = app.create_board(&token).await;
+    let first = app.create_task(&token, &board, "First").await;
+    let second = app.create_task(&token, &board, "Second").await;
+    app.update_column(&token, &board, "todo", json!({ "wipLimitPerAssignee": 1 }))
+        .await;
+
+    let assign = json!({ "assignees": [alice] });
+    let assigned = app
+        .edit(&token, &board, &first, Some("*"), assign.clone())
+        .await;
+    assert_eq!(assigned.status, 200, "{}", assigned.body);
+    let refused = app.edit(&token, &board, &second, Some("*"), assign).await;
+    assert_eq!(refused.status, 409);
+    assert_eq!(refused.body["assignee"], alice);
+    assert_eq!(refused.body["columnId"], "todo");
+    assert_eq!(refused.body["limit"], 1);
+    assert_eq!(refused.body["count"], 1);
+}
+
+#[actix_web::test]
+async fn blocked_tasks_cannot_be_finished() {
+    let app = TestApp::new().await;
+    let token = app.sign_up("alice").await;
+    let board = app.create_board(&token).await;
+    let blocked = app.create_task(&token, &board, "Blocked").await;
+    let blocker = app.create_task(&token, &board, "Blocker").await;
+    assert_eq!(
+        app.block(&token, &board, &blocked, &blocker).await.status,
+        201
+    );
+
+    let finish = json!({ "column": "completed" });
+    let refused = app
+        .move_task(&token, &board, &blocked, finish.clone())
+        .await;
+    assert_eq!(refused.status, 409);
+    assert_eq!(refused.body["openBlockers"], json!([blocker]));
+    let edited = app
+        .edit(&token, &board, &blocked, Some("*"), finish.clone())
+        .await;
+    assert_eq!(edited.status, 409);
+
+    // Other columns stay open, and so does finishing once the blocker is
+    // done or when the caller chooses to.
+    let active = json!({ "column": "active" });
+    assert_eq!(
+        app.move_task(&token, &board, &blocked, active).await.status,
+        200
+    );
+    let ignored = json!({ "column": "completed", "ignoreBlockers": true });
+    assert_eq!(
+        app.move_task(&token, &board, &blocked, ignored)
+            .await
+            .status,
+        200
+    );
+    let back = json!({ "column": "todo" });
+    assert_eq!(
+        app.move_task(&token, &board, &blocked, back).await.status,
+        200
+    );
+    let done = app
+        .move_task(&token, &board, &blocker, finish.clone())
+        .await;
+    assert_eq!(done.status, 200);
+    assert_eq!(
+        app.move_task(&token, &board, &blocked, finish).await.status,
+        200
+    );
+}
+
+#[actix_web::test]
+async fn blockers_cannot_form_a_cycle() {
+    let app = TestApp::new().await;
+    let token = app.sign_up("alice").await;
+    let board = app.create_board(&token).await;
+    let a = app.create_task(&token, &board, "A").await;
+    let b = app.create_task(&token, &board, "B").await;
+    let c = app.create_task(&token, &board, "C").await;
+
+    assert_eq!(app.block(&token, &board, &a, &a).await.status, 400);
+    assert_eq!(app.block(&token, &board, &a, &b).await.status, 201);
+    assert_eq!(app.block(&token, &board, &b, &c).await.status, 201);
+    let refused = app.block(&token, &board, &c, &a).await;
+    assert_eq!(refused.status, 409);
+    assert!(refused.body["error"].as_str().unwrap().contains(&a));
+    // Blocking again by the same task changes nothing.
+    let again = app.block(&token, &board, &a, &b).await;
+    assert_eq!(again.status, 200);
+    assert_eq!(again.body["blockedBy"], json!([b]));
+}
+
+#[actix_web::test]
+async fn edits_need_the_current_version() {
+    let app = TestApp::new().await;
+    let token = app.sign_up("alice").await;
+    let board = app.create_board(&token).await;
+    let uri = format!("/api/boards/{}/tasks", board);
+    let created = app.post(&token, &uri, json!({ "title": "Task" })).await;
+    assert_eq!(created.etag.as_deref(), Some("\"1\""));
+    let task = created.body["taskId"].as_str().unwrap();
+
+    let title = json!({ "title": "Renamed" });
+    let missing = app.edit(&token, &board, task, None, title.clone()).await;
+    assert_eq!(missing.status, 428);
+    let edited = app
+        .edit(&token, &board, task, Some("\"1\""), title.clone())
+        .await;
+    assert_eq!(edited.status, 200);
+    assert_eq!(edited.etag.as_deref(), Some("\"2\""));
+
+    let stale = app
+        .edit(&token, &board, task, Some("\"1\""), title.clone())
+        .await;
+    assert_eq!(stale.status, 412);
+    assert_eq!(stale.etag.as_deref(), Some("\"2\""));
+    assert_eq!(stale.body["task"]["version"], 2);
+    assert_eq!(stale.body["task"]["title"], "Renamed");
+
+    let weak = app
+        .edit(&token, &board, task, Some("W/\"2\""), title.clone())
+        .await;
+    assert_eq!(weak.status, 400);
+    let disagreeing = json!({ "title": "Renamed", "version": 1 });
+    let disagreeing = app
+        .edit(&token, &board, task, Some("\"2\""), disagreeing)
+        .await;
+    assert_eq!(disagreeing.status, 400);
+    let in_body = json!({ "title": "Again", "version": 2 });
+    let in_body = app.edit(&token, &board, task, None, in_body).await;
+    assert_eq!(in_body.status, 200);
+    assert_eq!(in_body.etag.as_deref(), Some("\"3\""));
+    let any = app.edit(&token, &board, task, Some("*"), title).await;
+    assert_eq!(any.status, 200);
+    assert_eq!(any.etag.as_deref(), Some("\"4\""));
+
+    let uri = format!("/api/boards/{}/tasks/{}", board, task);
+    let delete = || TestRequest::delete().uri(&uri);
+    assert_eq!(app.send(&token, delete()).await.status, 428);
+    let stale_delete = delete().insert_header((header::IF_MATCH, "\"3\""));
+    assert_eq!(app.send(&token, stale_delete).await.status, 412);
+    let delete_current = delete().insert_header((header::IF_MATCH, "\"4\""));
+    assert_eq!(app.send(&token, delete_current).await.status, 200);
+}
+
+#[actix_web::test]
+async fn roles_limit_what_members_may_do() {
+    let app = TestApp::new().await;
+    let owner = app.sign_up("alice").await;
+    let viewer = app.sign_up("bob").await;
+    let commenter = app.sign_up("carol").await;
+    let outsider = app.sign_up("dave").await;
+    let board = app.create_board(&owner).await;
+    let members = format!("/api/boards/{}/members", board);
+    for (username, role) in [("bob", "viewer"), ("carol", "commenter")] {
+        let added = app
+            .post(
+                &owner,
+                &members,
+                json!({ "username": username, "role": role }),
+            )
+            .await;
+        assert_eq!(added.status, 201, "{}", added.body);
+    }
+    let task = app.create_task(&owner, &board, "Task").await;
+
+    let tasks = format!("/api/boards/{}/tasks", board);
+    let listed = app.send(&viewer, TestRequest::get().uri(&tasks)).await;
+    assert_eq!(listed.status, 200);
+    let refused = app.post(&viewer, &tasks, json!({ "title": "Mine" })).await;
+    assert_eq!(refused.status, 403);
+    assert_eq!(refused.body["missingPermission"], "edit_tasks");
+    assert_eq!(refused.body["role"], "viewer");
+
+    let comments = format!("/api/boards/{}/tasks/{}/comments", board, task);
+    let comment = json!({ "body": "Looks good" });
+    let refused = app.post(&viewer, &comments, comment.clone()).await;
+    assert_eq!(refused.status, 403);
+    assert_eq!(refused.body["missingPermission"], "comment");
+    assert_eq!(app.post(&commenter, &comments, comment).await.status, 201);
+    let refused = app
+        .move_task(&commenter, &board, &task, json!({ "column": "active" }))
+        .await;
+    assert_eq!(refused.status, 403);
+    assert_eq!(refused.body["missingPermission"], "edit_tasks");
+    assert_eq!(refused.body["role"], "commenter");
+
+    let column = format!("/api/boards/{}/columns/todo", board);
+    let refused = app
+        .put(&commenter, &column, json!({ "name": "Backlog" }))
+        .await;
+    assert_eq!(refused.status, 403);
+    assert_eq!(refused.body["missingPermission"], "manage_board");
+    let refused = app
+        .post(
+            &viewer,
+            &members,
+            json!({ "username": "dave", "role": "owner" }),
+        )
+        .await;
+    assert_eq!(refused.status, 403);
+    assert_eq!(refused.body["missingPermission"], "manage_members");
+
+    // Boards are hidden from users who are not members at all.
+    let board_uri = format!("/api/boards/{}", board);
+    let hidden = app
+        .send(&outsider, TestRequest::get().uri(&board_uri))
+        .await;
+    assert_eq!(hidden.status, 404);
+    let hidden = app
+        .post(&outsider, &tasks, json!({ "title": "Mine" }))
+        .await;
+    assert_eq!(hidden.status, 404);
+}
@@ -0,0 +1,99 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use crate::auth::{self, AuthUser};
+use crate::models::{new_id, ApiToken, ApiTokenInfo, CreateApiTokenRequest, CreatedApiToken};
+use crate::AppState;
+
+use super::server_error;
+
+/// Token management needs a password sign-in, so a leaked API token cannot
+/// be used to mint more of them.
+fn session_required() -> HttpResponse {
+    HttpResponse::Forbidden().json(serde_json::json!({
+        "error": "API tokens can only be managed from a signed-in session"
+    }))
+}
+
+pub async fn get_tokens(data: web::Data<AppState>, user: AuthUser) -> impl Responder {
+    if !user.is_session() {
+        return session_required();
+    }
+
+    match data.store.list_api_tokens(&user.user.user_id).await {
+        Ok(tokens) => {
+            let tokens: Vec<ApiTokenInfo> = tokens.iter().map(ApiTokenInfo::from).collect();
+            HttpResponse::Ok().json(tokens)
+        }
+        Err(e) => server_error("Error fetching API tokens", "Failed to fetch API tokens", e),
+    }
+}
+
+pub async fn create_token(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    token_data: web::Json<CreateApiTokenRequest>,
+) -> impl Responder {
+    if !user.is_session() {
+        return session_required();
+    }
+
+    let token_data = token_data.into_inner();
+    let name = token_data.name.trim();
+    if name.is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Token name must not be empty"
+        }));
+    }
+    let now = chrono::Utc::now();
+    if token_data
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= now)
+    {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "expiresAt must be in the future"
+        }));
+    }
+
+    let token = auth::generate_api_token();
+    let api_token = ApiToken {
+        token_id: new_id("tok"),
+        user_id: user.user.user_id.clone(),
+        name: name.to_string(),
+        scope: token_data.scope,
+        token_hash: auth::hash_token(&token),
+        created_at: now,
+        expires_at: token_data.expires_at,
+    };
+
+    match data.store.create_api_token(api_token).await {
+        Ok(api_token) => HttpResponse::Created().json(CreatedApiToken {
+            token,
+            info: ApiTokenInfo::from(&api_token),
+        }),
+        Err(e) => server_error("Error creating API token", "Failed to create API token", e),
+    }
+}
+
+pub async fn revoke_token(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    token_id: web::Path<String>,
+) -> impl Responder {
+    if !user.is_session() {
+        return session_required();
+    }
+
+    match data
+        .store
+        .delete_api_token(&user.user.user_id, &token_id)
+        .await
+    {
+        Ok(true) => HttpResponse::Ok().json(serde_json::json!({
+            "message": "API token revoked"
+        })),
+        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
+            "error": "API token not found"
+        })),
+        Err(e) => server_error("Error revoking API token", "Failed to revoke API token", e),
+    }
+}
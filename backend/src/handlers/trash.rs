@@ -0,0 +1,185 @@
+use actix_web::{web, HttpResponse, Responder};
+use std::cmp::Reverse;
+
+use crate::auth::AuthUser;
+use crate::blobs;
+use crate::models::{Permission, TaskEventKind, TaskUpdate, DEFAULT_BOARD_ID};
+use crate::AppState;
+
+use super::blockers::closing_blockers;
+use super::tasks::{check_wip, column_tasks, rank_at, record, task_not_found, task_response};
+use super::{require_access, server_error};
+
+/// Lists the board's trash, most recently deleted first.
+async fn get_trash(data: &AppState, user: &AuthUser, board_id: &str) -> HttpResponse {
+    if let Err(response) = require_access(data, user, board_id, Permission::ViewBoard).await {
+        return response;
+    }
+
+    match data.store.list_trash(board_id).await {
+        Ok(mut tasks) => {
+            tasks.sort_by_key(|t| Reverse(t.deleted_at));
+            HttpResponse::Ok().json(tasks)
+        }
+        Err(e) => server_error("Error fetching trash", "Failed to fetch trash", e),
+    }
+}
+
+/// Puts a trashed task back at the end of its column, or of the first column
+/// if its own column was deleted in the meantime, as long as that stays
+/// within the column's WIP limits. Blockers that would close a cycle with
+/// the tasks changed while it was in the trash are dropped.
+async fn restore_task(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_id: &str,
+) -> HttpResponse {
+    let board = match require_access(data, user, board_id, Permission::EditTasks).await {
+        Ok(board) => board,
+        Err(response) => return response,
+    };
+
+    let trashed = match data.store.list_trash(board_id).await {
+        Ok(tasks) => tasks.into_iter().find(|t| t.task_id == task_id),
+        Err(e) => return server_error("Error fetching trash", "Failed to restore task", e),
+    };
+    let Some(trashed) = trashed else {
+        return task_not_found();
+    };
+
+    let column = match board.column(&trashed.column) {
+        Some(column) => column.column_id.clone(),
+        None => match board.columns.first() {
+            Some(column) => column.column_id.clone(),
+            None => {
+                return HttpResponse::Conflict().json(serde_json::json!({
+                    "error": "Board has no columns"
+                }))
+            }
+        },
+    };
+
+    // Coming out of the trash, the task enters its column anew.
+    let mut entering = trashed.clone();
+    entering.column.clear();
+    if let Err(response) =
+        check_wip(data, &board, &entering, &column, &trashed.assignees, false).await
+    {
+        return response;
+    }
+
+    let blocked_by = match data.store.list_tasks(board_id).await {
+        Ok(tasks) => {
+            let closing = closing_blockers(&tasks, &trashed);
+            (!closing.is_empty()).then(|| {
+                trashed
+                    .blocked_by
+                    .iter()
+                    .filter(|blocker| !closing.contains(blocker))
+                    .cloned()
+                    .collect()
+            })
+        }
+        Err(e) => return server_error("Error fetching tasks", "Failed to restore task", e),
+    };
+
+    let rank = match column_tasks(data, board_id, &column, None).await {
+        Ok(mut tasks) => {
+            let end = tasks.len();
+            rank_at(data, user, board_id, &mut tasks, end).await
+        }
+        Err(e) => Err(e),
+    };
+    let rank = match rank {
+        Ok(rank) => rank,
+        Err(e) => return server_error("Error ranking task", "Failed to restore task", e),
+    };
+
+    let update = TaskUpdate {
+        column: Some(column),
+        rank: Some(rank),
+        blocked_by,
+        ..Default::default()
+    };
+    match data.store.restore_task(board_id, task_id, &update).await {
+        Ok(Some(task)) => {
+            record(data, user, TaskEventKind::Restored, None, Some(&task)).await;
+            task_response(HttpResponse::Ok(), &task)
+        }
+        Ok(None) => task_not_found(),
+        Err(e) => server_error("Error restoring task", "Failed to restore task", e),
+    }
+}
+
+/// Deletes a trashed task and its attachments for good instead of waiting
+/// for the purge job.
+async fn purge_task(
+    data: &AppState,
+    user: &AuthUser,
+    board_id: &str,
+    task_id: &str,
+) -> HttpResponse {
+    if let Err(response) = require_access(data, user, board_id, Permission::EditTasks).await {
+        return response;
+    }
+
+    match data.store.purge_task(board_id, task_id).await {
+        Ok(Some(task)) => {
+            blobs::delete_attachments(data.blobs.as_ref(), &[task]).await;
+            HttpResponse::Ok().json(serde_json::json!({
+                "message": "Task deleted permanently"
+            }))
+        }
+        Ok(None) => task_not_found(),
+        Err(e) => server_error("Error purging task", "Failed to delete task", e),
+    }
+}
+
+pub async fn get_board_trash(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    board_id: web::Path<String>,
+) -> impl Responder {
+    get_trash(&data, &user, &board_id).await
+}
+
+pub async fn restore_board_task(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (board_id, task_id) = path.into_inner();
+    restore_task(&data, &user, &board_id, &task_id).await
+}
+
+pub async fn purge_board_task(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let (board_id, task_id) = path.into_inner();
+    purge_task(&data, &user, &board_id, &task_id).await
+}
+
+// The routes below predate boards and act on the default board.
+
+pub async fn get_default_trash(data: web::Data<AppState>, user: AuthUser) -> impl Responder {
+    get_trash(&data, &user, DEFAULT_BOARD_ID).await
+}
+
+pub async fn restore_default_task(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    task_id: web::Path<String>,
+) -> impl Responder {
+    restore_task(&data, &user, DEFAULT_BOARD_ID, &task_id).await
+}
+
+pub async fn purge_default_task(
+    data: web::Data<AppState>,
+    user: AuthUser,
+    task_id: web::Path<String>,
+) -> impl Responder {
+    purge_task(&data, &user, DEFAULT_BOARD_ID, &task_id).await
+}
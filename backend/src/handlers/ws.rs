@@ -0,0 +1,121 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_ws::{CloseCode, CloseReason, Message, MessageStream, Session};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::auth::AuthUser;
+use crate::models::{Permission, TaskEvent, DEFAULT_BOARD_ID};
+use crate::AppState;
+
+use super::{require_access, ACCESS_RECHECK};
+
+/// Whether the user can no longer see the board. A failed lookup leaves the
+/// socket open.
+async fn access_lost(data: &AppState, user: &AuthUser, board_id: &str) -> bool {
+    match require_access(data, user, board_id, Permission::ViewBoard).await {
+        Ok(_) => false,
+        Err(response) => response.status().is_client_error(),
+    }
+}
+
+/// Pushes the board's task events to the socket until either side closes it,
+/// or the user loses access to the board. A client that falls too far behind
+/// is told to refetch the board.
+async fn forward_events(
+    mut session: Session,
+    mut messages: MessageStream,
+    mut events: broadcast::Receiver<TaskEvent>,
+    data: web::Data<AppState>,
+    user: AuthUser,
+    board_id: String,
+) {
+    let first_recheck = tokio::time::Instant::now() + ACCESS_RECHECK;
+    let mut access_recheck = tokio::time::interval_at(first_recheck, ACCESS_RECHECK);
+    let mut reason = None;
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let text = match event {
+                    Ok(event) if event.board_id != board_id => continue,
+                    Ok(event) => match serde_json::to_string(&event) {
+                        Ok(text) => text,
+                        Err(e) => {
+                            eprintln!("Error serializing task event: {}", e);
+                            continue;
+                        }
+                    },
+                    Err(RecvError::Lagged(_)) => {
+                        serde_json::json!({ "type": "resync", "boardId": board_id }).to_string()
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if session.text(text).await.is_err() {
+                    return;
+                }
+            }
+            message = messages.recv() => match message {
+                Some(Ok(Message::Ping(bytes))) => {
+                    if session.pong(&bytes).await.is_err() {
+                        return;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Clients only listen; anything else they send is ignored.
+                Some(Ok(_)) => {}
+            },
+            _ = access_recheck.tick() => {
+                if access_lost(&data, &user, &board_id).await {
+                    reason = Some(CloseReason {
+                        code: CloseCode::Policy,
+                        description: Some("No longer allowed to see this board".to_string()),
+                    });
+                    break;
+                }
+            }
+        }
+    }
+    let _ = session.close(reason).await;
+}
+
+async fn open_socket(
+    req: &HttpRequest,
+    body: web::Payload,
+    data: web::Data<AppState>,
+    user: AuthUser,
+    board_id: &str,
+) -> actix_web::Result<HttpResponse> {
+    if let Err(response) = require_access(&data, &user, board_id, Permission::ViewBoard).await {
+        return Ok(response);
+    }
+
+    let (response, session, messages) = actix_ws::handle(req, body)?;
+    let events = data.events.subscribe();
+    actix_web::rt::spawn(forward_events(
+        session,
+        messages,
+        events,
+        data,
+        user,
+        board_id.to_string(),
+    ));
+    Ok(response)
+}
+
+pub async fn board_socket(
+    req: HttpRequest,
+    body: web::Payload,
+    data: web::Data<AppState>,
+    user: AuthUser,
+    board_id: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    open_socket(&req, body, data, user, &board_id).await
+}
+
+/// Socket for the default board, alongside the legacy `/api/tasks` routes.
+pub async fn default_socket(
+    req: HttpRequest,
+    body: web::Payload,
+    data: web::Data<AppState>,
+    user: AuthUser,
+) -> actix_web::Result<HttpResponse> {
+    open_socket(&req, body, data, user, DEFAULT_BOARD_ID).await
+}
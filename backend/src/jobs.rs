@@ -0,0 +1,171 @@
+//! Background work that runs alongside the HTTP server.
+
+use actix_web::web;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::auth::AuthUser;
+use crate::blobs::{self, BlobStore};
+use crate::handlers::tasks::insert_task;
+use crate::models::{occurrence_task_id, TaskTemplate};
+use crate::store::Store;
+use crate::AppState;
+
+/// How often the trash is checked for tasks past their retention period.
+const TRASH_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How often templates are checked for occurrences that have come due.
+const RECURRING_TASK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Permanently deletes trashed tasks, and their attachments, once they have
+/// been in the trash for longer than `retention`. The first run happens
+/// right away.
+pub fn spawn_trash_purge(
+    store: Arc<dyn Store>,
+    blobs: Arc<dyn BlobStore>,
+    retention: chrono::Duration,
+) {
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(TRASH_PURGE_INTERVAL);
+        loop {
+            interval.tick().await;
+            let cutoff = chrono::Utc::now() - retention;
+            match store.purge_trash(cutoff).await {
+                Ok(purged) if purged.is_empty() => {}
+                Ok(purged) => {
+                    blobs::delete_attachments(blobs.as_ref(), &purged).await;
+                    println!("Purged {} task(s) from the trash", purged.len());
+                }
+                Err(e) => eprintln!("Error purging the trash: {}", e),
+            }
+        }
+    });
+}
+
+/// Creates a task in the board's first column for every template whose next
+/// occurrence has come due, acting as the template's creator. Occurrences
+/// missed while the server was down collapse into one task for the latest
+/// of them. The first run happens right away.
+pub fn spawn_recurring_tasks(data: web::Data<AppState>) {
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(RECURRING_TASK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let now = Utc::now();
+            match data.store.due_templates(now).await {
+                Ok(due) => {
+                    for template in &due {
+                        run_template(&data, template, now).await;
+                    }
+                }
+                Err(e) => eprintln!("Error fetching due templates: {}", e),
+            }
+        }
+    });
+}
+
+/// Creates the task for the template's latest due occurrence and moves the
+/// template on to the occurrence after it. When the task cannot be stored the
+/// template stays due and the next run tries again.
+async fn run_template(data: &AppState, template: &TaskTemplate, now: DateTime<Utc>) {
+    let (Some(rule), Some(due)) = (&template.recurrence, template.next_run_at) else {
+        return;
+    };
+    let occurrence = rule.latest_until(due, now);
+    if !create_occurrence(data, template, occurrence).await {
+        return;
+    }
+
+    let next = rule.next_after(occurrence);
+    let advanced = data
+        .store
+        .advance_template(&template.board_id, &template.template_id, due, next)
+        .await;
+    if let Err(e) = advanced {
+        eprintln!(
+            "Error scheduling the next run of template {}: {}",
+            template.template_id, e
+        );
+    }
+}
+
+/// Makes sure the task for the occurrence at `at` exists. Its id is derived
+/// from the template and `at`, so an occurrence that already produced a task,
+/// before a restart or a failed update of the template, is not created again.
+/// Returns `false` when the occurrence should be tried again later.
+async fn create_occurrence(data: &AppState, template: &TaskTemplate, at: DateTime<Utc>) -> bool {
+    let board_id = &template.board_id;
+    let task_id = occurrence_task_id(&template.template_id, at);
+    let existing = match data.store.get_task(board_id, &task_id).await {
+        Ok(Some(_)) => true,
+        Ok(None) => match data.store.list_trash(board_id).await {
+            Ok(trash) => trash.iter().any(|t| t.task_id == task_id),
+            Err(e) => {
+                eprintln!("Error fetching the trash: {}", e);
+                return false;
+            }
+        },
+        Err(e) => {
+            eprintln!("Error fetching task: {}", e);
+            return false;
+        }
+    };
+    if existing {
+        return true;
+    }
+
+    let board = match data.store.get_board(board_id).await {
+        Ok(Some(board)) => board,
+        Ok(None) => return true,
+        Err(e) => {
+            eprintln!("Error fetching board: {}", e);
+            return false;
+        }
+    };
+    let creator = match data.store.get_user(&template.created_by).await {
+        Ok(Some(user)) => AuthUser::background(user),
+        Ok(None) => {
+            eprintln!(
+                "Skipped template {}: its creator no longer exists",
+                template.template_id
+            );
+            return true;
+        }
+        Err(e) => {
+            eprintln!("Error fetching user: {}", e);
+            return false;
+        }
+    };
+
+    let result = insert_task(
+        data,
+        &creator,
+        board_id,
+        template.task_request(&board),
+        task_id,
+        Some(template.template_id.clone()),
+        None,
+    )
+    .await;
+    match result {
+        Ok(task) => {
+            println!(
+                "Created task {} from template {}",
+                task.task_id, template.template_id
+            );
+            true
+        }
+        // Refusals such as a creator who lost access to the board would
+        // repeat on every run, so the occurrence is skipped.
+        Err(response) if !response.status().is_server_error() => {
+            eprintln!(
+                "Skipped template {}: creating its task failed with {}",
+                template.template_id,
+                response.status()
+            );
+            true
+        }
+        Err(_) => false,
+    }
+}
@@ -1,242 +1,344 @@
 use actix_web::{web, App, HttpResponse, HttpServer, Responder};
 use actix_cors::Cors;
-use mongodb::{Client, Collection, bson::doc};
-use serde::{Deserialize, Serialize};
+use mongodb::Client;
 use std::env;
+use std::sync::Arc;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct Task {
-    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
-    id: Option<mongodb::bson::oid::ObjectId>,
-    #[serde(rename = "taskId")]
-    task_id: String,
-    text: String,
-    column: String,
-}
+mod auth;
+mod blobs;
+mod events;
+mod handlers;
+mod jobs;
+mod markdown;
+mod models;
+mod rank;
+mod recurrence;
+mod store;
 
-#[derive(Debug, Serialize, Deserialize)]
-struct TasksResponse {
-    todo: Vec<Task>,
-    active: Vec<Task>,
-    completed: Vec<Task>,
-}
+use blobs::{BlobStore, LocalBlobStore, S3BlobStore, S3Config};
+use events::{EventHub, LocalHub};
+use handlers::attachments::{self, AttachmentLimits};
+use handlers::{
+    auth as auth_handlers, blockers, boards, checklist, columns, comments, feed, history, labels,
+    members, tasks, templates, tokens, trash, ws,
+};
+use models::{default_columns, Board, DEFAULT_BOARD_ID};
+use store::{MemoryStore, MongoStore, SqliteStore, Store};
 
-#[derive(Debug, Deserialize)]
-struct CreateTaskRequest {
-    text: String,
+struct AppState {
+    store: Arc<dyn Store>,
+    blobs: Arc<dyn BlobStore>,
+    events: Arc<dyn EventHub>,
+    session_ttl: chrono::Duration,
+    attachment_limits: AttachmentLimits,
+    /// Usernames that own every shared board, so those boards can be
+    /// managed and locked down.
+    board_admins: Vec<String>,
 }
 
-#[derive(Debug, Deserialize)]
-struct UpdateTaskRequest {
-    text: Option<String>,
-    column: Option<String>,
+async fn health_check() -> impl Responder {
+    HttpResponse::Ok().json(serde_json::json!({
+        "status": "healthy",
+        "service": "kanban-backend"
+    }))
 }
 
-struct AppState {
-    tasks_collection: Collection<Task>,
-}
+/// Builds the store named by `STORAGE_BACKEND` ("mongodb", "sqlite" or
+/// "memory"). MongoDB stays the default so existing deployments are unchanged.
+async fn build_store() -> Arc<dyn Store> {
+    let backend = env::var("STORAGE_BACKEND")
+        .unwrap_or_else(|_| "mongodb".to_string());
 
-async fn get_tasks(data: web::Data<AppState>) -> impl Responder {
-    let collection = &data.tasks_collection;
-    
-    match collection.find(None, None).await {
-        Ok(mut cursor) => {
-            let mut tasks = TasksResponse {
-                todo: Vec::new(),
-                active: Vec::new(),
-                completed: Vec::new(),
-            };
-            
-            use futures::stream::StreamExt;
-            while let Some(result) = cursor.next().await {
-                match result {
-                    Ok(task) => {
-                        match task.column.as_str() {
-                            "todo" => tasks.todo.push(task),
-                            "active" => tasks.active.push(task),
-                            "completed" => tasks.completed.push(task),
-                            _ => {}
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("Error reading task: {}", e);
-                    }
-                }
-            }
-            
-            HttpResponse::Ok().json(tasks)
+    match backend.as_str() {
+        "memory" => {
+            println!("Using in-memory storage (data is lost on restart)");
+            Arc::new(MemoryStore::new())
         }
-        Err(e) => {
-            eprintln!("Error fetching tasks: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to fetch tasks"
-            }))
+        "sqlite" => {
+            let path = env::var("SQLITE_PATH")
+                .unwrap_or_else(|_| "kanban.db".to_string());
+            println!("Using SQLite storage at: {}", path);
+            Arc::new(SqliteStore::open(&path).expect("Failed to open SQLite database"))
         }
-    }
-}
+        "mongodb" => {
+            let mongodb_uri = env::var("MONGODB_URI")
+                .unwrap_or_else(|_| "mongodb://localhost:27017".to_string());
+            let database_name = env::var("DATABASE_NAME")
+                .unwrap_or_else(|_| "kanban_db".to_string());
 
-async fn create_task(
-    data: web::Data<AppState>,
-    task_data: web::Json<CreateTaskRequest>,
-) -> impl Responder {
-    let collection = &data.tasks_collection;
-    
-    let new_task = Task {
-        id: None,
-        task_id: format!("task-{}", chrono::Utc::now().timestamp_millis()),
-        text: task_data.text.clone(),
-        column: "todo".to_string(),
-    };
-    
-    match collection.insert_one(new_task.clone(), None).await {
-        Ok(_) => HttpResponse::Created().json(new_task),
-        Err(e) => {
-            eprintln!("Error creating task: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to create task"
-            }))
+            println!("Connecting to MongoDB at: {}", mongodb_uri);
+
+            let client = Client::with_uri_str(&mongodb_uri)
+                .await
+                .expect("Failed to connect to MongoDB");
+
+            let database = client.database(&database_name);
+
+            println!("Connected to MongoDB successfully!");
+
+            Arc::new(
+                MongoStore::open(&database)
+                    .await
+                    .expect("Failed to prepare MongoDB collections"),
+            )
         }
+        other => panic!(
+            "Unknown STORAGE_BACKEND '{}', expected mongodb, sqlite or memory",
+            other
+        ),
     }
 }
 
-async fn update_task(
-    data: web::Data<AppState>,
-    task_id: web::Path<String>,
-    task_data: web::Json<UpdateTaskRequest>,
-) -> impl Responder {
-    let collection = &data.tasks_collection;
-    
-    let filter = doc! { "taskId": task_id.as_str() };
-    
-    let mut update_doc = doc! {};
-    if let Some(text) = &task_data.text {
-        update_doc.insert("text", text);
-    }
-    if let Some(column) = &task_data.column {
-        update_doc.insert("column", column);
-    }
-    
-    if update_doc.is_empty() {
-        return HttpResponse::BadRequest().json(serde_json::json!({
-            "error": "No fields to update"
-        }));
-    }
-    
-    let update = doc! { "$set": update_doc };
-    
-    match collection.update_one(filter.clone(), update, None).await {
-        Ok(result) => {
-            if result.matched_count == 0 {
-                HttpResponse::NotFound().json(serde_json::json!({
-                    "error": "Task not found"
-                }))
-            } else {
-                match collection.find_one(filter, None).await {
-                    Ok(Some(task)) => HttpResponse::Ok().json(task),
-                    Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-                        "error": "Task not found after update"
-                    })),
-                    Err(e) => {
-                        eprintln!("Error fetching updated task: {}", e);
-                        HttpResponse::InternalServerError().json(serde_json::json!({
-                            "error": "Failed to fetch updated task"
-                        }))
-                    }
-                }
-            }
+/// Builds the attachment storage named by `ATTACHMENT_STORAGE` ("local" or
+/// "s3").
+fn build_blobs() -> Arc<dyn BlobStore> {
+    let backend = env::var("ATTACHMENT_STORAGE").unwrap_or_else(|_| "local".to_string());
+
+    match backend.as_str() {
+        "local" => {
+            let dir = env::var("ATTACHMENT_DIR").unwrap_or_else(|_| "attachments".to_string());
+            println!("Storing attachments in: {}", dir);
+            Arc::new(
+                LocalBlobStore::open(dir).expect("Failed to create the attachment directory"),
+            )
         }
-        Err(e) => {
-            eprintln!("Error updating task: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to update task"
-            }))
+        "s3" => {
+            let required = |name: &str| {
+                env::var(name)
+                    .unwrap_or_else(|_| panic!("{} must be set for S3 attachment storage", name))
+            };
+            let config = S3Config {
+                endpoint: required("S3_ENDPOINT"),
+                bucket: required("S3_BUCKET"),
+                region: env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+                access_key_id: required("S3_ACCESS_KEY_ID"),
+                secret_access_key: required("S3_SECRET_ACCESS_KEY"),
+            };
+            println!(
+                "Storing attachments in bucket {} at: {}",
+                config.bucket, config.endpoint
+            );
+            Arc::new(S3BlobStore::new(config).expect("Invalid S3 attachment storage settings"))
         }
+        other => panic!("Unknown ATTACHMENT_STORAGE '{}', expected local or s3", other),
     }
 }
 
-async fn delete_task(
-    data: web::Data<AppState>,
-    task_id: web::Path<String>,
-) -> impl Responder {
-    let collection = &data.tasks_collection;
-    
-    let filter = doc! { "taskId": task_id.as_str() };
-    
-    match collection.delete_one(filter, None).await {
-        Ok(result) => {
-            if result.deleted_count == 0 {
-                HttpResponse::NotFound().json(serde_json::json!({
-                    "error": "Task not found"
-                }))
-            } else {
-                HttpResponse::Ok().json(serde_json::json!({
-                    "message": "Task deleted successfully"
-                }))
-            }
-        }
-        Err(e) => {
-            eprintln!("Error deleting task: {}", e);
-            HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": "Failed to delete task"
-            }))
-        }
+/// Builds the CORS policy from `CORS_ALLOWED_ORIGINS`, a comma-separated list
+/// of origins. "*" allows any origin and should only be used in development.
+fn build_cors(allowed_origins: &[String]) -> Cors {
+    let cors = Cors::default()
+        .allow_any_method()
+        .allow_any_header()
+        .max_age(3600);
+
+    if allowed_origins.iter().any(|origin| origin == "*") {
+        return cors.allow_any_origin();
     }
+    allowed_origins
+        .iter()
+        .fold(cors, |cors, origin| cors.allowed_origin(origin))
 }
 
-async fn health_check() -> impl Responder {
-    HttpResponse::Ok().json(serde_json::json!({
-        "status": "healthy",
-        "service": "kanban-backend"
-    }))
+/// Creates the board behind the legacy `/api/tasks` routes on first start.
+async fn ensure_default_board(store: &dyn Store) {
+    let existing = store
+        .get_board(DEFAULT_BOARD_ID)
+        .await
+        .expect("Failed to look up the default board");
+
+    if existing.is_none() {
+        store
+            .create_board(Board {
+                board_id: DEFAULT_BOARD_ID.to_string(),
+                name: "Default".to_string(),
+                description: String::new(),
+                created_at: chrono::Utc::now(),
+                columns: default_columns(),
+                members: Vec::new(),
+                labels: Vec::new(),
+                version: 1,
+            })
+            .await
+            .expect("Failed to create the default board");
+    }
+}
+
+/// Registers every route of the API.
+fn routes(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/health", web::get().to(health_check))
+        .route("/api/auth/register", web::post().to(auth_handlers::register))
+        .route("/api/auth/login", web::post().to(auth_handlers::login))
+        .route("/api/auth/logout", web::post().to(auth_handlers::logout))
+        .route("/api/auth/me", web::get().to(auth_handlers::me))
+        .route("/api/me/tasks", web::get().to(tasks::get_my_tasks))
+        .route("/api/tokens", web::get().to(tokens::get_tokens))
+        .route("/api/tokens", web::post().to(tokens::create_token))
+        .route("/api/tokens/{token_id}", web::delete().to(tokens::revoke_token))
+        .route("/api/board-templates", web::get().to(templates::get_board_templates))
+        .route("/api/board-templates", web::post().to(templates::create_board_template))
+        .route("/api/board-templates/{template_id}", web::get().to(templates::get_board_template))
+        .route("/api/board-templates/{template_id}", web::delete().to(templates::delete_board_template))
+        .route("/api/board-templates/{template_id}/boards", web::post().to(templates::instantiate_board_template))
+        .route("/api/boards", web::get().to(boards::get_boards))
+        .route("/api/boards", web::post().to(boards::create_board))
+        .route("/api/boards/{board_id}", web::get().to(boards::get_board))
+        .route("/api/boards/{board_id}", web::put().to(boards::update_board))
+        .route("/api/boards/{board_id}", web::delete().to(boards::delete_board))
+        .route("/api/boards/{board_id}/columns", web::get().to(columns::get_columns))
+        .route("/api/boards/{board_id}/columns", web::post().to(columns::create_column))
+        .route("/api/boards/{board_id}/columns/{column_id}", web::put().to(columns::update_column))
+        .route("/api/boards/{board_id}/columns/{column_id}", web::delete().to(columns::delete_column))
+        .route("/api/boards/{board_id}/labels", web::get().to(labels::get_labels))
+        .route("/api/boards/{board_id}/labels", web::post().to(labels::create_label))
+        .route("/api/boards/{board_id}/labels/{label_id}", web::put().to(labels::update_label))
+        .route("/api/boards/{board_id}/labels/{label_id}", web::delete().to(labels::delete_label))
+        .route("/api/boards/{board_id}/members", web::get().to(members::get_members))
+        .route("/api/boards/{board_id}/members", web::post().to(members::add_member))
+        .route("/api/boards/{board_id}/members/{user_id}", web::put().to(members::update_member))
+        .route("/api/boards/{board_id}/members/{user_id}", web::delete().to(members::remove_member))
+        .route("/api/boards/{board_id}/templates", web::get().to(templates::get_templates))
+        .route("/api/boards/{board_id}/templates", web::post().to(templates::create_template))
+        .route("/api/boards/{board_id}/templates/{template_id}", web::put().to(templates::update_template))
+        .route("/api/boards/{board_id}/templates/{template_id}", web::delete().to(templates::delete_template))
+        .route("/api/boards/{board_id}/templates/{template_id}/tasks", web::post().to(templates::instantiate_template))
+        .route("/api/boards/{board_id}/tasks", web::get().to(tasks::get_board_tasks))
+        .route("/api/boards/{board_id}/tasks", web::post().to(tasks::create_board_task))
+        .route("/api/boards/{board_id}/tasks/{id}", web::put().to(tasks::update_board_task))
+        .route("/api/boards/{board_id}/tasks/{id}", web::delete().to(tasks::delete_board_task))
+        .route("/api/boards/{board_id}/tasks/{id}/move", web::post().to(tasks::move_board_task))
+        .route("/api/boards/{board_id}/tasks/{id}/blockers", web::get().to(blockers::get_board_blockers))
+        .route("/api/boards/{board_id}/tasks/{id}/blockers", web::post().to(blockers::add_board_blocker))
+        .route("/api/boards/{board_id}/tasks/{id}/blockers/{blocker_id}", web::delete().to(blockers::remove_board_blocker))
+        .route("/api/boards/{board_id}/tasks/{id}/checklist", web::post().to(checklist::add_board_item))
+        .route("/api/boards/{board_id}/tasks/{id}/checklist/{item_id}", web::put().to(checklist::update_board_item))
+        .route("/api/boards/{board_id}/tasks/{id}/checklist/{item_id}", web::delete().to(checklist::delete_board_item))
+        .route("/api/boards/{board_id}/tasks/{id}/attachments", web::get().to(attachments::get_board_attachments))
+        .route("/api/boards/{board_id}/tasks/{id}/attachments", web::post().to(attachments::upload_board_attachment))
+        .route("/api/boards/{board_id}/tasks/{id}/attachments/{attachment_id}", web::get().to(attachments::download_board_attachment))
+        .route("/api/boards/{board_id}/tasks/{id}/attachments/{attachment_id}", web::delete().to(attachments::delete_board_attachment))
+        .route("/api/boards/{board_id}/tasks/{id}/comments", web::get().to(comments::get_board_comments))
+        .route("/api/boards/{board_id}/tasks/{id}/comments", web::post().to(comments::create_board_comment))
+        .route("/api/boards/{board_id}/tasks/{id}/comments/{comment_id}", web::put().to(comments::update_board_comment))
+        .route("/api/boards/{board_id}/tasks/{id}/comments/{comment_id}", web::delete().to(comments::delete_board_comment))
+        .route("/api/boards/{board_id}/tasks/{id}/restore", web::post().to(trash::restore_board_task))
+        .route("/api/boards/{board_id}/trash", web::get().to(trash::get_board_trash))
+        .route("/api/boards/{board_id}/trash/{id}", web::delete().to(trash::purge_board_task))
+        .route("/api/boards/{board_id}/tasks/{id}/history", web::get().to(history::get_board_task_history))
+        .route("/api/boards/{board_id}/audit", web::get().to(history::get_board_audit))
+        .route("/api/boards/{board_id}/ws", web::get().to(ws::board_socket))
+        .route("/api/tasks", web::get().to(tasks::get_default_tasks))
+        .route("/api/tasks", web::post().to(tasks::create_default_task))
+        .route("/api/tasks/{id}", web::put().to(tasks::update_default_task))
+        .route("/api/tasks/{id}", web::delete().to(tasks::delete_default_task))
+        .route("/api/tasks/{id}/move", web::post().to(tasks::move_default_task))
+        .route("/api/tasks/{id}/blockers", web::get().to(blockers::get_default_blockers))
+        .route("/api/tasks/{id}/blockers", web::post().to(blockers::add_default_blocker))
+        .route("/api/tasks/{id}/blockers/{blocker_id}", web::delete().to(blockers::remove_default_blocker))
+        .route("/api/tasks/{id}/checklist", web::post().to(checklist::add_default_item))
+        .route("/api/tasks/{id}/checklist/{item_id}", web::put().to(checklist::update_default_item))
+        .route("/api/tasks/{id}/checklist/{item_id}", web::delete().to(checklist::delete_default_item))
+        .route("/api/tasks/{id}/attachments", web::get().to(attachments::get_default_attachments))
+        .route("/api/tasks/{id}/attachments", web::post().to(attachments::upload_default_attachment))
+        .route("/api/tasks/{id}/attachments/{attachment_id}", web::get().to(attachments::download_default_attachment))
+        .route("/api/tasks/{id}/attachments/{attachment_id}", web::delete().to(attachments::delete_default_attachment))
+        .route("/api/tasks/{id}/comments", web::get().to(comments::get_default_comments))
+        .route("/api/tasks/{id}/comments", web::post().to(comments::create_default_comment))
+        .route("/api/tasks/{id}/comments/{comment_id}", web::put().to(comments::update_default_comment))
+        .route("/api/tasks/{id}/comments/{comment_id}", web::delete().to(comments::delete_default_comment))
+        .route("/api/tasks/{id}/restore", web::post().to(trash::restore_default_task))
+        .route("/api/trash", web::get().to(trash::get_default_trash))
+        .route("/api/trash/{id}", web::delete().to(trash::purge_default_task))
+        .route("/api/tasks/{id}/history", web::get().to(history::get_default_task_history))
+        .route("/api/audit", web::get().to(history::get_default_audit))
+        .route("/api/ws", web::get().to(ws::default_socket))
+        .route("/api/events", web::get().to(feed::stream_events));
 }
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv::dotenv().ok();
-    
-    let mongodb_uri = env::var("MONGODB_URI")
-        .unwrap_or_else(|_| "mongodb://localhost:27017".to_string());
-    let database_name = env::var("DATABASE_NAME")
-        .unwrap_or_else(|_| "kanban_db".to_string());
+
     let port = env::var("PORT")
         .unwrap_or_else(|_| "8080".to_string())
         .parse::<u16>()
         .expect("PORT must be a valid number");
-    
-    println!("Connecting to MongoDB at: {}", mongodb_uri);
-    
-    let client = Client::with_uri_str(&mongodb_uri)
-        .await
-        .expect("Failed to connect to MongoDB");
-    
-    let database = client.database(&database_name);
-    let tasks_collection: Collection<Task> = database.collection("tasks");
-    
-    println!("Connected to MongoDB successfully!");
-    
+
+    let store = build_store().await;
+    ensure_default_board(store.as_ref()).await;
+
+    let session_ttl_hours = env::var("SESSION_TTL_HOURS")
+        .unwrap_or_else(|_| "168".to_string())
+        .parse::<i64>()
+        .expect("SESSION_TTL_HOURS must be a whole number of hours");
+
+    let trash_retention_days = env::var("TRASH_RETENTION_DAYS")
+        .unwrap_or_else(|_| "30".to_string())
+        .parse::<i64>()
+        .expect("TRASH_RETENTION_DAYS must be a whole number of days");
+    let blobs = build_blobs();
+    jobs::spawn_trash_purge(
+        store.clone(),
+        blobs.clone(),
+        chrono::Duration::days(trash_retention_days),
+    );
+
+    let attachment_limits = AttachmentLimits {
+        max_bytes: env::var("ATTACHMENT_MAX_BYTES")
+            .unwrap_or_else(|_| (10 * 1024 * 1024).to_string())
+            .parse::<usize>()
+            .expect("ATTACHMENT_MAX_BYTES must be a whole number of bytes"),
+        allowed_types: env::var("ATTACHMENT_TYPES")
+            .unwrap_or_else(|_| {
+                "image/png,image/jpeg,image/gif,image/webp,text/plain,text/csv,\
+                 application/json,application/pdf,application/zip"
+                    .to_string()
+            })
+            .split(',')
+            .map(|allowed| allowed.trim().to_lowercase())
+            .filter(|allowed| !allowed.is_empty())
+            .collect(),
+    };
+
+    let board_admins: Vec<String> = env::var("BOARD_ADMINS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|username| username.trim().to_lowercase())
+        .filter(|username| !username.is_empty())
+        .collect();
+
+    let allowed_origins: Vec<String> = env::var("CORS_ALLOWED_ORIGINS")
+        .unwrap_or_else(|_| "http://localhost:5173".to_string())
+        .split(',')
+        .map(|origin| origin.trim().to_string())
+        .filter(|origin| !origin.is_empty())
+        .collect();
+
     let app_state = web::Data::new(AppState {
-        tasks_collection,
+        store,
+        blobs,
+        events: Arc::new(LocalHub::new()),
+        session_ttl: chrono::Duration::hours(session_ttl_hours),
+        attachment_limits,
+        board_admins,
     });
-    
+
+    jobs::spawn_recurring_tasks(app_state.clone());
+    // Hashed now so the first login for an unknown username is not slower.
+    auth::dummy_password_hash();
+
     println!("Starting server on port {}...", port);
-    
+
     HttpServer::new(move || {
-        let cors = Cors::default()
-            .allow_any_origin()
-            .allow_any_method()
-            .allow_any_header()
-            .max_age(3600);
-        
+        let cors = build_cors(&allowed_origins);
+
         App::new()
             .wrap(cors)
             .app_data(app_state.clone())
-            .route("/health", web::get().to(health_check))
-            .route("/api/tasks", web::get().to(get_tasks))
-            .route("/api/tasks", web::post().to(create_task))
-            .route("/api/tasks/{id}", web::put().to(update_task))
-            .route("/api/tasks/{id}", web::delete().to(delete_task))
+            .configure(routes)
     })
     .bind(("0.0.0.0", port))?
     .run()
     .await
-}
\ No newline at end of file
+}
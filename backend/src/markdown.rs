@@ -0,0 +1,14 @@
+//! Renders task descriptions for clients that want HTML instead of Markdown.
+
+use pulldown_cmark::{html, Options, Parser};
+
+/// Renders CommonMark (plus tables, strikethrough and task lists) to HTML and
+/// strips everything outside ammonia's allowlist, so the result can be
+/// inserted into a page as is. Links get `rel="noopener noreferrer"`.
+pub fn render(markdown: &str) -> String {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut unsafe_html = String::with_capacity(markdown.len() * 3 / 2);
+    html::push_html(&mut unsafe_html, Parser::new_ext(markdown, options));
+    ammonia::clean(&unsafe_html)
+}
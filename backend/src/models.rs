@@ -0,0 +1,1480 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+
+use crate::recurrence::{Frequency, Recurrence, Weekday};
+
+/// Board that serves the legacy `/api/tasks` routes and owns every task
+/// created before boards existed.
+pub const DEFAULT_BOARD_ID: &str = "default";
+
+fn default_board_id() -> String {
+    DEFAULT_BOARD_ID.to_string()
+}
+
+/// Generates a fresh identifier such as `task-01HZX3N5Q8...`. The ULID part
+/// sorts by creation time and carries 80 random bits, so ids created in the
+/// same millisecond do not collide.
+pub fn new_id(prefix: &str) -> String {
+    format!("{}-{}", prefix, ulid::Ulid::new())
+}
+
+/// The id of the task a template produces for its occurrence at `at`. The
+/// same occurrence always gets the same id, so a run repeated after a
+/// restart finds the task it created before instead of adding another one.
+pub fn occurrence_task_id(template_id: &str, at: DateTime<Utc>) -> String {
+    let digest = Sha256::digest(template_id.as_bytes());
+    let mut random = [0u8; 16];
+    random.copy_from_slice(&digest[..16]);
+    let id = ulid::Ulid::from_parts(at.timestamp_millis() as u64, u128::from_be_bytes(random));
+    format!("task-{}", id)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Column {
+    #[serde(rename = "columnId")]
+    pub column_id: String,
+    pub name: String,
+    /// Most tasks the column may hold.
+    #[serde(rename = "wipLimit", default, skip_serializing_if = "Option::is_none")]
+    pub wip_limit: Option<u32>,
+    /// Most tasks in the column any one assignee may hold.
+    #[serde(
+        rename = "wipLimitPerAssignee",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub wip_limit_per_assignee: Option<u32>,
+    /// Whether the column holds finished work. At most one column on a
+    /// board is marked; see [`Board::done_column`].
+    #[serde(default)]
+    pub done: bool,
+}
+
+/// How urgent a task is. Variants are declared from most to least urgent,
+/// which is the order they sort in.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Urgent,
+    High,
+    Medium,
+    Low,
+    #[default]
+    None,
+}
+
+/// Largest accepted estimate, in story points.
+pub const MAX_ESTIMATE: f64 = 1000.0;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Label {
+    #[serde(rename = "labelId")]
+    pub label_id: String,
+    pub name: String,
+    /// `#rrggbb` hex color.
+    pub color: String,
+}
+
+/// Columns every board starts with. Their ids match the values tasks carried
+/// before columns were configurable.
+pub fn default_columns() -> Vec<Column> {
+    [
+        ("todo", "To Do"),
+        ("active", "Active"),
+        ("completed", "Completed"),
+    ]
+    .into_iter()
+    .map(|(column_id, name)| Column {
+        column_id: column_id.to_string(),
+        name: name.to_string(),
+        wip_limit: None,
+        wip_limit_per_assignee: None,
+        done: column_id == "completed",
+    })
+    .collect()
+}
+
+/// What a board member may do. Each role includes everything the roles below
+/// it can do.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Owner,
+    Editor,
+    Commenter,
+    Viewer,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    ViewBoard,
+    EditTasks,
+    Comment,
+    ManageBoard,
+    ManageMembers,
+}
+
+impl Permission {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Permission::ViewBoard => "view_board",
+            Permission::EditTasks => "edit_tasks",
+            Permission::Comment => "comment",
+            Permission::ManageBoard => "manage_board",
+            Permission::ManageMembers => "manage_members",
+        }
+    }
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Owner => "owner",
+            Role::Editor => "editor",
+            Role::Commenter => "commenter",
+            Role::Viewer => "viewer",
+        }
+    }
+
+    pub fn allows(self, permission: Permission) -> bool {
+        match permission {
+            Permission::ViewBoard => true,
+            Permission::EditTasks => matches!(self, Role::Owner | Role::Editor),
+            Permission::Comment => self != Role::Viewer,
+            Permission::ManageBoard | Permission::ManageMembers => self == Role::Owner,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Member {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    pub role: Role,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Board {
+    #[serde(rename = "boardId")]
+    pub board_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    /// Columns in display order.
+    #[serde(default = "default_columns")]
+    pub columns: Vec<Column>,
+    /// Boards without members (the default board and boards created before
+    /// membership existed) are shared with every signed-in user.
+    #[serde(default)]
+    pub members: Vec<Member>,
+    /// Labels tasks on this board can carry.
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    /// Bumped by the store on every change. Column, label and member edits
+    /// are saved conditionally on it, so concurrent edits are not lost.
+    #[serde(default)]
+    pub version: u64,
+}
+
+impl Board {
+    pub fn column(&self, column_id: &str) -> Option<&Column> {
+        self.columns.iter().find(|c| c.column_id == column_id)
+    }
+
+    pub fn label(&self, label_id: &str) -> Option<&Label> {
+        self.labels.iter().find(|l| l.label_id == label_id)
+    }
+
+    /// The column that holds finished work: the one marked `done`, or the
+    /// last column on boards saved before columns could be marked.
+    pub fn done_column(&self) -> Option<&Column> {
+        self.columns
+            .iter()
+            .find(|c| c.done)
+            .or_else(|| self.columns.last())
+    }
+
+    /// Marks the column [`done_column`](Self::done_column) falls back to, so
+    /// that reordering or deleting columns cannot change which one is done.
+    pub fn pin_done_column(&mut self) {
+        if !self.columns.iter().any(|c| c.done) {
+            if let Some(last) = self.columns.last_mut() {
+                last.done = true;
+            }
+        }
+    }
+
+    /// Makes the column the board's only done column.
+    pub fn set_done_column(&mut self, column_id: &str) {
+        for column in &mut self.columns {
+            column.done = column.column_id == column_id;
+        }
+    }
+
+    /// The user's role on this board, or `None` if they may not see it.
+    /// Shared boards grant everyone the editor role; board admins are
+    /// raised to owner when access is checked.
+    pub fn role_of(&self, user_id: &str) -> Option<Role> {
+        if self.members.is_empty() {
+            return Some(Role::Editor);
+        }
+        self.members
+            .iter()
+            .find(|m| m.user_id == user_id)
+            .map(|m| m.role)
+    }
+}
+
+#[derive(Debug, Serialize, Default, Clone)]
+pub struct BoardUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columns: Option<Vec<Column>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub members: Option<Vec<Member>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<Vec<Label>>,
+}
+
+impl BoardUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.description.is_none()
+            && self.columns.is_none()
+            && self.members.is_none()
+            && self.labels.is_none()
+    }
+
+    pub fn apply(&self, board: &mut Board) {
+        if let Some(name) = &self.name {
+            board.name = name.clone();
+        }
+        if let Some(description) = &self.description {
+            board.description = description.clone();
+        }
+        if let Some(columns) = &self.columns {
+            board.columns = columns.clone();
+        }
+        if let Some(members) = &self.members {
+            board.members = members.clone();
+        }
+        if let Some(labels) = &self.labels {
+            board.labels = labels.clone();
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateBoardRequest {
+    pub name: String,
+    pub description: Option<String>,
+    /// Column names in order; the default columns are used when omitted.
+    pub columns: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateBoardRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddMemberRequest {
+    pub username: String,
+    pub role: Role,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateMemberRequest {
+    pub role: Role,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MemberResponse {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    pub username: String,
+    pub role: Role,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Task {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<mongodb::bson::oid::ObjectId>,
+    #[serde(rename = "taskId")]
+    pub task_id: String,
+    #[serde(rename = "boardId", default = "default_board_id")]
+    pub board_id: String,
+    /// Tasks and history entries written before descriptions existed call
+    /// this `text`.
+    #[serde(alias = "text")]
+    pub title: String,
+    /// Markdown.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    pub column: String,
+    /// Position within the column; see [`crate::rank`].
+    #[serde(default)]
+    pub rank: String,
+    /// Set while the task is in the trash.
+    #[serde(
+        rename = "deletedAt",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "sortable_time::option"
+    )]
+    pub deleted_at: Option<DateTime<Utc>>,
+    #[serde(
+        rename = "startAt",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "sortable_time::option"
+    )]
+    pub start_at: Option<DateTime<Utc>>,
+    #[serde(
+        rename = "dueAt",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "sortable_time::option"
+    )]
+    pub due_at: Option<DateTime<Utc>>,
+    /// Ids of the board labels attached to the task.
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// User ids of the board members working on the task.
+    #[serde(default)]
+    pub assignees: Vec<String>,
+    #[serde(default)]
+    pub priority: Priority,
+    /// Size in story points.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<f64>,
+    /// Ids of tasks on the same board that must be finished before this one.
+    #[serde(rename = "blockedBy", default)]
+    pub blocked_by: Vec<String>,
+    /// Checklist items in display order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checklist: Vec<ChecklistItem>,
+    /// Files attached to the task, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+    /// The template whose recurrence rule produced the task.
+    #[serde(
+        rename = "templateId",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub template_id: Option<String>,
+    /// Bumped by the store on every change and sent as the task's `ETag`.
+    #[serde(default)]
+    pub version: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChecklistItem {
+    #[serde(rename = "itemId")]
+    pub item_id: String,
+    pub text: String,
+    #[serde(default)]
+    pub done: bool,
+}
+
+/// A file attached to a task. The contents are kept in blob storage under
+/// the attachment id.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Attachment {
+    #[serde(rename = "attachmentId")]
+    pub attachment_id: String,
+    pub filename: String,
+    #[serde(rename = "contentType")]
+    pub content_type: String,
+    /// Size in bytes.
+    pub size: u64,
+    #[serde(rename = "uploadedBy")]
+    pub uploaded_by: String,
+    #[serde(rename = "uploadedAt", with = "sortable_time")]
+    pub uploaded_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Default, Clone, Copy)]
+pub struct ChecklistProgress {
+    pub completed: usize,
+    pub total: usize,
+}
+
+/// Which tasks of a board block which, built from all of its live tasks.
+/// A blocker is open until it reaches the done column; blockers that were
+/// deleted no longer count.
+#[derive(Debug, Default)]
+pub struct Blocking {
+    /// Blocker id to the ids of the tasks it blocks.
+    blocks: HashMap<String, Vec<String>>,
+    open: HashSet<String>,
+}
+
+impl Blocking {
+    pub fn new(board: &Board, tasks: &[Task]) -> Self {
+        let done = board.done_column().map(|c| c.column_id.as_str());
+        let mut blocking = Blocking::default();
+        for task in tasks {
+            if Some(task.column.as_str()) != done {
+                blocking.open.insert(task.task_id.clone());
+            }
+            for blocker in &task.blocked_by {
+                blocking
+                    .blocks
+                    .entry(blocker.clone())
+                    .or_default()
+                    .push(task.task_id.clone());
+            }
+        }
+        blocking
+    }
+
+    /// Ids of the tasks waiting on `task_id`.
+    pub fn blocks(&self, task_id: &str) -> Vec<String> {
+        self.blocks.get(task_id).cloned().unwrap_or_default()
+    }
+
+    /// The task's blockers that are not finished yet.
+    pub fn open_blockers(&self, task: &Task) -> Vec<String> {
+        task.blocked_by
+            .iter()
+            .filter(|id| self.open.contains(*id))
+            .cloned()
+            .collect()
+    }
+}
+
+/// A task as shown in board listings: checklist items are left out and
+/// summarized as progress counts, and comments are only counted.
+#[derive(Debug, Serialize)]
+pub struct TaskCard {
+    #[serde(flatten)]
+    pub task: Task,
+    #[serde(rename = "checklistProgress")]
+    pub checklist_progress: ChecklistProgress,
+    #[serde(rename = "commentCount")]
+    pub comment_count: u64,
+    /// Ids of the tasks this one blocks.
+    pub blocks: Vec<String>,
+    /// Whether any blocker is still open.
+    pub blocked: bool,
+    /// The description rendered to sanitized HTML, when asked for.
+    #[serde(rename = "descriptionHtml", skip_serializing_if = "Option::is_none")]
+    pub description_html: Option<String>,
+}
+
+impl TaskCard {
+    pub fn new(mut task: Task, comment_count: u64, blocking: &Blocking) -> Self {
+        let checklist = std::mem::take(&mut task.checklist);
+        TaskCard {
+            blocks: blocking.blocks(&task.task_id),
+            blocked: !blocking.open_blockers(&task).is_empty(),
+            description_html: None,
+            task,
+            checklist_progress: ChecklistProgress {
+                completed: checklist.iter().filter(|item| item.done).count(),
+                total: checklist.len(),
+            },
+            comment_count,
+        }
+    }
+}
+
+/// A user referenced as `@username` in a comment.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Mention {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    pub username: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Comment {
+    #[serde(rename = "commentId")]
+    pub comment_id: String,
+    #[serde(rename = "boardId")]
+    pub board_id: String,
+    #[serde(rename = "taskId")]
+    pub task_id: String,
+    #[serde(rename = "authorId")]
+    pub author_id: String,
+    pub body: String,
+    /// Board members mentioned in the body, in order of first appearance.
+    #[serde(default)]
+    pub mentions: Vec<Mention>,
+    #[serde(rename = "createdAt", with = "sortable_time")]
+    pub created_at: DateTime<Utc>,
+    #[serde(
+        rename = "editedAt",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "sortable_time::option"
+    )]
+    pub edited_at: Option<DateTime<Utc>>,
+}
+
+/// A new body for a stored comment.
+#[derive(Debug, Serialize, Clone)]
+pub struct CommentUpdate {
+    pub body: String,
+    pub mentions: Vec<Mention>,
+    #[serde(rename = "editedAt", with = "sortable_time")]
+    pub edited_at: DateTime<Utc>,
+}
+
+/// Partial update applied to a stored task. Fields left as `None` are kept.
+#[derive(Debug, Serialize, Default, Clone)]
+pub struct TaskUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rank: Option<String>,
+    /// `Some(None)` clears the start date.
+    #[serde(
+        rename = "startAt",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "sortable_time::nullable::serialize"
+    )]
+    pub start_at: Option<Option<DateTime<Utc>>>,
+    /// `Some(None)` clears the due date.
+    #[serde(
+        rename = "dueAt",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "sortable_time::nullable::serialize"
+    )]
+    pub due_at: Option<Option<DateTime<Utc>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assignees: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    /// `Some(None)` clears the estimate.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<Option<f64>>,
+    #[serde(rename = "blockedBy", skip_serializing_if = "Option::is_none")]
+    pub blocked_by: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checklist: Option<Vec<ChecklistItem>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attachments: Option<Vec<Attachment>>,
+}
+
+impl TaskUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.description.is_none()
+            && self.column.is_none()
+            && self.rank.is_none()
+            && self.start_at.is_none()
+            && self.due_at.is_none()
+            && self.labels.is_none()
+            && self.assignees.is_none()
+            && self.priority.is_none()
+            && self.estimate.is_none()
+            && self.blocked_by.is_none()
+            && self.checklist.is_none()
+            && self.attachments.is_none()
+    }
+
+    pub fn apply(&self, task: &mut Task) {
+        if let Some(title) = &self.title {
+            task.title = title.clone();
+        }
+        if let Some(description) = &self.description {
+            task.description = description.clone();
+        }
+        if let Some(column) = &self.column {
+            task.column = column.clone();
+        }
+        if let Some(rank) = &self.rank {
+            task.rank = rank.clone();
+        }
+        if let Some(start_at) = self.start_at {
+            task.start_at = start_at;
+        }
+        if let Some(due_at) = self.due_at {
+            task.due_at = due_at;
+        }
+        if let Some(labels) = &self.labels {
+            task.labels = labels.clone();
+        }
+        if let Some(assignees) = &self.assignees {
+            task.assignees = assignees.clone();
+        }
+        if let Some(priority) = self.priority {
+            task.priority = priority;
+        }
+        if let Some(estimate) = self.estimate {
+            task.estimate = estimate;
+        }
+        if let Some(blocked_by) = &self.blocked_by {
+            task.blocked_by = blocked_by.clone();
+        }
+        if let Some(checklist) = &self.checklist {
+            task.checklist = checklist.clone();
+        }
+        if let Some(attachments) = &self.attachments {
+            task.attachments = attachments.clone();
+        }
+    }
+}
+
+/// A task kept for reuse on its board. Templates with a `recurrence` are
+/// turned into tasks in the board's first column by
+/// [`crate::jobs::spawn_recurring_tasks`].
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaskTemplate {
+    #[serde(rename = "templateId")]
+    pub template_id: String,
+    #[serde(rename = "boardId")]
+    pub board_id: String,
+    pub title: String,
+    /// Markdown.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// Label ids.
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub priority: Priority,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<f64>,
+    /// Texts of the checklist items each task starts with.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checklist: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<Recurrence>,
+    /// The next occurrence that has no task yet. Unset for templates that
+    /// do not recur.
+    #[serde(
+        rename = "nextRunAt",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "sortable_time::option"
+    )]
+    pub next_run_at: Option<DateTime<Utc>>,
+    #[serde(rename = "createdBy")]
+    pub created_by: String,
+    #[serde(rename = "createdAt", with = "sortable_time")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl TaskTemplate {
+    /// The request that creates a task from the template. Labels that have
+    /// since been deleted from the board are left out.
+    pub fn task_request(&self, board: &Board) -> CreateTaskRequest {
+        CreateTaskRequest {
+            title: self.title.clone(),
+            description: self.description.clone(),
+            start_at: None,
+            due_at: None,
+            labels: self
+                .labels
+                .iter()
+                .filter(|id| board.label(id).is_some())
+                .cloned()
+                .collect(),
+            priority: self.priority,
+            estimate: self.estimate,
+            checklist: self.checklist.clone(),
+        }
+    }
+}
+
+/// Partial update applied to a stored template. Fields left as `None` are
+/// kept.
+#[derive(Debug, Serialize, Default, Clone)]
+pub struct TemplateUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    /// `Some(None)` clears the estimate.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<Option<f64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checklist: Option<Vec<String>>,
+    /// `Some(None)` stops the template from recurring.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<Option<Recurrence>>,
+    #[serde(
+        rename = "nextRunAt",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "sortable_time::nullable::serialize"
+    )]
+    pub next_run_at: Option<Option<DateTime<Utc>>>,
+}
+
+impl TemplateUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.description.is_none()
+            && self.labels.is_none()
+            && self.priority.is_none()
+            && self.estimate.is_none()
+            && self.checklist.is_none()
+            && self.recurrence.is_none()
+            && self.next_run_at.is_none()
+    }
+
+    pub fn apply(&self, template: &mut TaskTemplate) {
+        if let Some(title) = &self.title {
+            template.title = title.clone();
+        }
+        if let Some(description) = &self.description {
+            template.description = description.clone();
+        }
+        if let Some(labels) = &self.labels {
+            template.labels = labels.clone();
+        }
+        if let Some(priority) = self.priority {
+            template.priority = priority;
+        }
+        if let Some(estimate) = self.estimate {
+            template.estimate = estimate;
+        }
+        if let Some(checklist) = &self.checklist {
+            template.checklist = checklist.clone();
+        }
+        if let Some(recurrence) = &self.recurrence {
+            template.recurrence = recurrence.clone();
+        }
+        if let Some(next_run_at) = self.next_run_at {
+            template.next_run_at = next_run_at;
+        }
+    }
+}
+
+/// A saved board layout that new boards can start from. Board templates
+/// belong to the user who saved them.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BoardTemplate {
+    #[serde(rename = "templateId")]
+    pub template_id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// Columns in display order.
+    pub columns: Vec<ColumnTemplate>,
+    #[serde(default)]
+    pub labels: Vec<LabelTemplate>,
+    /// Starter cards, created in this order.
+    #[serde(default)]
+    pub cards: Vec<CardTemplate>,
+    #[serde(rename = "createdBy")]
+    pub created_by: String,
+    #[serde(rename = "createdAt", with = "sortable_time")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ColumnTemplate {
+    pub name: String,
+    #[serde(rename = "wipLimit", default, skip_serializing_if = "Option::is_none")]
+    pub wip_limit: Option<u32>,
+    #[serde(
+        rename = "wipLimitPerAssignee",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub wip_limit_per_assignee: Option<u32>,
+    /// At most one column is marked; the last one is done otherwise.
+    #[serde(default)]
+    pub done: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LabelTemplate {
+    pub name: String,
+    /// `#rrggbb` hex color.
+    pub color: String,
+}
+
+/// A starter card of a board template. Its column and labels are given by
+/// name, since the board they end up on does not exist yet.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CardTemplate {
+    pub title: String,
+    /// Markdown.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// Defaults to the first column.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub priority: Priority,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<f64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checklist: Vec<String>,
+}
+
+impl CardTemplate {
+    /// The request that creates the card, with its labels already turned
+    /// into label ids.
+    pub fn task_request(&self, labels: Vec<String>) -> CreateTaskRequest {
+        CreateTaskRequest {
+            title: self.title.clone(),
+            description: self.description.clone(),
+            start_at: None,
+            due_at: None,
+            labels,
+            priority: self.priority,
+            estimate: self.estimate,
+            checklist: self.checklist.clone(),
+        }
+    }
+}
+
+/// Narrows a board's task listing.
+#[derive(Debug, Default, Clone)]
+pub struct TaskFilter {
+    /// Only tasks due before this time; tasks without a due date never match.
+    pub due_before: Option<DateTime<Utc>>,
+    /// Only tasks carrying this label id.
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TaskQuery {
+    #[serde(rename = "dueBefore", alias = "due_before")]
+    pub due_before: Option<DateTime<Utc>>,
+    /// Only tasks past their due date that are not in the board's last
+    /// column.
+    #[serde(default)]
+    pub overdue: bool,
+    /// Label id or name.
+    pub label: Option<String>,
+    /// Order of the tasks within each column; by rank when omitted.
+    pub sort: Option<TaskSort>,
+    #[serde(default)]
+    pub order: SortOrder,
+    /// Adds each task's description rendered as sanitized HTML.
+    #[serde(default)]
+    pub html: bool,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskSort {
+    Rank,
+    /// Most urgent first when ascending.
+    Priority,
+    /// Smallest first when ascending; tasks without an estimate always come
+    /// last.
+    Estimate,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Places a task in `column`, directly after the task `after` and/or directly
+/// before the task `before`. With neither, the task goes to the end.
+#[derive(Debug, Deserialize)]
+pub struct MoveTaskRequest {
+    pub column: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    /// Finish the task even though some of its blockers are still open.
+    #[serde(rename = "ignoreBlockers", default)]
+    pub ignore_blockers: bool,
+    /// Move the task even though it goes past a WIP limit.
+    #[serde(rename = "overrideWip", default)]
+    pub override_wip: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ColumnTasks {
+    #[serde(rename = "columnId")]
+    pub column_id: String,
+    pub name: String,
+    pub tasks: Vec<TaskCard>,
+    /// Sum of the listed tasks' estimates.
+    #[serde(rename = "estimateTotal")]
+    pub estimate_total: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TasksResponse {
+    pub columns: Vec<ColumnTasks>,
+    /// Tasks whose column no longer exists on the board.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unassigned: Vec<TaskCard>,
+}
+
+/// A board's tasks assigned to the caller, for `/api/me/tasks`.
+#[derive(Debug, Serialize)]
+pub struct BoardTasks {
+    #[serde(rename = "boardId")]
+    pub board_id: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub tasks: TasksResponse,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateColumnRequest {
+    pub name: String,
+    /// Index to insert the column at; appended when omitted.
+    pub position: Option<usize>,
+    #[serde(rename = "wipLimit")]
+    pub wip_limit: Option<u32>,
+    #[serde(rename = "wipLimitPerAssignee")]
+    pub wip_limit_per_assignee: Option<u32>,
+    /// Makes the new column the board's done column.
+    #[serde(default)]
+    pub done: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateColumnRequest {
+    pub name: Option<String>,
+    /// New index of the column within the board.
+    pub position: Option<usize>,
+    /// `null` removes the limit.
+    #[serde(rename = "wipLimit", default, deserialize_with = "nullable")]
+    pub wip_limit: Option<Option<u32>>,
+    /// `null` removes the limit.
+    #[serde(rename = "wipLimitPerAssignee", default, deserialize_with = "nullable")]
+    pub wip_limit_per_assignee: Option<Option<u32>>,
+    /// `true` makes this the board's done column. A board always keeps one,
+    /// so it can only be unmarked by marking another column.
+    pub done: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateChecklistItemRequest {
+    pub text: String,
+    /// Index to insert the item at; appended when omitted.
+    pub position: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateChecklistItemRequest {
+    pub text: Option<String>,
+    pub done: Option<bool>,
+    /// New index of the item within the checklist.
+    pub position: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BlockerRequest {
+    /// The task that has to be finished first.
+    #[serde(rename = "taskId")]
+    pub task_id: String,
+}
+
+/// Both sides of a task's blocking relations.
+#[derive(Debug, Serialize)]
+pub struct BlockersResponse {
+    #[serde(rename = "blockedBy")]
+    pub blocked_by: Vec<Task>,
+    pub blocks: Vec<Task>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommentRequest {
+    pub body: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommentQuery {
+    /// Id of the last comment of the previous page.
+    pub after: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommentsResponse {
+    pub comments: Vec<Comment>,
+    /// Pass as `after` to fetch the next page; absent on the last page.
+    #[serde(rename = "nextCursor", skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateLabelRequest {
+    pub name: String,
+    pub color: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateLabelRequest {
+    pub name: Option<String>,
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteColumnQuery {
+    /// Column that receives the deleted column's tasks.
+    #[serde(rename = "moveTo")]
+    pub move_to: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTaskRequest {
+    /// Older clients send the title as `text`.
+    #[serde(alias = "text")]
+    pub title: String,
+    /// Markdown.
+    #[serde(default)]
+    pub description: String,
+    #[serde(rename = "startAt")]
+    pub start_at: Option<DateTime<Utc>>,
+    #[serde(rename = "dueAt")]
+    pub due_at: Option<DateTime<Utc>>,
+    /// Label ids.
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub priority: Priority,
+    pub estimate: Option<f64>,
+    /// Texts of the checklist items the task starts with.
+    #[serde(default)]
+    pub checklist: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTaskRequest {
+    #[serde(alias = "text")]
+    pub title: Option<String>,
+    /// Markdown; an empty string removes the description.
+    pub description: Option<String>,
+    pub column: Option<String>,
+    /// `null` clears the start date.
+    #[serde(rename = "startAt", default, deserialize_with = "nullable")]
+    pub start_at: Option<Option<DateTime<Utc>>>,
+    /// `null` clears the due date.
+    #[serde(rename = "dueAt", default, deserialize_with = "nullable")]
+    pub due_at: Option<Option<DateTime<Utc>>>,
+    /// Replaces the task's label ids.
+    pub labels: Option<Vec<String>>,
+    /// Replaces the task's assignees, given as user ids.
+    pub assignees: Option<Vec<String>>,
+    pub priority: Option<Priority>,
+    /// `null` clears the estimate.
+    #[serde(default, deserialize_with = "nullable")]
+    pub estimate: Option<Option<f64>>,
+    /// The version being edited, for clients that cannot send `If-Match`.
+    pub version: Option<u64>,
+    /// Finish the task even though some of its blockers are still open.
+    #[serde(rename = "ignoreBlockers", default)]
+    pub ignore_blockers: bool,
+    /// Move the task even though it goes past a WIP limit.
+    #[serde(rename = "overrideWip", default)]
+    pub override_wip: bool,
+}
+
+/// A recurrence rule, either spelled out or as an `RRULE` value.
+#[derive(Debug, Deserialize)]
+pub struct RecurrenceRequest {
+    pub rrule: Option<String>,
+    pub frequency: Option<Frequency>,
+    pub interval: Option<u32>,
+    #[serde(rename = "byDay", default)]
+    pub by_day: Vec<Weekday>,
+    #[serde(rename = "byMonthDay")]
+    pub by_month_day: Option<u32>,
+    /// Defaults to now.
+    #[serde(rename = "startAt")]
+    pub start_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTemplateRequest {
+    pub title: String,
+    /// Markdown.
+    #[serde(default)]
+    pub description: String,
+    /// Label ids.
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub priority: Priority,
+    pub estimate: Option<f64>,
+    /// Texts of the checklist items each task starts with.
+    #[serde(default)]
+    pub checklist: Vec<String>,
+    pub recurrence: Option<RecurrenceRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTemplateRequest {
+    pub title: Option<String>,
+    /// Markdown; an empty string removes the description.
+    pub description: Option<String>,
+    /// Replaces the template's label ids.
+    pub labels: Option<Vec<String>>,
+    pub priority: Option<Priority>,
+    /// `null` clears the estimate.
+    #[serde(default, deserialize_with = "nullable")]
+    pub estimate: Option<Option<f64>>,
+    /// Replaces the checklist item texts.
+    pub checklist: Option<Vec<String>>,
+    /// `null` stops the template from recurring.
+    #[serde(default, deserialize_with = "nullable")]
+    pub recurrence: Option<Option<RecurrenceRequest>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateBoardTemplateRequest {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub columns: Vec<ColumnTemplate>,
+    #[serde(default)]
+    pub labels: Vec<LabelTemplate>,
+    #[serde(default)]
+    pub cards: Vec<CardTemplate>,
+}
+
+/// Names the board made from a board template; both default to the
+/// template's own.
+#[derive(Debug, Deserialize, Default)]
+pub struct InstantiateBoardRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct User {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    /// Lowercase login name, unique across the deployment.
+    pub username: String,
+    /// Argon2 PHC string; never sent to clients.
+    #[serde(rename = "passwordHash")]
+    pub password_hash: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// The parts of a [`User`] that are safe to return from the API.
+#[derive(Debug, Serialize)]
+pub struct UserProfile {
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    pub username: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<&User> for UserProfile {
+    fn from(user: &User) -> Self {
+        UserProfile {
+            user_id: user.user_id.clone(),
+            username: user.username.clone(),
+            created_at: user.created_at,
+        }
+    }
+}
+
+/// A login session. Only the SHA-256 of the bearer token is stored.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Session {
+    #[serde(rename = "tokenHash")]
+    pub token_hash: String,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterRequest {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionResponse {
+    pub token: String,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: DateTime<Utc>,
+    pub user: UserProfile,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenScope {
+    /// `GET` requests only.
+    Read,
+    Write,
+}
+
+/// A personal API token for scripts and CI. Like sessions, only the SHA-256
+/// of the secret is stored.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApiToken {
+    #[serde(rename = "tokenId")]
+    pub token_id: String,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    pub name: String,
+    pub scope: TokenScope,
+    #[serde(rename = "tokenHash")]
+    pub token_hash: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    /// Tokens without an expiry stay valid until revoked.
+    #[serde(rename = "expiresAt")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiTokenRequest {
+    pub name: String,
+    pub scope: TokenScope,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// An [`ApiToken`] without its hash.
+#[derive(Debug, Serialize)]
+pub struct ApiTokenInfo {
+    #[serde(rename = "tokenId")]
+    pub token_id: String,
+    pub name: String,
+    pub scope: TokenScope,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl From<&ApiToken> for ApiTokenInfo {
+    fn from(token: &ApiToken) -> Self {
+        ApiTokenInfo {
+            token_id: token.token_id.clone(),
+            name: token.name.clone(),
+            scope: token.scope,
+            created_at: token.created_at,
+            expires_at: token.expires_at,
+        }
+    }
+}
+
+/// Returned once, when the token is created; the secret cannot be shown again.
+#[derive(Debug, Serialize)]
+pub struct CreatedApiToken {
+    pub token: String,
+    #[serde(flatten)]
+    pub info: ApiTokenInfo,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TaskEventKind {
+    #[serde(rename = "task.created")]
+    Created,
+    #[serde(rename = "task.updated")]
+    Updated,
+    #[serde(rename = "task.moved")]
+    Moved,
+    #[serde(rename = "task.deleted")]
+    Deleted,
+    #[serde(rename = "task.restored")]
+    Restored,
+}
+
+/// Tells a missing field (`None`) apart from an explicit `null`
+/// (`Some(None)`), so requests can clear optional values.
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Writes timestamps as RFC 3339 with exactly three fractional digits, so
+/// stored values sort in time order as plain strings and can be range-queried.
+pub mod sortable_time {
+    use chrono::{DateTime, SecondsFormat, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn format(at: &DateTime<Utc>) -> String {
+        at.to_rfc3339_opts(SecondsFormat::Millis, true)
+    }
+
+    pub fn serialize<S: Serializer>(at: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format(at))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Utc>, D::Error> {
+        DateTime::deserialize(deserializer)
+    }
+
+    /// Timestamps in partial updates, where `Some(None)` clears the value.
+    pub mod nullable {
+        use chrono::{DateTime, Utc};
+        use serde::Serializer;
+
+        pub fn serialize<S: Serializer>(
+            at: &Option<Option<DateTime<Utc>>>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match at {
+                Some(Some(at)) => serializer.serialize_some(&super::format(at)),
+                _ => serializer.serialize_none(),
+            }
+        }
+    }
+
+    /// The same format for optional timestamps.
+    pub mod option {
+        use chrono::{DateTime, Utc};
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(
+            at: &Option<DateTime<Utc>>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match at {
+                Some(at) => serializer.serialize_some(&super::format(at)),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<DateTime<Utc>>, D::Error> {
+            Option::deserialize(deserializer)
+        }
+    }
+}
+
+/// One entry of the append-only task history: who changed which task, when,
+/// and what it looked like before and after.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaskEvent {
+    /// Position in the event log, assigned when the event is stored. Ids only
+    /// ever increase, so clients can resume from the last one they saw.
+    #[serde(default)]
+    pub id: u64,
+    #[serde(rename = "type")]
+    pub kind: TaskEventKind,
+    #[serde(rename = "boardId")]
+    pub board_id: String,
+    #[serde(rename = "taskId")]
+    pub task_id: String,
+    /// The task after the change; absent for deletions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task: Option<Task>,
+    /// The task before the change; absent for creations.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<Task>,
+    /// The user who made the change.
+    #[serde(rename = "actorId")]
+    pub actor_id: String,
+    #[serde(with = "sortable_time")]
+    pub at: DateTime<Utc>,
+    /// The WIP limit the actor chose to go past with this change.
+    #[serde(
+        rename = "wipOverride",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub wip_override: Option<WipExceeded>,
+}
+
+/// A work-in-progress limit that a change would go past.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WipExceeded {
+    #[serde(rename = "columnId")]
+    pub column_id: String,
+    /// Set when the limit is the column's per-assignee one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
+    pub limit: u32,
+    /// Tasks already counting against the limit.
+    pub count: usize,
+}
+
+impl TaskEventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TaskEventKind::Created => "task.created",
+            TaskEventKind::Updated => "task.updated",
+            TaskEventKind::Moved => "task.moved",
+            TaskEventKind::Deleted => "task.deleted",
+            TaskEventKind::Restored => "task.restored",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventStreamQuery {
+    /// Only stream events of this board.
+    #[serde(rename = "boardId")]
+    pub board_id: Option<String>,
+    /// Same as the `Last-Event-ID` header, for clients that cannot set it.
+    #[serde(rename = "lastEventId")]
+    pub last_event_id: Option<u64>,
+}
+
+/// Selects entries of the task history, oldest first.
+#[derive(Debug, Clone)]
+pub struct EventFilter {
+    pub board_id: String,
+    pub task_id: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Only events with a greater id; used to page through results.
+    pub after: Option<u64>,
+    pub limit: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    /// Only events at or after this time.
+    pub from: Option<DateTime<Utc>>,
+    /// Only events before this time.
+    pub to: Option<DateTime<Utc>>,
+    /// Id of the last event of the previous page.
+    pub after: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistoryResponse {
+    pub events: Vec<TaskEvent>,
+    /// Pass as `after` to fetch the next page; absent on the last page.
+    #[serde(rename = "nextCursor", skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<u64>,
+}
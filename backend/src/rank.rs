@@ -0,0 +1,212 @@
+//! Lexicographic ranks for ordering tasks within a column.
+//!
+//! A rank is a base-36 fraction written without the leading "0." and without
+//! trailing zeros, so plain string comparison orders ranks numerically. There
+//! is always room for another rank between two different ones, which lets a
+//! move rewrite only the task being moved. The empty string is the lowest
+//! rank; tasks stored before ranks existed carry it.
+
+const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+const BASE: usize = DIGITS.len();
+
+/// Ranks longer than this are a sign that one spot has been inserted into
+/// many times; the column should be renumbered with [`spread`].
+pub const MAX_LEN: usize = 32;
+
+fn digit(c: u8) -> usize {
+    DIGITS
+        .iter()
+        .position(|&d| d == c)
+        .expect("rank contains a character outside the rank alphabet")
+}
+
+/// Returns a rank strictly between `lo` and `hi`, where `hi == None` means
+/// "after everything". Callers must ensure `lo < hi`.
+pub fn between(lo: &str, hi: Option<&str>) -> String {
+    debug_assert!(!matches!(hi, Some(hi) if lo >= hi));
+    let mut rank = Vec::new();
+    midpoint(lo.as_bytes(), hi.map(str::as_bytes), &mut rank);
+    String::from_utf8(rank).expect("rank alphabet is ASCII")
+}
+
+fn midpoint(lo: &[u8], hi: Option<&[u8]>, out: &mut Vec<u8>) {
+    if let Some(hi) = hi {
+        // Copy the shared prefix, treating a missing digit of `lo` as zero.
+        let shared = hi
+            .iter()
+            .enumerate()
+            .take_while(|&(i, &h)| lo.get(i).copied().unwrap_or(b'0') == h)
+            .count();
+        if shared > 0 {
+            out.extend_from_slice(&hi[..shared]);
+            let lo = lo.get(shared..).unwrap_or(&[]);
+            return midpoint(lo, Some(&hi[shared..]), out);
+        }
+    }
+
+    let lo_digit = lo.first().map_or(0, |&c| digit(c));
+    let hi_digit = hi.and_then(|hi| hi.first()).map_or(BASE, |&c| digit(c));
+
+    if hi_digit - lo_digit > 1 {
+        // Step by one digit at the open ends so that repeatedly appending or
+        // prepending grows ranks slowly; split the gap everywhere else.
+        let next = if hi.is_none() {
+            lo_digit + 1
+        } else if lo.is_empty() {
+            hi_digit - 1
+        } else {
+            (lo_digit + hi_digit) / 2
+        };
+        out.push(DIGITS[next]);
+    } else if let Some(hi) = hi.filter(|hi| hi.len() > 1) {
+        // The first digit of `hi` alone already sorts between the two.
+        out.push(hi[0]);
+    } else if lo.is_empty() {
+        // Prepending below a single-digit rank: descend from the top of the
+        // next level down.
+        out.push(DIGITS[0]);
+        out.push(DIGITS[BASE - 1]);
+    } else {
+        out.push(DIGITS[lo_digit]);
+        midpoint(&lo[1..], None, out);
+    }
+}
+
+/// Returns `count` evenly spaced, increasing ranks. Used to renumber a
+/// column whose ranks collide.
+pub fn spread(count: usize) -> Vec<String> {
+    let mut width = 1;
+    let mut space = BASE;
+    while space <= count {
+        width += 1;
+        space *= BASE;
+    }
+    let step = space / (count + 1);
+
+    (1..=count)
+        .map(|i| {
+            let mut value = i * step;
+            let mut rank = vec![b'0'; width];
+            for slot in rank.iter_mut().rev() {
+                *slot = DIGITS[value % BASE];
+                value /= BASE;
+            }
+            while rank.last() == Some(&b'0') {
+                rank.pop();
+            }
+            String::from_utf8(rank).expect("rank alphabet is ASCII")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Asserts that `between` lands strictly inside the bounds and keeps the
+    /// rank format, and returns the new rank.
+    fn check_between(lo: &str, hi: Option<&str>) -> String {
+        let rank = between(lo, hi);
+        assert!(lo < rank.as_str(), "{:?} is not above {:?}", rank, lo);
+        if let Some(hi) = hi {
+            assert!(rank.as_str() < hi, "{:?} is not below {:?}", rank, hi);
+        }
+        assert!(!rank.ends_with('0'), "{:?} has a trailing zero", rank);
+        assert!(rank.bytes().all(|c| DIGITS.contains(&c)));
+        rank
+    }
+
+    #[test]
+    fn between_open_and_empty_bounds() {
+        assert_eq!(check_between("", None), "1");
+        assert_eq!(check_between("", Some("1")), "0z");
+        assert_eq!(check_between("", Some("0z")), "0y");
+        assert_eq!(check_between("", Some("01")), "00z");
+        assert_eq!(check_between("z", None), "z1");
+        assert_eq!(check_between("zzz", None), "zzz1");
+        check_between("", Some("00001"));
+    }
+
+    #[test]
+    fn between_adjacent_digits() {
+        assert_eq!(check_between("a", Some("b")), "a1");
+        assert_eq!(check_between("0", Some("1")), "01");
+        assert_eq!(check_between("y", Some("z")), "y1");
+        assert_eq!(check_between("az", Some("b")), "az1");
+        assert_eq!(check_between("a", Some("a1")), "a0z");
+        assert_eq!(check_between("a", Some("c")), "b");
+        check_between("a0z", Some("a1"));
+        check_between("1", Some("10001"));
+    }
+
+    #[test]
+    fn between_at_max_len() {
+        let lo = "y".repeat(MAX_LEN);
+        let hi = "z".repeat(MAX_LEN);
+        // A short rank fits between long ones.
+        assert_eq!(check_between(&lo, Some(&hi)), "z");
+
+        let lo = format!("{}1", "a".repeat(MAX_LEN - 1));
+        let hi = format!("{}2", "a".repeat(MAX_LEN - 1));
+        assert!(check_between(&lo, Some(&hi)).len() > MAX_LEN);
+        check_between(&"z".repeat(MAX_LEN), None);
+        check_between("", Some(&format!("{}1", "0".repeat(MAX_LEN - 1))));
+    }
+
+    #[test]
+    fn repeated_inserts_stay_ordered() {
+        // The same spot, over and over, until ranks pass MAX_LEN.
+        let (mut lo, hi) = (String::from("a"), String::from("b"));
+        while lo.len() <= MAX_LEN {
+            lo = check_between(&lo, Some(&hi));
+        }
+        let (lo, mut hi) = (String::from("a"), String::from("b"));
+        while hi.len() <= MAX_LEN {
+            hi = check_between(&lo, Some(&hi));
+        }
+
+        // Appending and prepending grow ranks slowly.
+        let mut last = String::new();
+        for _ in 0..100 {
+            last = check_between(&last, None);
+        }
+        assert!(last.len() <= 4);
+        let mut first = String::from("1");
+        for _ in 0..100 {
+            first = check_between("", Some(&first));
+        }
+        assert!(first.len() <= 5);
+
+        // Random positions in a growing column.
+        let mut ranks: Vec<String> = Vec::new();
+        let mut seed: u64 = 7;
+        for _ in 0..2000 {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let at = (seed >> 33) as usize % (ranks.len() + 1);
+            let lo = if at == 0 { "" } else { ranks[at - 1].as_str() };
+            let hi = ranks.get(at).map(String::as_str);
+            let rank = check_between(lo, hi);
+            ranks.insert(at, rank);
+        }
+        assert!(ranks.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn spread_is_strictly_increasing() {
+        for count in [0, 1, 2, 35, 36, 37, 1000, BASE * BASE, BASE * BASE + 1] {
+            let ranks = spread(count);
+            assert_eq!(ranks.len(), count);
+            assert!(ranks.iter().all(|rank| !rank.is_empty()));
+            assert!(ranks.windows(2).all(|pair| pair[0] < pair[1]));
+            for rank in &ranks {
+                assert!(!rank.ends_with('0'), "{:?} has a trailing zero", rank);
+                assert!(rank.len() <= MAX_LEN);
+            }
+            if let Some(last) = ranks.last() {
+                check_between(last, None);
+            }
+        }
+    }
+}
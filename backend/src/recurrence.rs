@@ -0,0 +1,429 @@
+//! Recurrence rules for task templates.
+//!
+//! Rules cover the daily, weekly and monthly part of RFC 5545 `RRULE`s:
+//! `FREQ`, `INTERVAL`, `BYDAY` (plain weekdays, weekly rules only) and
+//! `BYMONTHDAY` (one positive day, monthly rules only). Occurrences fall on
+//! the time of day of the rule's `startAt` and are worked out in UTC.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::sortable_time;
+
+/// Largest accepted `interval`.
+pub const MAX_INTERVAL: u32 = 366;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// Days of the week, written as in `BYDAY`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Weekday {
+    Mo,
+    Tu,
+    We,
+    Th,
+    Fr,
+    Sa,
+    Su,
+}
+
+impl Weekday {
+    fn parse(day: &str) -> Option<Self> {
+        match day {
+            "MO" => Some(Weekday::Mo),
+            "TU" => Some(Weekday::Tu),
+            "WE" => Some(Weekday::We),
+            "TH" => Some(Weekday::Th),
+            "FR" => Some(Weekday::Fr),
+            "SA" => Some(Weekday::Sa),
+            "SU" => Some(Weekday::Su),
+            _ => None,
+        }
+    }
+}
+
+impl From<chrono::Weekday> for Weekday {
+    fn from(day: chrono::Weekday) -> Self {
+        match day {
+            chrono::Weekday::Mon => Weekday::Mo,
+            chrono::Weekday::Tue => Weekday::Tu,
+            chrono::Weekday::Wed => Weekday::We,
+            chrono::Weekday::Thu => Weekday::Th,
+            chrono::Weekday::Fri => Weekday::Fr,
+            chrono::Weekday::Sat => Weekday::Sa,
+            chrono::Weekday::Sun => Weekday::Su,
+        }
+    }
+}
+
+fn one() -> u32 {
+    1
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    /// Every how many days, weeks or months.
+    #[serde(default = "one")]
+    pub interval: u32,
+    /// Weekdays of a weekly rule; defaults to the weekday of `start_at`.
+    #[serde(rename = "byDay", default, skip_serializing_if = "Vec::is_empty")]
+    pub by_day: Vec<Weekday>,
+    /// Day of the month of a monthly rule; defaults to the day of `start_at`.
+    /// Months without that day are skipped.
+    #[serde(
+        rename = "byMonthDay",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub by_month_day: Option<u32>,
+    /// No occurrence is earlier than this, and all fall on its time of day.
+    #[serde(rename = "startAt", with = "sortable_time")]
+    pub start_at: DateTime<Utc>,
+}
+
+impl Recurrence {
+    /// Builds a rule from an `RRULE` value such as
+    /// `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH`, with or without the `RRULE:`
+    /// prefix.
+    pub fn parse_rrule(rule: &str, start_at: DateTime<Utc>) -> Result<Self, String> {
+        let rule = rule.trim();
+        let rule = rule.strip_prefix("RRULE:").unwrap_or(rule);
+
+        let mut frequency = None;
+        let mut interval = 1;
+        let mut by_day = Vec::new();
+        let mut by_month_day = None;
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let Some((key, value)) = part.split_once('=') else {
+                return Err(format!("Malformed RRULE part '{}'", part));
+            };
+            let invalid = || format!("Invalid {} '{}'", key, value);
+            match key {
+                "FREQ" => {
+                    frequency = Some(match value {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        _ => return Err(format!("Unsupported FREQ '{}'", value)),
+                    })
+                }
+                "INTERVAL" => interval = value.parse().map_err(|_| invalid())?,
+                "BYDAY" => {
+                    by_day = value
+                        .split(',')
+                        .map(Weekday::parse)
+                        .collect::<Option<_>>()
+                        .ok_or_else(invalid)?
+                }
+                "BYMONTHDAY" => by_month_day = Some(value.parse().map_err(|_| invalid())?),
+                _ => return Err(format!("Unsupported RRULE part '{}'", key)),
+            }
+        }
+
+        let recurrence = Recurrence {
+            frequency: frequency.ok_or("RRULE must have a FREQ")?,
+            interval,
+            by_day,
+            by_month_day,
+            start_at,
+        };
+        recurrence.validate()?;
+        Ok(recurrence)
+    }
+
+    /// Checks that the parts fit together and that the rule matches at least
+    /// one date.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=MAX_INTERVAL).contains(&self.interval) {
+            return Err(format!("interval must be between 1 and {}", MAX_INTERVAL));
+        }
+        if !self.by_day.is_empty() && self.frequency != Frequency::Weekly {
+            return Err("byDay only applies to weekly rules".to_string());
+        }
+        if let Some(day) = self.by_month_day {
+            if self.frequency != Frequency::Monthly {
+                return Err("byMonthDay only applies to monthly rules".to_string());
+            }
+            if !(1..=31).contains(&day) {
+                return Err("byMonthDay must be between 1 and 31".to_string());
+            }
+        }
+        if self.first().is_none() {
+            return Err("The rule never matches a date".to_string());
+        }
+        Ok(())
+    }
+
+    /// The earliest occurrence.
+    pub fn first(&self) -> Option<DateTime<Utc>> {
+        self.next_from(self.start_at)
+    }
+
+    /// The earliest occurrence at or after `from`.
+    pub fn next_from(&self, from: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.next_after(from - Duration::nanoseconds(1))
+    }
+
+    /// The earliest occurrence strictly after `after`, or `None` if there is
+    /// none within reach.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = self.start_at.date_naive();
+        let interval = i64::from(self.interval);
+        let at = |day: NaiveDate| Utc.from_utc_datetime(&day.and_time(self.start_at.time()));
+        let fits = |at: DateTime<Utc>| at > after && at >= self.start_at;
+        // No occurrence falls on a day before this one.
+        let from = after.date_naive().max(start);
+
+        match self.frequency {
+            Frequency::Daily => {
+                let periods = ((from - start).num_days() + interval - 1) / interval;
+                (periods..periods + 2)
+                    .map(|n| at(start + Duration::days(n * interval)))
+                    .find(|&at| fits(at))
+            }
+            Frequency::Weekly => {
+                let days = if self.by_day.is_empty() {
+                    vec![Weekday::from(start.weekday())]
+                } else {
+                    self.by_day.clone()
+                };
+                let first_monday =
+                    start - Duration::days(i64::from(start.weekday().num_days_from_monday()));
+                (0..7 * (interval + 1))
+                    .map(|n| from + Duration::days(n))
+                    .filter(|day| (*day - first_monday).num_days() / 7 % interval == 0)
+                    .filter(|day| days.contains(&Weekday::from(day.weekday())))
+                    .map(at)
+                    .find(|&at| fits(at))
+            }
+            Frequency::Monthly => {
+                let day = self.by_month_day.unwrap_or(start.day());
+                let month_index =
+                    |date: NaiveDate| i64::from(date.year()) * 12 + i64::from(date.month0());
+                let first_month = month_index(start);
+                let periods = (month_index(from) - first_month + interval - 1) / interval;
+                // Enough periods to reach the next February 29th.
+                (periods..periods + 100)
+                    .map(|n| first_month + n * interval)
+                    .filter_map(|month| {
+                        let year = i32::try_from(month.div_euclid(12)).ok()?;
+                        NaiveDate::from_ymd_opt(year, month.rem_euclid(12) as u32 + 1, day)
+                    })
+                    .map(at)
+                    .find(|&at| fits(at))
+            }
+        }
+    }
+
+    /// The latest occurrence at or before `now`, starting the search from
+    /// the occurrence at `due`. Occurrences missed while the server was down
+    /// are skipped over rather than caught up one by one.
+    pub fn latest_until(&self, due: DateTime<Utc>, now: DateTime<Utc>) -> DateTime<Utc> {
+        let mut latest = due;
+        while let Some(next) = self.next_after(latest).filter(|&next| next <= now) {
+            latest = next;
+        }
+        latest
+    }
+}
+
+/// Drops the parts of `at` below a second, so stored start times compare
+/// equal to the occurrences computed from them.
+pub fn whole_seconds(at: DateTime<Utc>) -> DateTime<Utc> {
+    at.with_nanosecond(0).unwrap_or(at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(at: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(at)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn rule(rrule: &str, start_at: &str) -> Recurrence {
+        Recurrence::parse_rrule(rrule, time(start_at)).unwrap()
+    }
+
+    /// The first `count` occurrences, as RFC 3339 strings.
+    fn occurrences(rule: &Recurrence, count: usize) -> Vec<String> {
+        let mut next = rule.first();
+        let mut found = Vec::new();
+        while let Some(at) = next.filter(|_| found.len() < count) {
+            found.push(at.to_rfc3339());
+            next = rule.next_after(at);
+        }
+        found
+    }
+
+    #[test]
+    fn weekly_interval_with_by_day() {
+        // Wednesday; weeks count from its Monday, so that Monday is skipped
+        // for being before the start.
+        let every_other = rule("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH", "2024-01-03T09:00:00Z");
+        assert_eq!(
+            occurrences(&every_other, 5),
+            [
+                "2024-01-04T09:00:00+00:00",
+                "2024-01-15T09:00:00+00:00",
+                "2024-01-18T09:00:00+00:00",
+                "2024-01-29T09:00:00+00:00",
+                "2024-02-01T09:00:00+00:00",
+            ]
+        );
+
+        let across_new_year = rule("FREQ=WEEKLY;INTERVAL=2;BYDAY=SU", "2024-12-25T09:00:00Z");
+        assert_eq!(
+            occurrences(&across_new_year, 3),
+            [
+                "2024-12-29T09:00:00+00:00",
+                "2025-01-12T09:00:00+00:00",
+                "2025-01-26T09:00:00+00:00",
+            ]
+        );
+
+        let start_weekday = rule("FREQ=WEEKLY;INTERVAL=3", "2024-01-03T09:00:00Z");
+        assert_eq!(
+            occurrences(&start_weekday, 3),
+            [
+                "2024-01-03T09:00:00+00:00",
+                "2024-01-24T09:00:00+00:00",
+                "2024-02-14T09:00:00+00:00",
+            ]
+        );
+    }
+
+    #[test]
+    fn monthly_skips_months_without_the_day() {
+        let on_31st = rule("FREQ=MONTHLY;BYMONTHDAY=31", "2024-01-10T08:00:00Z");
+        assert_eq!(
+            occurrences(&on_31st, 5),
+            [
+                "2024-01-31T08:00:00+00:00",
+                "2024-03-31T08:00:00+00:00",
+                "2024-05-31T08:00:00+00:00",
+                "2024-07-31T08:00:00+00:00",
+                "2024-08-31T08:00:00+00:00",
+            ]
+        );
+
+        let every_other = rule(
+            "FREQ=MONTHLY;INTERVAL=2;BYMONTHDAY=31",
+            "2024-02-01T08:00:00Z",
+        );
+        assert_eq!(
+            occurrences(&every_other, 4),
+            [
+                "2024-08-31T08:00:00+00:00",
+                "2024-10-31T08:00:00+00:00",
+                "2024-12-31T08:00:00+00:00",
+                "2025-08-31T08:00:00+00:00",
+            ]
+        );
+
+        let on_29th = rule("FREQ=MONTHLY;BYMONTHDAY=29", "2023-01-30T08:00:00Z");
+        assert_eq!(
+            occurrences(&on_29th, 2),
+            ["2023-03-29T08:00:00+00:00", "2023-04-29T08:00:00+00:00"]
+        );
+        let leap_day = rule(
+            "FREQ=MONTHLY;INTERVAL=12;BYMONTHDAY=29",
+            "2023-02-01T08:00:00Z",
+        );
+        assert_eq!(
+            occurrences(&leap_day, 2),
+            ["2024-02-29T08:00:00+00:00", "2028-02-29T08:00:00+00:00"]
+        );
+
+        // Without BYMONTHDAY the day of the start is kept.
+        let from_start = rule("FREQ=MONTHLY", "2024-01-31T08:00:00Z");
+        assert_eq!(
+            occurrences(&from_start, 2),
+            ["2024-01-31T08:00:00+00:00", "2024-03-31T08:00:00+00:00"]
+        );
+    }
+
+    #[test]
+    fn rules_that_never_match_are_refused() {
+        let start = time("2024-02-01T08:00:00Z");
+        assert!(Recurrence::parse_rrule("FREQ=MONTHLY;INTERVAL=12;BYMONTHDAY=30", start).is_err());
+        assert!(Recurrence::parse_rrule("FREQ=MONTHLY;BYDAY=MO", start).is_err());
+        assert!(Recurrence::parse_rrule("FREQ=WEEKLY;BYMONTHDAY=1", start).is_err());
+        assert!(Recurrence::parse_rrule("FREQ=DAILY;INTERVAL=0", start).is_err());
+        assert!(Recurrence::parse_rrule("INTERVAL=2", start).is_err());
+    }
+
+    #[test]
+    fn start_at_in_the_past() {
+        let daily = rule("FREQ=DAILY;INTERVAL=3", "2020-01-06T10:00:00Z");
+        assert_eq!(daily.first(), Some(time("2020-01-06T10:00:00Z")));
+        // 2024-03-01 is 1516 days after the start, one past a period.
+        assert_eq!(
+            daily.next_from(time("2024-03-01T12:00:00Z")),
+            Some(time("2024-03-03T10:00:00Z"))
+        );
+        assert_eq!(
+            daily.next_from(time("2024-03-03T10:00:00Z")),
+            Some(time("2024-03-03T10:00:00Z"))
+        );
+        assert_eq!(
+            daily.next_after(time("2024-03-03T10:00:00Z")),
+            Some(time("2024-03-06T10:00:00Z"))
+        );
+
+        // 2025-06-02 starts the 74th week after the start's.
+        let weekly = rule("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH", "2024-01-03T09:00:00Z");
+        assert_eq!(
+            weekly.next_after(time("2025-06-01T00:00:00Z")),
+            Some(time("2025-06-02T09:00:00Z"))
+        );
+        assert_eq!(
+            weekly.next_after(time("2025-06-05T09:00:00Z")),
+            Some(time("2025-06-16T09:00:00Z"))
+        );
+
+        let monthly = rule("FREQ=MONTHLY", "2023-05-15T09:00:00Z");
+        assert_eq!(
+            monthly.next_after(time("2024-03-15T09:00:00Z")),
+            Some(time("2024-04-15T09:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn latest_until_collapses_missed_runs() {
+        let daily = rule("FREQ=DAILY", "2024-01-01T09:00:00Z");
+        let due = time("2024-01-01T09:00:00Z");
+        assert_eq!(
+            daily.latest_until(due, time("2024-01-10T08:59:59Z")),
+            time("2024-01-09T09:00:00Z")
+        );
+        assert_eq!(
+            daily.latest_until(due, time("2024-01-10T09:00:00Z")),
+            time("2024-01-10T09:00:00Z")
+        );
+        // Nothing missed: the due occurrence itself.
+        assert_eq!(daily.latest_until(due, time("2024-01-01T12:00:00Z")), due);
+
+        let weekly = rule("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH", "2024-01-03T09:00:00Z");
+        assert_eq!(
+            weekly.latest_until(time("2024-01-04T09:00:00Z"), time("2024-02-05T08:00:00Z")),
+            time("2024-02-01T09:00:00Z")
+        );
+
+        let monthly = rule("FREQ=MONTHLY;BYMONTHDAY=31", "2024-01-10T08:00:00Z");
+        assert_eq!(
+            monthly.latest_until(time("2024-01-31T08:00:00Z"), time("2024-06-15T00:00:00Z")),
+            time("2024-05-31T08:00:00Z")
+        );
+    }
+}
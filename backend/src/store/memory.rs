@@ -0,0 +1,646 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::{
+    ApiTokenStore, BoardStore, CommentStore, EventStore, SessionStore, StoreError, StoreResult,
+    TaskStore, TemplateStore, UserStore,
+};
+use crate::models::{
+    ApiToken, Board, BoardTemplate, BoardUpdate, Comment, CommentUpdate, EventFilter, Session,
+    Task, TaskEvent, TaskFilter, TaskTemplate, TaskUpdate, TemplateUpdate, User,
+};
+
+/// Keeps everything in process memory. Data is lost on restart, which makes
+/// it a good fit for tests and demos.
+#[derive(Default)]
+pub struct MemoryStore {
+    boards: Mutex<Vec<Board>>,
+    tasks: Mutex<Vec<Task>>,
+    comments: Mutex<Vec<Comment>>,
+    templates: Mutex<Vec<TaskTemplate>>,
+    board_templates: Mutex<Vec<BoardTemplate>>,
+    users: Mutex<Vec<User>>,
+    sessions: Mutex<Vec<Session>>,
+    api_tokens: Mutex<Vec<ApiToken>>,
+    events: Mutex<Vec<TaskEvent>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn live_task(task: &Task, board_id: &str, task_id: &str) -> bool {
+    task.board_id == board_id && task.task_id == task_id && task.deleted_at.is_none()
+}
+
+fn trashed_task(task: &Task, board_id: &str, task_id: &str) -> bool {
+    task.board_id == board_id && task.task_id == task_id && task.deleted_at.is_some()
+}
+
+fn matches_task_filter(filter: &TaskFilter, task: &Task) -> bool {
+    if let Some(before) = filter.due_before {
+        if !matches!(task.due_at, Some(due_at) if due_at < before) {
+            return false;
+        }
+    }
+    if let Some(label) = &filter.label {
+        if !task.labels.contains(label) {
+            return false;
+        }
+    }
+    true
+}
+
+fn task_comment(comment: &Comment, board_id: &str, task_id: &str, comment_id: &str) -> bool {
+    comment.board_id == board_id && comment.task_id == task_id && comment.comment_id == comment_id
+}
+
+fn version_matches(task: &Task, expected_version: Option<u64>) -> bool {
+    !matches!(expected_version, Some(version) if version != task.version)
+}
+
+#[async_trait]
+impl TaskStore for MemoryStore {
+    async fn list_tasks(&self, board_id: &str) -> StoreResult<Vec<Task>> {
+        let tasks = self.tasks.lock().unwrap();
+        Ok(tasks
+            .iter()
+            .filter(|t| t.board_id == board_id && t.deleted_at.is_none())
+            .cloned()
+            .collect())
+    }
+
+    async fn find_tasks(&self, board_id: &str, filter: &TaskFilter) -> StoreResult<Vec<Task>> {
+        let tasks = self.tasks.lock().unwrap();
+        Ok(tasks
+            .iter()
+            .filter(|t| t.board_id == board_id && t.deleted_at.is_none())
+            .filter(|t| matches_task_filter(filter, t))
+            .cloned()
+            .collect())
+    }
+
+    async fn list_assigned_tasks(&self, user_id: &str) -> StoreResult<Vec<Task>> {
+        let tasks = self.tasks.lock().unwrap();
+        Ok(tasks
+            .iter()
+            .filter(|t| t.deleted_at.is_none() && t.assignees.iter().any(|a| a == user_id))
+            .cloned()
+            .collect())
+    }
+
+    async fn get_task(&self, board_id: &str, task_id: &str) -> StoreResult<Option<Task>> {
+        let tasks = self.tasks.lock().unwrap();
+        Ok(tasks
+            .iter()
+            .find(|t| live_task(t, board_id, task_id))
+            .cloned())
+    }
+
+    async fn create_task(&self, task: Task) -> StoreResult<Task> {
+        let mut tasks = self.tasks.lock().unwrap();
+        if tasks.iter().any(|t| t.task_id == task.task_id) {
+            return Err(StoreError::Conflict(format!(
+                "duplicate taskId {}",
+                task.task_id
+            )));
+        }
+        tasks.push(task.clone());
+        Ok(task)
+    }
+
+    async fn update_task(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        update: &TaskUpdate,
+        expected_version: Option<u64>,
+    ) -> StoreResult<Option<Task>> {
+        let mut tasks = self.tasks.lock().unwrap();
+        Ok(tasks
+            .iter_mut()
+            .find(|t| live_task(t, board_id, task_id))
+            .filter(|t| version_matches(t, expected_version))
+            .map(|task| {
+                update.apply(task);
+                task.version += 1;
+                task.clone()
+            }))
+    }
+
+    async fn move_column_tasks(
+        &self,
+        board_id: &str,
+        from: &str,
+        to: &str,
+    ) -> StoreResult<Vec<(Task, Task)>> {
+        let mut tasks = self.tasks.lock().unwrap();
+        let mut moved = Vec::new();
+        for task in tasks
+            .iter_mut()
+            .filter(|t| t.board_id == board_id && t.column == from)
+        {
+            let before = task.clone();
+            task.column = to.to_string();
+            task.version += 1;
+            moved.push((before, task.clone()));
+        }
+        Ok(moved)
+    }
+
+    async fn remove_label(&self, board_id: &str, label_id: &str) -> StoreResult<Vec<(Task, Task)>> {
+        let mut tasks = self.tasks.lock().unwrap();
+        let mut removed = Vec::new();
+        for task in tasks
+            .iter_mut()
+            .filter(|t| t.board_id == board_id && t.labels.iter().any(|l| l == label_id))
+        {
+            let before = task.clone();
+            task.labels.retain(|l| l != label_id);
+            task.version += 1;
+            removed.push((before, task.clone()));
+        }
+        Ok(removed)
+    }
+
+    async fn trash_task(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        deleted_at: DateTime<Utc>,
+        expected_version: Option<u64>,
+    ) -> StoreResult<Option<Task>> {
+        let mut tasks = self.tasks.lock().unwrap();
+        Ok(tasks
+            .iter_mut()
+            .find(|t| live_task(t, board_id, task_id))
+            .filter(|t| version_matches(t, expected_version))
+            .map(|task| {
+                task.deleted_at = Some(deleted_at);
+                task.version += 1;
+                task.clone()
+            }))
+    }
+
+    async fn list_trash(&self, board_id: &str) -> StoreResult<Vec<Task>> {
+        let tasks = self.tasks.lock().unwrap();
+        Ok(tasks
+            .iter()
+            .filter(|t| t.board_id == board_id && t.deleted_at.is_some())
+            .cloned()
+            .collect())
+    }
+
+    async fn restore_task(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        update: &TaskUpdate,
+    ) -> StoreResult<Option<Task>> {
+        let mut tasks = self.tasks.lock().unwrap();
+        Ok(tasks
+            .iter_mut()
+            .find(|t| trashed_task(t, board_id, task_id))
+            .map(|task| {
+                task.deleted_at = None;
+                update.apply(task);
+                task.version += 1;
+                task.clone()
+            }))
+    }
+
+    async fn purge_task(&self, board_id: &str, task_id: &str) -> StoreResult<Option<Task>> {
+        let mut tasks = self.tasks.lock().unwrap();
+        let Some(index) = tasks
+            .iter()
+            .position(|t| trashed_task(t, board_id, task_id))
+        else {
+            return Ok(None);
+        };
+        let task = tasks.remove(index);
+
+        let mut comments = self.comments.lock().unwrap();
+        comments.retain(|c| c.board_id != board_id || c.task_id != task_id);
+        Ok(Some(task))
+    }
+
+    async fn purge_trash(&self, deleted_before: DateTime<Utc>) -> StoreResult<Vec<Task>> {
+        let mut tasks = self.tasks.lock().unwrap();
+        let (purged, kept): (Vec<Task>, Vec<Task>) = tasks
+            .drain(..)
+            .partition(|t| matches!(t.deleted_at, Some(at) if at < deleted_before));
+        *tasks = kept;
+
+        let mut comments = self.comments.lock().unwrap();
+        comments.retain(|c| {
+            !purged
+                .iter()
+                .any(|t| t.board_id == c.board_id && t.task_id == c.task_id)
+        });
+        Ok(purged)
+    }
+}
+
+#[async_trait]
+impl BoardStore for MemoryStore {
+    async fn list_boards(&self) -> StoreResult<Vec<Board>> {
+        Ok(self.boards.lock().unwrap().clone())
+    }
+
+    async fn get_board(&self, board_id: &str) -> StoreResult<Option<Board>> {
+        let boards = self.boards.lock().unwrap();
+        Ok(boards.iter().find(|b| b.board_id == board_id).cloned())
+    }
+
+    async fn create_board(&self, board: Board) -> StoreResult<Board> {
+        self.boards.lock().unwrap().push(board.clone());
+        Ok(board)
+    }
+
+    async fn update_board(
+        &self,
+        board_id: &str,
+        update: &BoardUpdate,
+        expected_version: Option<u64>,
+    ) -> StoreResult<Option<Board>> {
+        let mut boards = self.boards.lock().unwrap();
+        Ok(boards
+            .iter_mut()
+            .find(|b| b.board_id == board_id)
+            .filter(|b| !matches!(expected_version, Some(version) if version != b.version))
+            .map(|board| {
+                update.apply(board);
+                board.version += 1;
+                board.clone()
+            }))
+    }
+
+    async fn delete_board(&self, board_id: &str) -> StoreResult<Option<Vec<Task>>> {
+        let mut boards = self.boards.lock().unwrap();
+        let Some(index) = boards.iter().position(|b| b.board_id == board_id) else {
+            return Ok(None);
+        };
+        boards.remove(index);
+
+        let mut tasks = self.tasks.lock().unwrap();
+        let (deleted, kept): (Vec<Task>, Vec<Task>) =
+            tasks.drain(..).partition(|t| t.board_id == board_id);
+        *tasks = kept;
+
+        let mut comments = self.comments.lock().unwrap();
+        comments.retain(|c| c.board_id != board_id);
+
+        let mut templates = self.templates.lock().unwrap();
+        templates.retain(|t| t.board_id != board_id);
+        Ok(Some(deleted))
+    }
+}
+
+#[async_trait]
+impl CommentStore for MemoryStore {
+    async fn create_comment(&self, comment: Comment) -> StoreResult<Comment> {
+        let mut comments = self.comments.lock().unwrap();
+        if comments.iter().any(|c| c.comment_id == comment.comment_id) {
+            return Err(StoreError::Conflict(format!(
+                "duplicate comment id {}",
+                comment.comment_id
+            )));
+        }
+        comments.push(comment.clone());
+        Ok(comment)
+    }
+
+    async fn get_comment(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        comment_id: &str,
+    ) -> StoreResult<Option<Comment>> {
+        let comments = self.comments.lock().unwrap();
+        Ok(comments
+            .iter()
+            .find(|c| task_comment(c, board_id, task_id, comment_id))
+            .cloned())
+    }
+
+    async fn update_comment(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        comment_id: &str,
+        update: &CommentUpdate,
+    ) -> StoreResult<Option<Comment>> {
+        let mut comments = self.comments.lock().unwrap();
+        let Some(comment) = comments
+            .iter_mut()
+            .find(|c| task_comment(c, board_id, task_id, comment_id))
+        else {
+            return Ok(None);
+        };
+        comment.body = update.body.clone();
+        comment.mentions = update.mentions.clone();
+        comment.edited_at = Some(update.edited_at);
+        Ok(Some(comment.clone()))
+    }
+
+    async fn delete_comment(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        comment_id: &str,
+    ) -> StoreResult<bool> {
+        let mut comments = self.comments.lock().unwrap();
+        let before = comments.len();
+        comments.retain(|c| !task_comment(c, board_id, task_id, comment_id));
+        Ok(comments.len() < before)
+    }
+
+    async fn list_comments(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        after: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<Vec<Comment>> {
+        let comments = self.comments.lock().unwrap();
+        let mut page: Vec<Comment> = comments
+            .iter()
+            .filter(|c| c.board_id == board_id && c.task_id == task_id)
+            .filter(|c| !matches!(after, Some(after) if c.comment_id.as_str() <= after))
+            .cloned()
+            .collect();
+        page.sort_by(|a, b| a.comment_id.cmp(&b.comment_id));
+        page.truncate(limit);
+        Ok(page)
+    }
+
+    async fn count_comments(&self, board_id: &str) -> StoreResult<HashMap<String, u64>> {
+        let comments = self.comments.lock().unwrap();
+        let mut counts = HashMap::new();
+        for comment in comments.iter().filter(|c| c.board_id == board_id) {
+            *counts.entry(comment.task_id.clone()).or_insert(0) += 1;
+        }
+        Ok(counts)
+    }
+}
+
+fn board_template(template: &TaskTemplate, board_id: &str, template_id: &str) -> bool {
+    template.board_id == board_id && template.template_id == template_id
+}
+
+#[async_trait]
+impl TemplateStore for MemoryStore {
+    async fn create_template(&self, template: TaskTemplate) -> StoreResult<TaskTemplate> {
+        let mut templates = self.templates.lock().unwrap();
+        if templates
+            .iter()
+            .any(|t| t.template_id == template.template_id)
+        {
+            return Err(StoreError::Conflict(format!(
+                "duplicate template id {}",
+                template.template_id
+            )));
+        }
+        templates.push(template.clone());
+        Ok(template)
+    }
+
+    async fn list_templates(&self, board_id: &str) -> StoreResult<Vec<TaskTemplate>> {
+        let templates = self.templates.lock().unwrap();
+        Ok(templates
+            .iter()
+            .filter(|t| t.board_id == board_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_template(
+        &self,
+        board_id: &str,
+        template_id: &str,
+    ) -> StoreResult<Option<TaskTemplate>> {
+        let templates = self.templates.lock().unwrap();
+        Ok(templates
+            .iter()
+            .find(|t| board_template(t, board_id, template_id))
+            .cloned())
+    }
+
+    async fn update_template(
+        &self,
+        board_id: &str,
+        template_id: &str,
+        update: &TemplateUpdate,
+    ) -> StoreResult<Option<TaskTemplate>> {
+        let mut templates = self.templates.lock().unwrap();
+        let Some(template) = templates
+            .iter_mut()
+            .find(|t| board_template(t, board_id, template_id))
+        else {
+            return Ok(None);
+        };
+        update.apply(template);
+        Ok(Some(template.clone()))
+    }
+
+    async fn delete_template(&self, board_id: &str, template_id: &str) -> StoreResult<bool> {
+        let mut templates = self.templates.lock().unwrap();
+        let before = templates.len();
+        templates.retain(|t| !board_template(t, board_id, template_id));
+        Ok(templates.len() < before)
+    }
+
+    async fn due_templates(&self, now: DateTime<Utc>) -> StoreResult<Vec<TaskTemplate>> {
+        let templates = self.templates.lock().unwrap();
+        Ok(templates
+            .iter()
+            .filter(|t| matches!(t.next_run_at, Some(at) if at <= now))
+            .cloned()
+            .collect())
+    }
+
+    async fn advance_template(
+        &self,
+        board_id: &str,
+        template_id: &str,
+        from: DateTime<Utc>,
+        to: Option<DateTime<Utc>>,
+    ) -> StoreResult<bool> {
+        let mut templates = self.templates.lock().unwrap();
+        let Some(template) = templates
+            .iter_mut()
+            .find(|t| board_template(t, board_id, template_id) && t.next_run_at == Some(from))
+        else {
+            return Ok(false);
+        };
+        template.next_run_at = to;
+        Ok(true)
+    }
+
+    async fn create_board_template(&self, template: BoardTemplate) -> StoreResult<BoardTemplate> {
+        let mut templates = self.board_templates.lock().unwrap();
+        if templates
+            .iter()
+            .any(|t| t.template_id == template.template_id)
+        {
+            return Err(StoreError::Conflict(format!(
+                "duplicate board template id {}",
+                template.template_id
+            )));
+        }
+        templates.push(template.clone());
+        Ok(template)
+    }
+
+    async fn list_board_templates(&self, user_id: &str) -> StoreResult<Vec<BoardTemplate>> {
+        let templates = self.board_templates.lock().unwrap();
+        Ok(templates
+            .iter()
+            .filter(|t| t.created_by == user_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_board_template(
+        &self,
+        user_id: &str,
+        template_id: &str,
+    ) -> StoreResult<Option<BoardTemplate>> {
+        let templates = self.board_templates.lock().unwrap();
+        Ok(templates
+            .iter()
+            .find(|t| t.created_by == user_id && t.template_id == template_id)
+            .cloned())
+    }
+
+    async fn delete_board_template(&self, user_id: &str, template_id: &str) -> StoreResult<bool> {
+        let mut templates = self.board_templates.lock().unwrap();
+        let before = templates.len();
+        templates.retain(|t| !(t.created_by == user_id && t.template_id == template_id));
+        Ok(templates.len() < before)
+    }
+}
+
+#[async_trait]
+impl UserStore for MemoryStore {
+    async fn create_user(&self, user: User) -> StoreResult<User> {
+        let mut users = self.users.lock().unwrap();
+        if users.iter().any(|u| u.username == user.username) {
+            return Err(StoreError::Conflict(format!(
+                "duplicate username {}",
+                user.username
+            )));
+        }
+        users.push(user.clone());
+        Ok(user)
+    }
+
+    async fn get_user(&self, user_id: &str) -> StoreResult<Option<User>> {
+        let users = self.users.lock().unwrap();
+        Ok(users.iter().find(|u| u.user_id == user_id).cloned())
+    }
+
+    async fn get_user_by_username(&self, username: &str) -> StoreResult<Option<User>> {
+        let users = self.users.lock().unwrap();
+        Ok(users.iter().find(|u| u.username == username).cloned())
+    }
+}
+
+#[async_trait]
+impl SessionStore for MemoryStore {
+    async fn create_session(&self, session: Session) -> StoreResult<Session> {
+        self.sessions.lock().unwrap().push(session.clone());
+        Ok(session)
+    }
+
+    async fn get_session(&self, token_hash: &str) -> StoreResult<Option<Session>> {
+        let sessions = self.sessions.lock().unwrap();
+        Ok(sessions
+            .iter()
+            .find(|s| s.token_hash == token_hash)
+            .cloned())
+    }
+
+    async fn delete_session(&self, token_hash: &str) -> StoreResult<bool> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let before = sessions.len();
+        sessions.retain(|s| s.token_hash != token_hash);
+        Ok(sessions.len() < before)
+    }
+}
+
+#[async_trait]
+impl ApiTokenStore for MemoryStore {
+    async fn create_api_token(&self, token: ApiToken) -> StoreResult<ApiToken> {
+        self.api_tokens.lock().unwrap().push(token.clone());
+        Ok(token)
+    }
+
+    async fn list_api_tokens(&self, user_id: &str) -> StoreResult<Vec<ApiToken>> {
+        let tokens = self.api_tokens.lock().unwrap();
+        Ok(tokens
+            .iter()
+            .filter(|t| t.user_id == user_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_api_token(&self, token_hash: &str) -> StoreResult<Option<ApiToken>> {
+        let tokens = self.api_tokens.lock().unwrap();
+        Ok(tokens.iter().find(|t| t.token_hash == token_hash).cloned())
+    }
+
+    async fn delete_api_token(&self, user_id: &str, token_id: &str) -> StoreResult<bool> {
+        let mut tokens = self.api_tokens.lock().unwrap();
+        let before = tokens.len();
+        tokens.retain(|t| !(t.user_id == user_id && t.token_id == token_id));
+        Ok(tokens.len() < before)
+    }
+}
+
+fn matches_filter(filter: &EventFilter, event: &TaskEvent) -> bool {
+    if event.board_id != filter.board_id || event.id <= filter.after.unwrap_or(0) {
+        return false;
+    }
+    if matches!(&filter.task_id, Some(task_id) if event.task_id != *task_id) {
+        return false;
+    }
+    if matches!(filter.from, Some(from) if event.at < from) {
+        return false;
+    }
+    !matches!(filter.to, Some(to) if event.at >= to)
+}
+
+#[async_trait]
+impl EventStore for MemoryStore {
+    async fn append_event(&self, mut event: TaskEvent) -> StoreResult<TaskEvent> {
+        let mut events = self.events.lock().unwrap();
+        event.id = events.last().map_or(1, |last| last.id + 1);
+        events.push(event.clone());
+        Ok(event)
+    }
+
+    async fn list_events_after(&self, after: u64, limit: usize) -> StoreResult<Vec<TaskEvent>> {
+        let events = self.events.lock().unwrap();
+        Ok(events
+            .iter()
+            .filter(|e| e.id > after)
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    async fn find_events(&self, filter: &EventFilter) -> StoreResult<Vec<TaskEvent>> {
+        let events = self.events.lock().unwrap();
+        Ok(events
+            .iter()
+            .filter(|e| matches_filter(filter, e))
+            .take(filter.limit)
+            .cloned()
+            .collect())
+    }
+}
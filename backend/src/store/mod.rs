@@ -0,0 +1,352 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::models::{
+    ApiToken, Board, BoardTemplate, BoardUpdate, Comment, CommentUpdate, EventFilter, Session,
+    Task, TaskEvent, TaskFilter, TaskTemplate, TaskUpdate, TemplateUpdate, User,
+};
+
+mod memory;
+mod mongo;
+mod sqlite;
+#[cfg(test)]
+mod tests;
+
+pub use memory::MemoryStore;
+pub use mongo::MongoStore;
+pub use sqlite::SqliteStore;
+
+#[derive(Debug)]
+pub enum StoreError {
+    /// A unique field (task id, username, ...) is already taken.
+    Conflict(String),
+    Backend(String),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Conflict(msg) => write!(f, "conflict: {}", msg),
+            StoreError::Backend(msg) => write!(f, "storage error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<mongodb::error::Error> for StoreError {
+    fn from(e: mongodb::error::Error) -> Self {
+        use mongodb::error::{ErrorKind, WriteFailure};
+
+        match e.kind.as_ref() {
+            ErrorKind::Write(WriteFailure::WriteError(write)) if write.code == 11000 => {
+                StoreError::Conflict(write.message.clone())
+            }
+            _ => StoreError::Backend(e.to_string()),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for StoreError {
+    fn from(e: rusqlite::Error) -> Self {
+        match &e {
+            rusqlite::Error::SqliteFailure(err, _)
+                if err.code == rusqlite::ErrorCode::ConstraintViolation =>
+            {
+                StoreError::Conflict(e.to_string())
+            }
+            _ => StoreError::Backend(e.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for StoreError {
+    fn from(e: serde_json::Error) -> Self {
+        StoreError::Backend(e.to_string())
+    }
+}
+
+pub type StoreResult<T> = Result<T, StoreError>;
+
+/// Persistence for tasks. Handlers only talk to this trait, so the server can
+/// run against MongoDB, an embedded SQLite file or plain memory.
+///
+/// Every lookup is scoped to a board: a task id that exists on another board
+/// is treated as missing. Tasks in the trash are only visible through the
+/// trash methods. Every write bumps the task's `version`; writes that take an
+/// `expected_version` only apply while the stored version still matches, and
+/// the check is part of the same atomic update.
+#[async_trait]
+pub trait TaskStore: Send + Sync {
+    /// Returns every task on the board in insertion order.
+    async fn list_tasks(&self, board_id: &str) -> StoreResult<Vec<Task>>;
+
+    /// Returns the board's tasks that match `filter`, in insertion order.
+    async fn find_tasks(&self, board_id: &str, filter: &TaskFilter) -> StoreResult<Vec<Task>>;
+
+    /// Returns the tasks assigned to the user on every board, in insertion
+    /// order.
+    async fn list_assigned_tasks(&self, user_id: &str) -> StoreResult<Vec<Task>>;
+
+    async fn get_task(&self, board_id: &str, task_id: &str) -> StoreResult<Option<Task>>;
+
+    async fn create_task(&self, task: Task) -> StoreResult<Task>;
+
+    /// Applies `update` to the task and returns the result, or `None` if the
+    /// board has no task with that id or its version is not
+    /// `expected_version`.
+    async fn update_task(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        update: &TaskUpdate,
+        expected_version: Option<u64>,
+    ) -> StoreResult<Option<Task>>;
+
+    /// Moves every task in column `from`, trashed ones included, to column
+    /// `to` and returns each moved task as it was before and after the move.
+    async fn move_column_tasks(
+        &self,
+        board_id: &str,
+        from: &str,
+        to: &str,
+    ) -> StoreResult<Vec<(Task, Task)>>;
+
+    /// Removes the label from every task on the board, trashed ones included,
+    /// and returns each task that carried it as it was before and after.
+    async fn remove_label(&self, board_id: &str, label_id: &str) -> StoreResult<Vec<(Task, Task)>>;
+
+    /// Moves the task to the trash and returns it, or `None` if the board has
+    /// no such task outside the trash or its version is not
+    /// `expected_version`.
+    async fn trash_task(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        deleted_at: DateTime<Utc>,
+        expected_version: Option<u64>,
+    ) -> StoreResult<Option<Task>>;
+
+    /// Returns the board's trashed tasks in insertion order.
+    async fn list_trash(&self, board_id: &str) -> StoreResult<Vec<Task>>;
+
+    /// Takes the task out of the trash, applies `update` and returns the
+    /// result, or `None` if the task is not in the board's trash.
+    async fn restore_task(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        update: &TaskUpdate,
+    ) -> StoreResult<Option<Task>>;
+
+    /// Permanently deletes a trashed task and its comments and returns the
+    /// task, or `None` if it is not in the board's trash.
+    async fn purge_task(&self, board_id: &str, task_id: &str) -> StoreResult<Option<Task>>;
+
+    /// Permanently deletes every task trashed before `deleted_before`, on all
+    /// boards, together with their comments, and returns the deleted tasks.
+    async fn purge_trash(&self, deleted_before: DateTime<Utc>) -> StoreResult<Vec<Task>>;
+}
+
+#[async_trait]
+pub trait BoardStore: Send + Sync {
+    /// Returns every board, oldest first.
+    async fn list_boards(&self) -> StoreResult<Vec<Board>>;
+
+    async fn get_board(&self, board_id: &str) -> StoreResult<Option<Board>>;
+
+    async fn create_board(&self, board: Board) -> StoreResult<Board>;
+
+    /// Applies `update` to the board, bumps its `version` and returns the
+    /// result, or `None` if there is no such board or its version is not
+    /// `expected_version`. The check is part of the same atomic update.
+    async fn update_board(
+        &self,
+        board_id: &str,
+        update: &BoardUpdate,
+        expected_version: Option<u64>,
+    ) -> StoreResult<Option<Board>>;
+
+    /// Deletes the board together with all of its tasks, comments and
+    /// templates, and returns the tasks that went with it, or `None` if there
+    /// is no such board. The board's events stay in the log.
+    async fn delete_board(&self, board_id: &str) -> StoreResult<Option<Vec<Task>>>;
+}
+
+/// Discussion threads on tasks. Like tasks, comments are scoped to a board
+/// and looked up by task.
+#[async_trait]
+pub trait CommentStore: Send + Sync {
+    async fn create_comment(&self, comment: Comment) -> StoreResult<Comment>;
+
+    async fn get_comment(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        comment_id: &str,
+    ) -> StoreResult<Option<Comment>>;
+
+    /// Replaces the comment's body and mentions and returns the result, or
+    /// `None` if the task has no such comment.
+    async fn update_comment(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        comment_id: &str,
+        update: &CommentUpdate,
+    ) -> StoreResult<Option<Comment>>;
+
+    /// Returns `false` if the task has no such comment.
+    async fn delete_comment(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        comment_id: &str,
+    ) -> StoreResult<bool>;
+
+    /// Returns up to `limit` of the task's comments with an id greater than
+    /// `after`, oldest first.
+    async fn list_comments(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        after: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<Vec<Comment>>;
+
+    /// Returns the number of comments per task id, for tasks on the board
+    /// that have any.
+    async fn count_comments(&self, board_id: &str) -> StoreResult<HashMap<String, u64>>;
+}
+
+/// Task and board templates. Like tasks, task templates are scoped to a
+/// board; board templates belong to the user who saved them.
+#[async_trait]
+pub trait TemplateStore: Send + Sync {
+    async fn create_template(&self, template: TaskTemplate) -> StoreResult<TaskTemplate>;
+
+    /// Returns the board's templates, oldest first.
+    async fn list_templates(&self, board_id: &str) -> StoreResult<Vec<TaskTemplate>>;
+
+    async fn get_template(
+        &self,
+        board_id: &str,
+        template_id: &str,
+    ) -> StoreResult<Option<TaskTemplate>>;
+
+    /// Applies `update` to the template and returns the result, or `None` if
+    /// the board has no template with that id.
+    async fn update_template(
+        &self,
+        board_id: &str,
+        template_id: &str,
+        update: &TemplateUpdate,
+    ) -> StoreResult<Option<TaskTemplate>>;
+
+    /// Returns `false` if the board has no template with that id.
+    async fn delete_template(&self, board_id: &str, template_id: &str) -> StoreResult<bool>;
+
+    /// Returns the templates on every board whose `next_run_at` is at or
+    /// before `now`.
+    async fn due_templates(&self, now: DateTime<Utc>) -> StoreResult<Vec<TaskTemplate>>;
+
+    /// Moves the template's `next_run_at` from `from` to `to` and returns
+    /// `true`. Returns `false`, changing nothing, once the template no longer
+    /// runs at `from`, because it was edited or deleted in the meantime. The
+    /// check is part of the same atomic update.
+    async fn advance_template(
+        &self,
+        board_id: &str,
+        template_id: &str,
+        from: DateTime<Utc>,
+        to: Option<DateTime<Utc>>,
+    ) -> StoreResult<bool>;
+
+    async fn create_board_template(&self, template: BoardTemplate) -> StoreResult<BoardTemplate>;
+
+    /// Returns the user's board templates, oldest first.
+    async fn list_board_templates(&self, user_id: &str) -> StoreResult<Vec<BoardTemplate>>;
+
+    async fn get_board_template(
+        &self,
+        user_id: &str,
+        template_id: &str,
+    ) -> StoreResult<Option<BoardTemplate>>;
+
+    /// Returns `false` if the user has no board template with that id.
+    async fn delete_board_template(&self, user_id: &str, template_id: &str) -> StoreResult<bool>;
+}
+
+#[async_trait]
+pub trait UserStore: Send + Sync {
+    /// Fails with [`StoreError::Conflict`] when the username is taken.
+    async fn create_user(&self, user: User) -> StoreResult<User>;
+
+    async fn get_user(&self, user_id: &str) -> StoreResult<Option<User>>;
+
+    async fn get_user_by_username(&self, username: &str) -> StoreResult<Option<User>>;
+}
+
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    async fn create_session(&self, session: Session) -> StoreResult<Session>;
+
+    async fn get_session(&self, token_hash: &str) -> StoreResult<Option<Session>>;
+
+    /// Returns `false` if there was no such session.
+    async fn delete_session(&self, token_hash: &str) -> StoreResult<bool>;
+}
+
+#[async_trait]
+pub trait ApiTokenStore: Send + Sync {
+    async fn create_api_token(&self, token: ApiToken) -> StoreResult<ApiToken>;
+
+    /// Returns the user's tokens, oldest first.
+    async fn list_api_tokens(&self, user_id: &str) -> StoreResult<Vec<ApiToken>>;
+
+    async fn get_api_token(&self, token_hash: &str) -> StoreResult<Option<ApiToken>>;
+
+    /// Returns `false` if the user has no token with that id.
+    async fn delete_api_token(&self, user_id: &str, token_id: &str) -> StoreResult<bool>;
+}
+
+/// The append-only log of task changes behind the event stream and the task
+/// history.
+#[async_trait]
+pub trait EventStore: Send + Sync {
+    /// Stores the event under the next id and returns it with that id set.
+    async fn append_event(&self, event: TaskEvent) -> StoreResult<TaskEvent>;
+
+    /// Returns up to `limit` events with an id greater than `after`, oldest
+    /// first.
+    async fn list_events_after(&self, after: u64, limit: usize) -> StoreResult<Vec<TaskEvent>>;
+
+    async fn find_events(&self, filter: &EventFilter) -> StoreResult<Vec<TaskEvent>>;
+}
+
+/// Everything the handlers need from a storage backend.
+pub trait Store:
+    TaskStore
+    + BoardStore
+    + CommentStore
+    + TemplateStore
+    + UserStore
+    + SessionStore
+    + ApiTokenStore
+    + EventStore
+{
+}
+
+impl<T> Store for T where
+    T: TaskStore
+        + BoardStore
+        + CommentStore
+        + TemplateStore
+        + UserStore
+        + SessionStore
+        + ApiTokenStore
+        + EventStore
+{
+}
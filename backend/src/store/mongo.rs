@@ -0,0 +1,928 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::TryStreamExt;
+use mongodb::{
+    bson::{doc, Document},
+    options::{FindOneAndUpdateOptions, IndexOptions, ReturnDocument},
+    Collection, Database, IndexModel,
+};
+use std::collections::HashMap;
+
+use super::{
+    ApiTokenStore, BoardStore, CommentStore, EventStore, SessionStore, StoreError, StoreResult,
+    TaskStore, TemplateStore, UserStore,
+};
+use crate::models::{
+    new_id, sortable_time, ApiToken, Board, BoardTemplate, BoardUpdate, Comment, CommentUpdate,
+    EventFilter, Session, Task, TaskEvent, TaskFilter, TaskTemplate, TaskUpdate, TemplateUpdate,
+    User, DEFAULT_BOARD_ID,
+};
+
+pub struct MongoStore {
+    boards: Collection<Board>,
+    tasks: Collection<Task>,
+    comments: Collection<Comment>,
+    templates: Collection<TaskTemplate>,
+    board_templates: Collection<BoardTemplate>,
+    users: Collection<User>,
+    sessions: Collection<Session>,
+    api_tokens: Collection<ApiToken>,
+    events: Collection<TaskEvent>,
+    /// One `{ _id: name, seq }` document per sequence.
+    counters: Collection<Document>,
+}
+
+fn unique_index(field: &str) -> IndexModel {
+    IndexModel::builder()
+        .keys(doc! { field: 1 })
+        .options(
+            IndexOptions::builder()
+                .unique(true)
+                .name(format!("{}_unique", field))
+                .build(),
+        )
+        .build()
+}
+
+impl MongoStore {
+    /// Wraps the database and brings existing documents up to date with the
+    /// current schema.
+    pub async fn open(database: &Database) -> StoreResult<Self> {
+        let store = Self {
+            boards: database.collection("boards"),
+            tasks: database.collection("tasks"),
+            comments: database.collection("comments"),
+            templates: database.collection("task_templates"),
+            board_templates: database.collection("board_templates"),
+            users: database.collection("users"),
+            sessions: database.collection("sessions"),
+            api_tokens: database.collection("api_tokens"),
+            events: database.collection("task_events"),
+            counters: database.collection("counters"),
+        };
+
+        // Tasks created before boards existed belong to the default board.
+        store
+            .tasks
+            .update_many(
+                doc! { "boardId": { "$exists": false } },
+                doc! { "$set": { "boardId": DEFAULT_BOARD_ID } },
+                None,
+            )
+            .await?;
+
+        // Tasks written before descriptions existed keep their title in `text`.
+        store
+            .tasks
+            .update_many(
+                doc! { "text": { "$exists": true }, "title": { "$exists": false } },
+                doc! { "$rename": { "text": "title" } },
+                None,
+            )
+            .await?;
+
+        // Tasks and boards written before versioning start at version 0.
+        store
+            .tasks
+            .update_many(
+                doc! { "version": { "$exists": false } },
+                doc! { "$set": { "version": 0_i64 } },
+                None,
+            )
+            .await?;
+        store
+            .boards
+            .update_many(
+                doc! { "version": { "$exists": false } },
+                doc! { "$set": { "version": 0_i64 } },
+                None,
+            )
+            .await?;
+
+        let rekeyed = store.rekey_duplicate_task_ids().await?;
+        if rekeyed > 0 {
+            println!(
+                "Assigned new ids to {} tasks with duplicate taskId",
+                rekeyed
+            );
+        }
+        store
+            .tasks
+            .create_indexes(
+                [
+                    unique_index("taskId"),
+                    IndexModel::builder()
+                        .keys(doc! { "boardId": 1, "dueAt": 1 })
+                        .build(),
+                    IndexModel::builder()
+                        .keys(doc! { "boardId": 1, "labels": 1 })
+                        .build(),
+                    IndexModel::builder().keys(doc! { "assignees": 1 }).build(),
+                    IndexModel::builder()
+                        .keys(doc! { "deletedAt": 1 })
+                        .options(IndexOptions::builder().sparse(true).build())
+                        .build(),
+                ],
+                None,
+            )
+            .await?;
+        store
+            .comments
+            .create_indexes(
+                [
+                    unique_index("commentId"),
+                    IndexModel::builder()
+                        .keys(doc! { "boardId": 1, "taskId": 1, "commentId": 1 })
+                        .build(),
+                ],
+                None,
+            )
+            .await?;
+        store
+            .templates
+            .create_indexes(
+                [
+                    unique_index("templateId"),
+                    IndexModel::builder().keys(doc! { "boardId": 1 }).build(),
+                    IndexModel::builder()
+                        .keys(doc! { "nextRunAt": 1 })
+                        .options(IndexOptions::builder().sparse(true).build())
+                        .build(),
+                ],
+                None,
+            )
+            .await?;
+        store
+            .board_templates
+            .create_indexes(
+                [
+                    unique_index("templateId"),
+                    IndexModel::builder().keys(doc! { "createdBy": 1 }).build(),
+                ],
+                None,
+            )
+            .await?;
+        store
+            .users
+            .create_indexes([unique_index("userId"), unique_index("username")], None)
+            .await?;
+        store
+            .sessions
+            .create_index(unique_index("tokenHash"), None)
+            .await?;
+        store
+            .api_tokens
+            .create_indexes([unique_index("tokenHash"), unique_index("tokenId")], None)
+            .await?;
+        store
+            .events
+            .create_indexes(
+                [
+                    unique_index("id"),
+                    IndexModel::builder()
+                        .keys(doc! { "boardId": 1, "taskId": 1, "id": 1 })
+                        .build(),
+                    IndexModel::builder()
+                        .keys(doc! { "boardId": 1, "at": 1 })
+                        .build(),
+                ],
+                None,
+            )
+            .await?;
+
+        Ok(store)
+    }
+
+    /// Older servers derived task ids from the current millisecond, so two
+    /// tasks could share one. The oldest task keeps the id and every other
+    /// copy gets a fresh one. Once the unique index exists this finds nothing.
+    async fn rekey_duplicate_task_ids(&self) -> StoreResult<u64> {
+        let pipeline = vec![
+            doc! { "$sort": { "_id": 1 } },
+            doc! { "$group": { "_id": "$taskId", "ids": { "$push": "$_id" } } },
+            doc! { "$match": { "ids.1": { "$exists": true } } },
+        ];
+        let mut cursor = self.tasks.aggregate(pipeline, None).await?;
+
+        let mut rekeyed = 0;
+        while let Some(group) = cursor.try_next().await? {
+            let ids = group
+                .get_array("ids")
+                .map_err(|e| StoreError::Backend(e.to_string()))?;
+            for id in ids.iter().skip(1) {
+                self.tasks
+                    .update_one(
+                        doc! { "_id": id },
+                        doc! { "$set": { "taskId": new_id("task") } },
+                        None,
+                    )
+                    .await?;
+                rekeyed += 1;
+            }
+        }
+        Ok(rekeyed)
+    }
+}
+
+impl MongoStore {
+    /// Atomically increments the named counter and returns its new value.
+    async fn next_sequence(&self, name: &str) -> StoreResult<u64> {
+        let options = FindOneAndUpdateOptions::builder()
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .build();
+        let counter = self
+            .counters
+            .find_one_and_update(
+                doc! { "_id": name },
+                doc! { "$inc": { "seq": 1_i64 } },
+                options,
+            )
+            .await?
+            .ok_or_else(|| StoreError::Backend(format!("counter {} was not created", name)))?;
+        counter
+            .get_i64("seq")
+            .map(|seq| seq as u64)
+            .map_err(|e| StoreError::Backend(e.to_string()))
+    }
+
+    /// Applies `update` to every task matching `filter` in one
+    /// `update_many`, and returns each as it was before and after. The
+    /// before copies are read just ahead of the write; `apply` makes the
+    /// same change to a copy.
+    async fn update_all_tasks(
+        &self,
+        filter: Document,
+        update: Document,
+        apply: impl Fn(&mut Task),
+    ) -> StoreResult<Vec<(Task, Task)>> {
+        let matched: Vec<Task> = self
+            .tasks
+            .find(filter.clone(), None)
+            .await?
+            .try_collect()
+            .await?;
+        self.tasks.update_many(filter, update, None).await?;
+        Ok(matched
+            .into_iter()
+            .map(|before| {
+                let mut after = before.clone();
+                apply(&mut after);
+                after.version += 1;
+                (before, after)
+            })
+            .collect())
+    }
+}
+
+/// Matches the task unless it is in the trash. `null` also matches documents
+/// written before the field existed.
+fn live_task(board_id: &str, task_id: &str) -> Document {
+    doc! { "boardId": board_id, "taskId": task_id, "deletedAt": null }
+}
+
+fn trashed_task(board_id: &str, task_id: &str) -> Document {
+    doc! { "boardId": board_id, "taskId": task_id, "deletedAt": { "$ne": null } }
+}
+
+/// Narrows a task or board filter to one version, so a stale write matches
+/// nothing.
+fn versioned(mut filter: Document, expected_version: Option<u64>) -> Document {
+    if let Some(version) = expected_version {
+        filter.insert("version", version as i64);
+    }
+    filter
+}
+
+fn to_set_document<T: serde::Serialize>(update: &T) -> StoreResult<mongodb::bson::Document> {
+    mongodb::bson::to_document(update).map_err(|e| StoreError::Backend(e.to_string()))
+}
+
+#[async_trait]
+impl TaskStore for MongoStore {
+    async fn list_tasks(&self, board_id: &str) -> StoreResult<Vec<Task>> {
+        let cursor = self
+            .tasks
+            .find(doc! { "boardId": board_id, "deletedAt": null }, None)
+            .await?;
+        Ok(cursor.try_collect().await?)
+    }
+
+    async fn find_tasks(&self, board_id: &str, filter: &TaskFilter) -> StoreResult<Vec<Task>> {
+        let mut query = doc! { "boardId": board_id, "deletedAt": null };
+        if let Some(before) = &filter.due_before {
+            // Due dates are stored in a fixed-width format, so string order
+            // is time order; tasks without one are never matched by `$lt`.
+            query.insert("dueAt", doc! { "$lt": sortable_time::format(before) });
+        }
+        if let Some(label) = &filter.label {
+            query.insert("labels", label);
+        }
+        let cursor = self.tasks.find(query, None).await?;
+        Ok(cursor.try_collect().await?)
+    }
+
+    async fn list_assigned_tasks(&self, user_id: &str) -> StoreResult<Vec<Task>> {
+        let cursor = self
+            .tasks
+            .find(doc! { "assignees": user_id, "deletedAt": null }, None)
+            .await?;
+        Ok(cursor.try_collect().await?)
+    }
+
+    async fn get_task(&self, board_id: &str, task_id: &str) -> StoreResult<Option<Task>> {
+        Ok(self
+            .tasks
+            .find_one(live_task(board_id, task_id), None)
+            .await?)
+    }
+
+    async fn create_task(&self, task: Task) -> StoreResult<Task> {
+        self.tasks.insert_one(&task, None).await?;
+        Ok(task)
+    }
+
+    async fn update_task(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        update: &TaskUpdate,
+        expected_version: Option<u64>,
+    ) -> StoreResult<Option<Task>> {
+        let mut changes = doc! { "$inc": { "version": 1 } };
+        let set = to_set_document(update)?;
+        if !set.is_empty() {
+            changes.insert("$set", set);
+        }
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+        Ok(self
+            .tasks
+            .find_one_and_update(
+                versioned(live_task(board_id, task_id), expected_version),
+                changes,
+                options,
+            )
+            .await?)
+    }
+
+    async fn move_column_tasks(
+        &self,
+        board_id: &str,
+        from: &str,
+        to: &str,
+    ) -> StoreResult<Vec<(Task, Task)>> {
+        self.update_all_tasks(
+            doc! { "boardId": board_id, "column": from },
+            doc! { "$set": { "column": to }, "$inc": { "version": 1 } },
+            |task| task.column = to.to_string(),
+        )
+        .await
+    }
+
+    async fn remove_label(&self, board_id: &str, label_id: &str) -> StoreResult<Vec<(Task, Task)>> {
+        self.update_all_tasks(
+            doc! { "boardId": board_id, "labels": label_id },
+            doc! { "$pull": { "labels": label_id }, "$inc": { "version": 1 } },
+            |task| task.labels.retain(|l| l != label_id),
+        )
+        .await
+    }
+
+    async fn trash_task(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        deleted_at: DateTime<Utc>,
+        expected_version: Option<u64>,
+    ) -> StoreResult<Option<Task>> {
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+        Ok(self
+            .tasks
+            .find_one_and_update(
+                versioned(live_task(board_id, task_id), expected_version),
+                doc! {
+                    "$set": { "deletedAt": sortable_time::format(&deleted_at) },
+                    "$inc": { "version": 1 },
+                },
+                options,
+            )
+            .await?)
+    }
+
+    async fn list_trash(&self, board_id: &str) -> StoreResult<Vec<Task>> {
+        let cursor = self
+            .tasks
+            .find(
+                doc! { "boardId": board_id, "deletedAt": { "$ne": null } },
+                None,
+            )
+            .await?;
+        Ok(cursor.try_collect().await?)
+    }
+
+    async fn restore_task(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        update: &TaskUpdate,
+    ) -> StoreResult<Option<Task>> {
+        let mut changes = doc! { "$unset": { "deletedAt": "" }, "$inc": { "version": 1 } };
+        let set = to_set_document(update)?;
+        if !set.is_empty() {
+            changes.insert("$set", set);
+        }
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+        Ok(self
+            .tasks
+            .find_one_and_update(trashed_task(board_id, task_id), changes, options)
+            .await?)
+    }
+
+    async fn purge_task(&self, board_id: &str, task_id: &str) -> StoreResult<Option<Task>> {
+        let Some(task) = self
+            .tasks
+            .find_one_and_delete(trashed_task(board_id, task_id), None)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        self.comments
+            .delete_many(doc! { "boardId": board_id, "taskId": task_id }, None)
+            .await?;
+        Ok(Some(task))
+    }
+
+    async fn purge_trash(&self, deleted_before: DateTime<Utc>) -> StoreResult<Vec<Task>> {
+        // `deletedAt` is stored in a fixed-width format, so string order is
+        // time order.
+        let expired = doc! { "deletedAt": { "$lt": sortable_time::format(&deleted_before) } };
+        let candidates: Vec<Task> = self
+            .tasks
+            .find(expired.clone(), None)
+            .await?
+            .try_collect()
+            .await?;
+
+        // Deleting one by one returns exactly the tasks that went, even if
+        // one is restored in the meantime.
+        let mut purged = Vec::new();
+        for candidate in candidates {
+            let mut filter = expired.clone();
+            filter.insert("taskId", &candidate.task_id);
+            if let Some(task) = self.tasks.find_one_and_delete(filter, None).await? {
+                purged.push(task);
+            }
+        }
+        if purged.is_empty() {
+            return Ok(purged);
+        }
+
+        // Task ids are unique across boards.
+        let task_ids: Vec<&str> = purged.iter().map(|t| t.task_id.as_str()).collect();
+        self.comments
+            .delete_many(doc! { "taskId": { "$in": task_ids } }, None)
+            .await?;
+        Ok(purged)
+    }
+}
+
+#[async_trait]
+impl BoardStore for MongoStore {
+    async fn list_boards(&self) -> StoreResult<Vec<Board>> {
+        let options = mongodb::options::FindOptions::builder()
+            .sort(doc! { "createdAt": 1 })
+            .build();
+        let cursor = self.boards.find(None, options).await?;
+        Ok(cursor.try_collect().await?)
+    }
+
+    async fn get_board(&self, board_id: &str) -> StoreResult<Option<Board>> {
+        Ok(self
+            .boards
+            .find_one(doc! { "boardId": board_id }, None)
+            .await?)
+    }
+
+    async fn create_board(&self, board: Board) -> StoreResult<Board> {
+        self.boards.insert_one(&board, None).await?;
+        Ok(board)
+    }
+
+    async fn update_board(
+        &self,
+        board_id: &str,
+        update: &BoardUpdate,
+        expected_version: Option<u64>,
+    ) -> StoreResult<Option<Board>> {
+        let mut changes = doc! { "$inc": { "version": 1 } };
+        let set = to_set_document(update)?;
+        if !set.is_empty() {
+            changes.insert("$set", set);
+        }
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+        Ok(self
+            .boards
+            .find_one_and_update(
+                versioned(doc! { "boardId": board_id }, expected_version),
+                changes,
+                options,
+            )
+            .await?)
+    }
+
+    async fn delete_board(&self, board_id: &str) -> StoreResult<Option<Vec<Task>>> {
+        let result = self
+            .boards
+            .delete_one(doc! { "boardId": board_id }, None)
+            .await?;
+        if result.deleted_count == 0 {
+            return Ok(None);
+        }
+
+        let tasks: Vec<Task> = self
+            .tasks
+            .find(doc! { "boardId": board_id }, None)
+            .await?
+            .try_collect()
+            .await?;
+        self.tasks
+            .delete_many(doc! { "boardId": board_id }, None)
+            .await?;
+        self.comments
+            .delete_many(doc! { "boardId": board_id }, None)
+            .await?;
+        self.templates
+            .delete_many(doc! { "boardId": board_id }, None)
+            .await?;
+        Ok(Some(tasks))
+    }
+}
+
+fn task_comment(board_id: &str, task_id: &str, comment_id: &str) -> Document {
+    doc! { "boardId": board_id, "taskId": task_id, "commentId": comment_id }
+}
+
+#[async_trait]
+impl CommentStore for MongoStore {
+    async fn create_comment(&self, comment: Comment) -> StoreResult<Comment> {
+        self.comments.insert_one(&comment, None).await?;
+        Ok(comment)
+    }
+
+    async fn get_comment(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        comment_id: &str,
+    ) -> StoreResult<Option<Comment>> {
+        Ok(self
+            .comments
+            .find_one(task_comment(board_id, task_id, comment_id), None)
+            .await?)
+    }
+
+    async fn update_comment(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        comment_id: &str,
+        update: &CommentUpdate,
+    ) -> StoreResult<Option<Comment>> {
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+        Ok(self
+            .comments
+            .find_one_and_update(
+                task_comment(board_id, task_id, comment_id),
+                doc! { "$set": to_set_document(update)? },
+                options,
+            )
+            .await?)
+    }
+
+    async fn delete_comment(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        comment_id: &str,
+    ) -> StoreResult<bool> {
+        let result = self
+            .comments
+            .delete_one(task_comment(board_id, task_id, comment_id), None)
+            .await?;
+        Ok(result.deleted_count > 0)
+    }
+
+    async fn list_comments(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        after: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<Vec<Comment>> {
+        let mut query = doc! { "boardId": board_id, "taskId": task_id };
+        if let Some(after) = after {
+            query.insert("commentId", doc! { "$gt": after });
+        }
+        let options = mongodb::options::FindOptions::builder()
+            .sort(doc! { "commentId": 1 })
+            .limit(limit as i64)
+            .build();
+        let cursor = self.comments.find(query, options).await?;
+        Ok(cursor.try_collect().await?)
+    }
+
+    async fn count_comments(&self, board_id: &str) -> StoreResult<HashMap<String, u64>> {
+        let pipeline = vec![
+            doc! { "$match": { "boardId": board_id } },
+            doc! { "$group": { "_id": "$taskId", "count": { "$sum": 1_i64 } } },
+        ];
+        let mut cursor = self.comments.aggregate(pipeline, None).await?;
+
+        let mut counts = HashMap::new();
+        while let Some(group) = cursor.try_next().await? {
+            let task_id = group
+                .get_str("_id")
+                .map_err(|e| StoreError::Backend(e.to_string()))?;
+            let count = group
+                .get_i64("count")
+                .map_err(|e| StoreError::Backend(e.to_string()))?;
+            counts.insert(task_id.to_string(), count as u64);
+        }
+        Ok(counts)
+    }
+}
+
+fn board_template(board_id: &str, template_id: &str) -> Document {
+    doc! { "boardId": board_id, "templateId": template_id }
+}
+
+#[async_trait]
+impl TemplateStore for MongoStore {
+    async fn create_template(&self, template: TaskTemplate) -> StoreResult<TaskTemplate> {
+        self.templates.insert_one(&template, None).await?;
+        Ok(template)
+    }
+
+    async fn list_templates(&self, board_id: &str) -> StoreResult<Vec<TaskTemplate>> {
+        let options = mongodb::options::FindOptions::builder()
+            .sort(doc! { "_id": 1 })
+            .build();
+        let cursor = self
+            .templates
+            .find(doc! { "boardId": board_id }, options)
+            .await?;
+        Ok(cursor.try_collect().await?)
+    }
+
+    async fn get_template(
+        &self,
+        board_id: &str,
+        template_id: &str,
+    ) -> StoreResult<Option<TaskTemplate>> {
+        Ok(self
+            .templates
+            .find_one(board_template(board_id, template_id), None)
+            .await?)
+    }
+
+    async fn update_template(
+        &self,
+        board_id: &str,
+        template_id: &str,
+        update: &TemplateUpdate,
+    ) -> StoreResult<Option<TaskTemplate>> {
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+        let set = to_set_document(update)?;
+        if set.is_empty() {
+            return self.get_template(board_id, template_id).await;
+        }
+        Ok(self
+            .templates
+            .find_one_and_update(
+                board_template(board_id, template_id),
+                doc! { "$set": set },
+                options,
+            )
+            .await?)
+    }
+
+    async fn delete_template(&self, board_id: &str, template_id: &str) -> StoreResult<bool> {
+        let result = self
+            .templates
+            .delete_one(board_template(board_id, template_id), None)
+            .await?;
+        Ok(result.deleted_count > 0)
+    }
+
+    async fn due_templates(&self, now: DateTime<Utc>) -> StoreResult<Vec<TaskTemplate>> {
+        // `nextRunAt` is stored in a fixed-width format, so string order is
+        // time order.
+        let cursor = self
+            .templates
+            .find(
+                doc! { "nextRunAt": { "$lte": sortable_time::format(&now) } },
+                None,
+            )
+            .await?;
+        Ok(cursor.try_collect().await?)
+    }
+
+    async fn advance_template(
+        &self,
+        board_id: &str,
+        template_id: &str,
+        from: DateTime<Utc>,
+        to: Option<DateTime<Utc>>,
+    ) -> StoreResult<bool> {
+        let mut query = board_template(board_id, template_id);
+        query.insert("nextRunAt", sortable_time::format(&from));
+        let change = match to {
+            Some(to) => doc! { "$set": { "nextRunAt": sortable_time::format(&to) } },
+            None => doc! { "$unset": { "nextRunAt": "" } },
+        };
+        let result = self.templates.update_one(query, change, None).await?;
+        Ok(result.modified_count > 0)
+    }
+
+    async fn create_board_template(&self, template: BoardTemplate) -> StoreResult<BoardTemplate> {
+        self.board_templates.insert_one(&template, None).await?;
+        Ok(template)
+    }
+
+    async fn list_board_templates(&self, user_id: &str) -> StoreResult<Vec<BoardTemplate>> {
+        let options = mongodb::options::FindOptions::builder()
+            .sort(doc! { "_id": 1 })
+            .build();
+        let cursor = self
+            .board_templates
+            .find(doc! { "createdBy": user_id }, options)
+            .await?;
+        Ok(cursor.try_collect().await?)
+    }
+
+    async fn get_board_template(
+        &self,
+        user_id: &str,
+        template_id: &str,
+    ) -> StoreResult<Option<BoardTemplate>> {
+        Ok(self
+            .board_templates
+            .find_one(
+                doc! { "createdBy": user_id, "templateId": template_id },
+                None,
+            )
+            .await?)
+    }
+
+    async fn delete_board_template(&self, user_id: &str, template_id: &str) -> StoreResult<bool> {
+        let result = self
+            .board_templates
+            .delete_one(
+                doc! { "createdBy": user_id, "templateId": template_id },
+                None,
+            )
+            .await?;
+        Ok(result.deleted_count > 0)
+    }
+}
+
+#[async_trait]
+impl UserStore for MongoStore {
+    async fn create_user(&self, user: User) -> StoreResult<User> {
+        self.users.insert_one(&user, None).await?;
+        Ok(user)
+    }
+
+    async fn get_user(&self, user_id: &str) -> StoreResult<Option<User>> {
+        Ok(self
+            .users
+            .find_one(doc! { "userId": user_id }, None)
+            .await?)
+    }
+
+    async fn get_user_by_username(&self, username: &str) -> StoreResult<Option<User>> {
+        Ok(self
+            .users
+            .find_one(doc! { "username": username }, None)
+            .await?)
+    }
+}
+
+#[async_trait]
+impl SessionStore for MongoStore {
+    async fn create_session(&self, session: Session) -> StoreResult<Session> {
+        self.sessions.insert_one(&session, None).await?;
+        Ok(session)
+    }
+
+    async fn get_session(&self, token_hash: &str) -> StoreResult<Option<Session>> {
+        Ok(self
+            .sessions
+            .find_one(doc! { "tokenHash": token_hash }, None)
+            .await?)
+    }
+
+    async fn delete_session(&self, token_hash: &str) -> StoreResult<bool> {
+        let result = self
+            .sessions
+            .delete_one(doc! { "tokenHash": token_hash }, None)
+            .await?;
+        Ok(result.deleted_count > 0)
+    }
+}
+
+#[async_trait]
+impl ApiTokenStore for MongoStore {
+    async fn create_api_token(&self, token: ApiToken) -> StoreResult<ApiToken> {
+        self.api_tokens.insert_one(&token, None).await?;
+        Ok(token)
+    }
+
+    async fn list_api_tokens(&self, user_id: &str) -> StoreResult<Vec<ApiToken>> {
+        let options = mongodb::options::FindOptions::builder()
+            .sort(doc! { "createdAt": 1 })
+            .build();
+        let cursor = self
+            .api_tokens
+            .find(doc! { "userId": user_id }, options)
+            .await?;
+        Ok(cursor.try_collect().await?)
+    }
+
+    async fn get_api_token(&self, token_hash: &str) -> StoreResult<Option<ApiToken>> {
+        Ok(self
+            .api_tokens
+            .find_one(doc! { "tokenHash": token_hash }, None)
+            .await?)
+    }
+
+    async fn delete_api_token(&self, user_id: &str, token_id: &str) -> StoreResult<bool> {
+        let result = self
+            .api_tokens
+            .delete_one(doc! { "userId": user_id, "tokenId": token_id }, None)
+            .await?;
+        Ok(result.deleted_count > 0)
+    }
+}
+
+#[async_trait]
+impl EventStore for MongoStore {
+    async fn append_event(&self, mut event: TaskEvent) -> StoreResult<TaskEvent> {
+        event.id = self.next_sequence("task_events").await?;
+        self.events.insert_one(&event, None).await?;
+        Ok(event)
+    }
+
+    async fn list_events_after(&self, after: u64, limit: usize) -> StoreResult<Vec<TaskEvent>> {
+        let options = mongodb::options::FindOptions::builder()
+            .sort(doc! { "id": 1 })
+            .limit(limit as i64)
+            .build();
+        let cursor = self
+            .events
+            .find(doc! { "id": { "$gt": after as i64 } }, options)
+            .await?;
+        Ok(cursor.try_collect().await?)
+    }
+
+    async fn find_events(&self, filter: &EventFilter) -> StoreResult<Vec<TaskEvent>> {
+        let mut query = doc! {
+            "boardId": &filter.board_id,
+            "id": { "$gt": filter.after.unwrap_or(0) as i64 },
+        };
+        if let Some(task_id) = &filter.task_id {
+            query.insert("taskId", task_id);
+        }
+        // `at` is stored in a fixed-width format, so string order is time order.
+        let mut at = Document::new();
+        if let Some(from) = &filter.from {
+            at.insert("$gte", sortable_time::format(from));
+        }
+        if let Some(to) = &filter.to {
+            at.insert("$lt", sortable_time::format(to));
+        }
+        if !at.is_empty() {
+            query.insert("at", at);
+        }
+
+        let options = mongodb::options::FindOptions::builder()
+            .sort(doc! { "id": 1 })
+            .limit(filter.limit as i64)
+            .build();
+        let cursor = self.events.find(query, options).await?;
+        Ok(cursor.try_collect().await?)
+    }
+}
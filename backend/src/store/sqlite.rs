@@ -0,0 +1,1156 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use super::{
+    ApiTokenStore, BoardStore, CommentStore, EventStore, SessionStore, StoreError, StoreResult,
+    TaskStore, TemplateStore, UserStore,
+};
+use crate::models::{
+    new_id, sortable_time, ApiToken, Board, BoardTemplate, BoardUpdate, Comment, CommentUpdate,
+    EventFilter, Session, Task, TaskEvent, TaskFilter, TaskTemplate, TaskUpdate, TemplateUpdate,
+    User,
+};
+
+/// Embedded single-file storage. Each row keeps the record serialized as JSON
+/// next to the columns we look it up by, so new fields need no schema
+/// migration.
+pub struct SqliteStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStore {
+    pub fn open(path: &str) -> StoreResult<Self> {
+        let mut conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS tasks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                task_id TEXT NOT NULL,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS boards (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                board_id TEXT NOT NULL UNIQUE,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS users (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id TEXT NOT NULL UNIQUE,
+                username TEXT NOT NULL UNIQUE,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS sessions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                token_hash TEXT NOT NULL UNIQUE,
+                user_id TEXT NOT NULL,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS api_tokens (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                token_id TEXT NOT NULL UNIQUE,
+                token_hash TEXT NOT NULL UNIQUE,
+                user_id TEXT NOT NULL,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS task_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                board_id TEXT NOT NULL,
+                task_id TEXT NOT NULL,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS comments (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                comment_id TEXT NOT NULL UNIQUE,
+                board_id TEXT NOT NULL,
+                task_id TEXT NOT NULL,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS task_templates (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                template_id TEXT NOT NULL UNIQUE,
+                board_id TEXT NOT NULL,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS board_templates (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                template_id TEXT NOT NULL UNIQUE,
+                user_id TEXT NOT NULL,
+                data TEXT NOT NULL
+            );",
+        )?;
+        add_column_if_missing(
+            &conn,
+            "tasks",
+            "board_id",
+            "TEXT NOT NULL DEFAULT 'default'",
+        )?;
+        add_column_if_missing(&conn, "tasks", "deleted_at", "TEXT")?;
+        // Tasks written before descriptions existed keep their title in `text`.
+        conn.execute(
+            "UPDATE tasks
+             SET data = json_remove(json_set(data, '$.title', json_extract(data, '$.text')), '$.text')
+             WHERE json_type(data, '$.text') IS NOT NULL AND json_type(data, '$.title') IS NULL",
+            [],
+        )?;
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_tasks_board_id ON tasks (board_id);
+            CREATE INDEX IF NOT EXISTS idx_tasks_deleted_at ON tasks (deleted_at);
+            CREATE INDEX IF NOT EXISTS idx_tasks_due_at
+                ON tasks (board_id, json_extract(data, '$.dueAt'));
+            CREATE INDEX IF NOT EXISTS idx_task_events_board_task
+                ON task_events (board_id, task_id, id);
+            CREATE INDEX IF NOT EXISTS idx_comments_board_task
+                ON comments (board_id, task_id, comment_id);
+            CREATE INDEX IF NOT EXISTS idx_task_templates_board_id ON task_templates (board_id);
+            CREATE INDEX IF NOT EXISTS idx_task_templates_next_run_at
+                ON task_templates (json_extract(data, '$.nextRunAt'));
+            CREATE INDEX IF NOT EXISTS idx_board_templates_user_id ON board_templates (user_id);",
+        )?;
+
+        let rekeyed = rekey_duplicate_task_ids(&mut conn)?;
+        if rekeyed > 0 {
+            println!(
+                "Assigned new ids to {} tasks with duplicate taskId",
+                rekeyed
+            );
+        }
+        conn.execute_batch(
+            "DROP INDEX IF EXISTS idx_tasks_task_id;
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_tasks_task_id_unique ON tasks (task_id);",
+        )?;
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Runs blocking SQLite work off the async executor.
+    async fn with_conn<T, F>(&self, f: F) -> StoreResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> StoreResult<T> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || f(&mut conn.lock().unwrap()))
+            .await
+            .map_err(|e| StoreError::Backend(e.to_string()))?
+    }
+}
+
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> StoreResult<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?
+        .iter()
+        .any(|name| name == column);
+    if !exists {
+        conn.execute_batch(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))?;
+    }
+    Ok(())
+}
+
+/// Gives every task that shares its id with an older task a fresh id. Once
+/// the unique index exists this finds nothing.
+fn rekey_duplicate_task_ids(conn: &mut Connection) -> StoreResult<u64> {
+    let tx = conn.transaction()?;
+    let duplicates = {
+        let mut stmt = tx.prepare(
+            "SELECT id FROM tasks t
+             WHERE EXISTS (SELECT 1 FROM tasks o WHERE o.task_id = t.task_id AND o.id < t.id)",
+        )?;
+        let ids = stmt.query_map([], |row| row.get::<_, i64>(0))?;
+        ids.collect::<Result<Vec<_>, _>>()?
+    };
+
+    for id in &duplicates {
+        let task_id = new_id("task");
+        tx.execute(
+            "UPDATE tasks SET task_id = ?1, data = json_set(data, '$.taskId', ?1) WHERE id = ?2",
+            params![task_id, id],
+        )?;
+    }
+    tx.commit()?;
+    Ok(duplicates.len() as u64)
+}
+
+/// Runs a `SELECT data ...` query and decodes every row.
+fn query_json<T: DeserializeOwned>(
+    conn: &Connection,
+    sql: &str,
+    params: impl rusqlite::Params,
+) -> StoreResult<Vec<T>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map(params, |row| row.get::<_, String>(0))?;
+    let mut records = Vec::new();
+    for data in rows {
+        records.push(serde_json::from_str(&data?)?);
+    }
+    Ok(records)
+}
+
+/// Returns the first decoded row of a `SELECT data ...` query.
+fn query_one_json<T: DeserializeOwned>(
+    conn: &Connection,
+    sql: &str,
+    params: impl rusqlite::Params,
+) -> StoreResult<Option<T>> {
+    Ok(query_json(conn, sql, params)?.into_iter().next())
+}
+
+/// Loads the first row matched by a `SELECT id, data ...` query, lets `f`
+/// modify it and writes it back, all inside one transaction.
+fn update_json<T, F>(
+    conn: &mut Connection,
+    table: &str,
+    select: &str,
+    params: impl rusqlite::Params,
+    f: F,
+) -> StoreResult<Option<T>>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce(&mut T),
+{
+    let tx = conn.transaction()?;
+    let row = tx
+        .query_row(select, params, |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })
+        .optional()?;
+    let Some((id, data)) = row else {
+        return Ok(None);
+    };
+
+    let mut record: T = serde_json::from_str(&data)?;
+    f(&mut record);
+    tx.execute(
+        &format!("UPDATE {} SET data = ?1 WHERE id = ?2", table),
+        params![serde_json::to_string(&record)?, id],
+    )?;
+    tx.commit()?;
+    Ok(Some(record))
+}
+
+/// Like [`update_json`] for a task, also keeping the `deleted_at` column in
+/// step with the task in the same transaction.
+fn update_task_json<F>(
+    conn: &mut Connection,
+    select: &str,
+    params: impl rusqlite::Params,
+    f: F,
+) -> StoreResult<Option<Task>>
+where
+    F: FnOnce(&mut Task),
+{
+    let tx = conn.transaction()?;
+    let row = tx
+        .query_row(select, params, |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })
+        .optional()?;
+    let Some((id, data)) = row else {
+        return Ok(None);
+    };
+
+    let mut task: Task = serde_json::from_str(&data)?;
+    f(&mut task);
+    tx.execute(
+        "UPDATE tasks SET data = ?1, deleted_at = ?2 WHERE id = ?3",
+        params![
+            serde_json::to_string(&task)?,
+            task.deleted_at.as_ref().map(sortable_time::format),
+            id
+        ],
+    )?;
+    tx.commit()?;
+    Ok(Some(task))
+}
+
+/// Like [`update_json`], for every record `select` returns. Returns each
+/// changed record as it was before and after `f`.
+fn update_all_json<T, F>(
+    conn: &mut Connection,
+    table: &str,
+    select: &str,
+    params: impl rusqlite::Params,
+    mut f: F,
+) -> StoreResult<Vec<(T, T)>>
+where
+    T: Clone + Serialize + DeserializeOwned,
+    F: FnMut(&mut T),
+{
+    let tx = conn.transaction()?;
+    let rows = tx
+        .prepare(select)?
+        .query_map(params, |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut changed = Vec::with_capacity(rows.len());
+    for (id, data) in rows {
+        let before: T = serde_json::from_str(&data)?;
+        let mut record = before.clone();
+        f(&mut record);
+        tx.execute(
+            &format!("UPDATE {} SET data = ?1 WHERE id = ?2", table),
+            params![serde_json::to_string(&record)?, id],
+        )?;
+        changed.push((before, record));
+    }
+    tx.commit()?;
+    Ok(changed)
+}
+
+#[async_trait]
+impl TaskStore for SqliteStore {
+    async fn list_tasks(&self, board_id: &str) -> StoreResult<Vec<Task>> {
+        let board_id = board_id.to_string();
+        self.with_conn(move |conn| {
+            query_json(
+                conn,
+                "SELECT data FROM tasks WHERE board_id = ?1 AND deleted_at IS NULL ORDER BY id",
+                params![board_id],
+            )
+        })
+        .await
+    }
+
+    async fn find_tasks(&self, board_id: &str, filter: &TaskFilter) -> StoreResult<Vec<Task>> {
+        let board_id = board_id.to_string();
+        let due_before = filter.due_before.as_ref().map(sortable_time::format);
+        let label = filter.label.clone();
+        self.with_conn(move |conn| {
+            query_json(
+                conn,
+                "SELECT data FROM tasks
+                 WHERE board_id = ?1 AND deleted_at IS NULL
+                   AND (?2 IS NULL OR json_extract(data, '$.dueAt') < ?2)
+                   AND (?3 IS NULL OR EXISTS (
+                       SELECT 1 FROM json_each(data, '$.labels') WHERE value = ?3))
+                 ORDER BY id",
+                params![board_id, due_before, label],
+            )
+        })
+        .await
+    }
+
+    async fn list_assigned_tasks(&self, user_id: &str) -> StoreResult<Vec<Task>> {
+        let user_id = user_id.to_string();
+        self.with_conn(move |conn| {
+            query_json(
+                conn,
+                "SELECT data FROM tasks
+                 WHERE deleted_at IS NULL
+                   AND EXISTS (SELECT 1 FROM json_each(data, '$.assignees') WHERE value = ?1)
+                 ORDER BY id",
+                params![user_id],
+            )
+        })
+        .await
+    }
+
+    async fn get_task(&self, board_id: &str, task_id: &str) -> StoreResult<Option<Task>> {
+        let board_id = board_id.to_string();
+        let task_id = task_id.to_string();
+        self.with_conn(move |conn| {
+            query_one_json(
+                conn,
+                "SELECT data FROM tasks
+                 WHERE board_id = ?1 AND task_id = ?2 AND deleted_at IS NULL",
+                params![board_id, task_id],
+            )
+        })
+        .await
+    }
+
+    async fn create_task(&self, task: Task) -> StoreResult<Task> {
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO tasks (task_id, board_id, data) VALUES (?1, ?2, ?3)",
+                params![task.task_id, task.board_id, serde_json::to_string(&task)?],
+            )?;
+            Ok(task)
+        })
+        .await
+    }
+
+    async fn update_task(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        update: &TaskUpdate,
+        expected_version: Option<u64>,
+    ) -> StoreResult<Option<Task>> {
+        let board_id = board_id.to_string();
+        let task_id = task_id.to_string();
+        let update = update.clone();
+        self.with_conn(move |conn| {
+            update_json(
+                conn,
+                "tasks",
+                "SELECT id, data FROM tasks
+                 WHERE board_id = ?1 AND task_id = ?2 AND deleted_at IS NULL
+                   AND (?3 IS NULL OR IFNULL(json_extract(data, '$.version'), 0) = ?3)",
+                params![board_id, task_id, expected_version],
+                |task: &mut Task| {
+                    update.apply(task);
+                    task.version += 1;
+                },
+            )
+        })
+        .await
+    }
+
+    async fn move_column_tasks(
+        &self,
+        board_id: &str,
+        from: &str,
+        to: &str,
+    ) -> StoreResult<Vec<(Task, Task)>> {
+        let board_id = board_id.to_string();
+        let from = from.to_string();
+        let to = to.to_string();
+        self.with_conn(move |conn| {
+            update_all_json(
+                conn,
+                "tasks",
+                "SELECT id, data FROM tasks
+                 WHERE board_id = ?1 AND json_extract(data, '$.column') = ?2
+                 ORDER BY id",
+                params![board_id, from],
+                |task: &mut Task| {
+                    task.column = to.clone();
+                    task.version += 1;
+                },
+            )
+        })
+        .await
+    }
+
+    async fn remove_label(&self, board_id: &str, label_id: &str) -> StoreResult<Vec<(Task, Task)>> {
+        let board_id = board_id.to_string();
+        let label_id = label_id.to_string();
+        self.with_conn(move |conn| {
+            update_all_json(
+                conn,
+                "tasks",
+                "SELECT id, data FROM tasks
+                 WHERE board_id = ?1
+                   AND EXISTS (SELECT 1 FROM json_each(data, '$.labels') WHERE value = ?2)
+                 ORDER BY id",
+                params![board_id, label_id],
+                |task: &mut Task| {
+                    task.labels.retain(|l| *l != label_id);
+                    task.version += 1;
+                },
+            )
+        })
+        .await
+    }
+
+    async fn trash_task(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        deleted_at: DateTime<Utc>,
+        expected_version: Option<u64>,
+    ) -> StoreResult<Option<Task>> {
+        let board_id = board_id.to_string();
+        let task_id = task_id.to_string();
+        self.with_conn(move |conn| {
+            update_task_json(
+                conn,
+                "SELECT id, data FROM tasks
+                 WHERE board_id = ?1 AND task_id = ?2 AND deleted_at IS NULL
+                   AND (?3 IS NULL OR IFNULL(json_extract(data, '$.version'), 0) = ?3)",
+                params![board_id, task_id, expected_version],
+                |task| {
+                    task.deleted_at = Some(deleted_at);
+                    task.version += 1;
+                },
+            )
+        })
+        .await
+    }
+
+    async fn list_trash(&self, board_id: &str) -> StoreResult<Vec<Task>> {
+        let board_id = board_id.to_string();
+        self.with_conn(move |conn| {
+            query_json(
+                conn,
+                "SELECT data FROM tasks
+                 WHERE board_id = ?1 AND deleted_at IS NOT NULL ORDER BY id",
+                params![board_id],
+            )
+        })
+        .await
+    }
+
+    async fn restore_task(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        update: &TaskUpdate,
+    ) -> StoreResult<Option<Task>> {
+        let board_id = board_id.to_string();
+        let task_id = task_id.to_string();
+        let update = update.clone();
+        self.with_conn(move |conn| {
+            update_task_json(
+                conn,
+                "SELECT id, data FROM tasks
+                 WHERE board_id = ?1 AND task_id = ?2 AND deleted_at IS NOT NULL",
+                params![board_id, task_id],
+                |task| {
+                    task.deleted_at = None;
+                    update.apply(task);
+                    task.version += 1;
+                },
+            )
+        })
+        .await
+    }
+
+    async fn purge_task(&self, board_id: &str, task_id: &str) -> StoreResult<Option<Task>> {
+        let board_id = board_id.to_string();
+        let task_id = task_id.to_string();
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            let task = query_one_json(
+                &tx,
+                "DELETE FROM tasks
+                 WHERE board_id = ?1 AND task_id = ?2 AND deleted_at IS NOT NULL
+                 RETURNING data",
+                params![board_id, task_id],
+            )?;
+            if task.is_none() {
+                return Ok(None);
+            }
+            tx.execute(
+                "DELETE FROM comments WHERE board_id = ?1 AND task_id = ?2",
+                params![board_id, task_id],
+            )?;
+            tx.commit()?;
+            Ok(task)
+        })
+        .await
+    }
+
+    async fn purge_trash(&self, deleted_before: DateTime<Utc>) -> StoreResult<Vec<Task>> {
+        self.with_conn(move |conn| {
+            let deleted_before = sortable_time::format(&deleted_before);
+            let tx = conn.transaction()?;
+            tx.execute(
+                "DELETE FROM comments WHERE EXISTS (
+                     SELECT 1 FROM tasks t
+                     WHERE t.board_id = comments.board_id AND t.task_id = comments.task_id
+                       AND t.deleted_at < ?1
+                 )",
+                params![deleted_before],
+            )?;
+            let deleted = query_json(
+                &tx,
+                "DELETE FROM tasks WHERE deleted_at < ?1 RETURNING data",
+                params![deleted_before],
+            )?;
+            tx.commit()?;
+            Ok(deleted)
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl BoardStore for SqliteStore {
+    async fn list_boards(&self) -> StoreResult<Vec<Board>> {
+        self.with_conn(|conn| query_json(conn, "SELECT data FROM boards ORDER BY id", []))
+            .await
+    }
+
+    async fn get_board(&self, board_id: &str) -> StoreResult<Option<Board>> {
+        let board_id = board_id.to_string();
+        self.with_conn(move |conn| {
+            query_one_json(
+                conn,
+                "SELECT data FROM boards WHERE board_id = ?1",
+                params![board_id],
+            )
+        })
+        .await
+    }
+
+    async fn create_board(&self, board: Board) -> StoreResult<Board> {
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO boards (board_id, data) VALUES (?1, ?2)",
+                params![board.board_id, serde_json::to_string(&board)?],
+            )?;
+            Ok(board)
+        })
+        .await
+    }
+
+    async fn update_board(
+        &self,
+        board_id: &str,
+        update: &BoardUpdate,
+        expected_version: Option<u64>,
+    ) -> StoreResult<Option<Board>> {
+        let board_id = board_id.to_string();
+        let update = update.clone();
+        self.with_conn(move |conn| {
+            update_json(
+                conn,
+                "boards",
+                "SELECT id, data FROM boards
+                 WHERE board_id = ?1
+                   AND (?2 IS NULL OR IFNULL(json_extract(data, '$.version'), 0) = ?2)",
+                params![board_id, expected_version],
+                |board: &mut Board| {
+                    update.apply(board);
+                    board.version += 1;
+                },
+            )
+        })
+        .await
+    }
+
+    async fn delete_board(&self, board_id: &str) -> StoreResult<Option<Vec<Task>>> {
+        let board_id = board_id.to_string();
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            let deleted =
+                tx.execute("DELETE FROM boards WHERE board_id = ?1", params![board_id])?;
+            if deleted == 0 {
+                return Ok(None);
+            }
+            let tasks = query_json(
+                &tx,
+                "DELETE FROM tasks WHERE board_id = ?1 RETURNING data",
+                params![board_id],
+            )?;
+            tx.execute(
+                "DELETE FROM comments WHERE board_id = ?1",
+                params![board_id],
+            )?;
+            tx.execute(
+                "DELETE FROM task_templates WHERE board_id = ?1",
+                params![board_id],
+            )?;
+            tx.commit()?;
+            Ok(Some(tasks))
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl CommentStore for SqliteStore {
+    async fn create_comment(&self, comment: Comment) -> StoreResult<Comment> {
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO comments (comment_id, board_id, task_id, data)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    comment.comment_id,
+                    comment.board_id,
+                    comment.task_id,
+                    serde_json::to_string(&comment)?
+                ],
+            )?;
+            Ok(comment)
+        })
+        .await
+    }
+
+    async fn get_comment(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        comment_id: &str,
+    ) -> StoreResult<Option<Comment>> {
+        let board_id = board_id.to_string();
+        let task_id = task_id.to_string();
+        let comment_id = comment_id.to_string();
+        self.with_conn(move |conn| {
+            query_one_json(
+                conn,
+                "SELECT data FROM comments
+                 WHERE board_id = ?1 AND task_id = ?2 AND comment_id = ?3",
+                params![board_id, task_id, comment_id],
+            )
+        })
+        .await
+    }
+
+    async fn update_comment(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        comment_id: &str,
+        update: &CommentUpdate,
+    ) -> StoreResult<Option<Comment>> {
+        let board_id = board_id.to_string();
+        let task_id = task_id.to_string();
+        let comment_id = comment_id.to_string();
+        let update = update.clone();
+        self.with_conn(move |conn| {
+            update_json(
+                conn,
+                "comments",
+                "SELECT id, data FROM comments
+                 WHERE board_id = ?1 AND task_id = ?2 AND comment_id = ?3",
+                params![board_id, task_id, comment_id],
+                |comment: &mut Comment| {
+                    comment.body = update.body;
+                    comment.mentions = update.mentions;
+                    comment.edited_at = Some(update.edited_at);
+                },
+            )
+        })
+        .await
+    }
+
+    async fn delete_comment(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        comment_id: &str,
+    ) -> StoreResult<bool> {
+        let board_id = board_id.to_string();
+        let task_id = task_id.to_string();
+        let comment_id = comment_id.to_string();
+        self.with_conn(move |conn| {
+            let deleted = conn.execute(
+                "DELETE FROM comments WHERE board_id = ?1 AND task_id = ?2 AND comment_id = ?3",
+                params![board_id, task_id, comment_id],
+            )?;
+            Ok(deleted > 0)
+        })
+        .await
+    }
+
+    async fn list_comments(
+        &self,
+        board_id: &str,
+        task_id: &str,
+        after: Option<&str>,
+        limit: usize,
+    ) -> StoreResult<Vec<Comment>> {
+        let board_id = board_id.to_string();
+        let task_id = task_id.to_string();
+        let after = after.map(str::to_string);
+        self.with_conn(move |conn| {
+            query_json(
+                conn,
+                "SELECT data FROM comments
+                 WHERE board_id = ?1 AND task_id = ?2 AND (?3 IS NULL OR comment_id > ?3)
+                 ORDER BY comment_id
+                 LIMIT ?4",
+                params![board_id, task_id, after, limit as i64],
+            )
+        })
+        .await
+    }
+
+    async fn count_comments(&self, board_id: &str) -> StoreResult<HashMap<String, u64>> {
+        let board_id = board_id.to_string();
+        self.with_conn(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT task_id, COUNT(*) FROM comments WHERE board_id = ?1 GROUP BY task_id",
+            )?;
+            let rows = stmt.query_map(params![board_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64))
+            })?;
+            Ok(rows.collect::<Result<HashMap<_, _>, _>>()?)
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl TemplateStore for SqliteStore {
+    async fn create_template(&self, template: TaskTemplate) -> StoreResult<TaskTemplate> {
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO task_templates (template_id, board_id, data) VALUES (?1, ?2, ?3)",
+                params![
+                    template.template_id,
+                    template.board_id,
+                    serde_json::to_string(&template)?
+                ],
+            )?;
+            Ok(template)
+        })
+        .await
+    }
+
+    async fn list_templates(&self, board_id: &str) -> StoreResult<Vec<TaskTemplate>> {
+        let board_id = board_id.to_string();
+        self.with_conn(move |conn| {
+            query_json(
+                conn,
+                "SELECT data FROM task_templates WHERE board_id = ?1 ORDER BY id",
+                params![board_id],
+            )
+        })
+        .await
+    }
+
+    async fn get_template(
+        &self,
+        board_id: &str,
+        template_id: &str,
+    ) -> StoreResult<Option<TaskTemplate>> {
+        let board_id = board_id.to_string();
+        let template_id = template_id.to_string();
+        self.with_conn(move |conn| {
+            query_one_json(
+                conn,
+                "SELECT data FROM task_templates WHERE board_id = ?1 AND template_id = ?2",
+                params![board_id, template_id],
+            )
+        })
+        .await
+    }
+
+    async fn update_template(
+        &self,
+        board_id: &str,
+        template_id: &str,
+        update: &TemplateUpdate,
+    ) -> StoreResult<Option<TaskTemplate>> {
+        let board_id = board_id.to_string();
+        let template_id = template_id.to_string();
+        let update = update.clone();
+        self.with_conn(move |conn| {
+            update_json(
+                conn,
+                "task_templates",
+                "SELECT id, data FROM task_templates WHERE board_id = ?1 AND template_id = ?2",
+                params![board_id, template_id],
+                |template: &mut TaskTemplate| update.apply(template),
+            )
+        })
+        .await
+    }
+
+    async fn delete_template(&self, board_id: &str, template_id: &str) -> StoreResult<bool> {
+        let board_id = board_id.to_string();
+        let template_id = template_id.to_string();
+        self.with_conn(move |conn| {
+            let deleted = conn.execute(
+                "DELETE FROM task_templates WHERE board_id = ?1 AND template_id = ?2",
+                params![board_id, template_id],
+            )?;
+            Ok(deleted > 0)
+        })
+        .await
+    }
+
+    async fn due_templates(&self, now: DateTime<Utc>) -> StoreResult<Vec<TaskTemplate>> {
+        let now = sortable_time::format(&now);
+        self.with_conn(move |conn| {
+            query_json(
+                conn,
+                "SELECT data FROM task_templates
+                 WHERE json_extract(data, '$.nextRunAt') <= ?1
+                 ORDER BY id",
+                params![now],
+            )
+        })
+        .await
+    }
+
+    async fn advance_template(
+        &self,
+        board_id: &str,
+        template_id: &str,
+        from: DateTime<Utc>,
+        to: Option<DateTime<Utc>>,
+    ) -> StoreResult<bool> {
+        let board_id = board_id.to_string();
+        let template_id = template_id.to_string();
+        let from = sortable_time::format(&from);
+        let to = to.as_ref().map(sortable_time::format);
+        self.with_conn(move |conn| {
+            let updated = conn.execute(
+                "UPDATE task_templates
+                 SET data = CASE WHEN ?4 IS NULL
+                     THEN json_remove(data, '$.nextRunAt')
+                     ELSE json_set(data, '$.nextRunAt', ?4) END
+                 WHERE board_id = ?1 AND template_id = ?2
+                   AND json_extract(data, '$.nextRunAt') = ?3",
+                params![board_id, template_id, from, to],
+            )?;
+            Ok(updated > 0)
+        })
+        .await
+    }
+
+    async fn create_board_template(&self, template: BoardTemplate) -> StoreResult<BoardTemplate> {
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO board_templates (template_id, user_id, data) VALUES (?1, ?2, ?3)",
+                params![
+                    template.template_id,
+                    template.created_by,
+                    serde_json::to_string(&template)?
+                ],
+            )?;
+            Ok(template)
+        })
+        .await
+    }
+
+    async fn list_board_templates(&self, user_id: &str) -> StoreResult<Vec<BoardTemplate>> {
+        let user_id = user_id.to_string();
+        self.with_conn(move |conn| {
+            query_json(
+                conn,
+                "SELECT data FROM board_templates WHERE user_id = ?1 ORDER BY id",
+                params![user_id],
+            )
+        })
+        .await
+    }
+
+    async fn get_board_template(
+        &self,
+        user_id: &str,
+        template_id: &str,
+    ) -> StoreResult<Option<BoardTemplate>> {
+        let user_id = user_id.to_string();
+        let template_id = template_id.to_string();
+        self.with_conn(move |conn| {
+            query_one_json(
+                conn,
+                "SELECT data FROM board_templates WHERE user_id = ?1 AND template_id = ?2",
+                params![user_id, template_id],
+            )
+        })
+        .await
+    }
+
+    async fn delete_board_template(&self, user_id: &str, template_id: &str) -> StoreResult<bool> {
+        let user_id = user_id.to_string();
+        let template_id = template_id.to_string();
+        self.with_conn(move |conn| {
+            let deleted = conn.execute(
+                "DELETE FROM board_templates WHERE user_id = ?1 AND template_id = ?2",
+                params![user_id, template_id],
+            )?;
+            Ok(deleted > 0)
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl UserStore for SqliteStore {
+    async fn create_user(&self, user: User) -> StoreResult<User> {
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO users (user_id, username, data) VALUES (?1, ?2, ?3)",
+                params![user.user_id, user.username, serde_json::to_string(&user)?],
+            )?;
+            Ok(user)
+        })
+        .await
+    }
+
+    async fn get_user(&self, user_id: &str) -> StoreResult<Option<User>> {
+        let user_id = user_id.to_string();
+        self.with_conn(move |conn| {
+            query_one_json(
+                conn,
+                "SELECT data FROM users WHERE user_id = ?1",
+                params![user_id],
+            )
+        })
+        .await
+    }
+
+    async fn get_user_by_username(&self, username: &str) -> StoreResult<Option<User>> {
+        let username = username.to_string();
+        self.with_conn(move |conn| {
+            query_one_json(
+                conn,
+                "SELECT data FROM users WHERE username = ?1",
+                params![username],
+            )
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl SessionStore for SqliteStore {
+    async fn create_session(&self, session: Session) -> StoreResult<Session> {
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO sessions (token_hash, user_id, data) VALUES (?1, ?2, ?3)",
+                params![
+                    session.token_hash,
+                    session.user_id,
+                    serde_json::to_string(&session)?
+                ],
+            )?;
+            Ok(session)
+        })
+        .await
+    }
+
+    async fn get_session(&self, token_hash: &str) -> StoreResult<Option<Session>> {
+        let token_hash = token_hash.to_string();
+        self.with_conn(move |conn| {
+            query_one_json(
+                conn,
+                "SELECT data FROM sessions WHERE token_hash = ?1",
+                params![token_hash],
+            )
+        })
+        .await
+    }
+
+    async fn delete_session(&self, token_hash: &str) -> StoreResult<bool> {
+        let token_hash = token_hash.to_string();
+        self.with_conn(move |conn| {
+            let deleted = conn.execute(
+                "DELETE FROM sessions WHERE token_hash = ?1",
+                params![token_hash],
+            )?;
+            Ok(deleted > 0)
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl ApiTokenStore for SqliteStore {
+    async fn create_api_token(&self, token: ApiToken) -> StoreResult<ApiToken> {
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO api_tokens (token_id, token_hash, user_id, data)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    token.token_id,
+                    token.token_hash,
+                    token.user_id,
+                    serde_json::to_string(&token)?
+                ],
+            )?;
+            Ok(token)
+        })
+        .await
+    }
+
+    async fn list_api_tokens(&self, user_id: &str) -> StoreResult<Vec<ApiToken>> {
+        let user_id = user_id.to_string();
+        self.with_conn(move |conn| {
+            query_json(
+                conn,
+                "SELECT data FROM api_tokens WHERE user_id = ?1 ORDER BY id",
+                params![user_id],
+            )
+        })
+        .await
+    }
+
+    async fn get_api_token(&self, token_hash: &str) -> StoreResult<Option<ApiToken>> {
+        let token_hash = token_hash.to_string();
+        self.with_conn(move |conn| {
+            query_one_json(
+                conn,
+                "SELECT data FROM api_tokens WHERE token_hash = ?1",
+                params![token_hash],
+            )
+        })
+        .await
+    }
+
+    async fn delete_api_token(&self, user_id: &str, token_id: &str) -> StoreResult<bool> {
+        let user_id = user_id.to_string();
+        let token_id = token_id.to_string();
+        self.with_conn(move |conn| {
+            let deleted = conn.execute(
+                "DELETE FROM api_tokens WHERE user_id = ?1 AND token_id = ?2",
+                params![user_id, token_id],
+            )?;
+            Ok(deleted > 0)
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl EventStore for SqliteStore {
+    async fn append_event(&self, mut event: TaskEvent) -> StoreResult<TaskEvent> {
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            tx.execute(
+                "INSERT INTO task_events (board_id, task_id, data) VALUES (?1, ?2, '{}')",
+                params![event.board_id, event.task_id],
+            )?;
+            event.id = tx.last_insert_rowid() as u64;
+            tx.execute(
+                "UPDATE task_events SET data = ?1 WHERE id = ?2",
+                params![serde_json::to_string(&event)?, event.id as i64],
+            )?;
+            tx.commit()?;
+            Ok(event)
+        })
+        .await
+    }
+
+    async fn list_events_after(&self, after: u64, limit: usize) -> StoreResult<Vec<TaskEvent>> {
+        self.with_conn(move |conn| {
+            query_json(
+                conn,
+                "SELECT data FROM task_events WHERE id > ?1 ORDER BY id LIMIT ?2",
+                params![after as i64, limit as i64],
+            )
+        })
+        .await
+    }
+
+    async fn find_events(&self, filter: &EventFilter) -> StoreResult<Vec<TaskEvent>> {
+        let filter = filter.clone();
+        self.with_conn(move |conn| {
+            // `at` is stored in a fixed-width format, so string order is time
+            // order.
+            query_json(
+                conn,
+                "SELECT data FROM task_events
+                 WHERE board_id = ?1
+                   AND (?2 IS NULL OR task_id = ?2)
+                   AND (?3 IS NULL OR json_extract(data, '$.at') >= ?3)
+                   AND (?4 IS NULL OR json_extract(data, '$.at') < ?4)
+                   AND id > ?5
+                 ORDER BY id
+                 LIMIT ?6",
+                params![
+                    filter.board_id,
+                    filter.task_id,
+                    filter.from.as_ref().map(sortable_time::format),
+                    filter.to.as_ref().map(sortable_time::format),
+                    filter.after.unwrap_or(0) as i64,
+                    filter.limit as i64
+                ],
+            )
+        })
+        .await
+    }
+}
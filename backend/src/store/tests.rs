@@ -0,0 +1,434 @@
+//! Conformance checks for the store contracts. Every check runs against
+//! each backend that needs no server; MongoDB is left to manual testing.
+
+use chrono::{Duration, Utc};
+
+use super::{MemoryStore, SqliteStore, Store};
+use crate::models::{
+    default_columns, Board, BoardUpdate, EventFilter, Priority, Task, TaskEvent, TaskEventKind,
+    TaskFilter, TaskUpdate,
+};
+
+fn board(board_id: &str) -> Board {
+    Board {
+        board_id: board_id.to_string(),
+        name: board_id.to_string(),
+        description: String::new(),
+        created_at: Utc::now(),
+        columns: default_columns(),
+        members: Vec::new(),
+        labels: Vec::new(),
+        version: 1,
+    }
+}
+
+fn task(board_id: &str, task_id: &str) -> Task {
+    Task {
+        id: None,
+        task_id: task_id.to_string(),
+        board_id: board_id.to_string(),
+        title: task_id.to_string(),
+        description: String::new(),
+        column: "todo".to_string(),
+        rank: "1".to_string(),
+        deleted_at: None,
+        start_at: None,
+        due_at: None,
+        labels: Vec::new(),
+        assignees: Vec::new(),
+        priority: Priority::None,
+        estimate: None,
+        blocked_by: Vec::new(),
+        checklist: Vec::new(),
+        attachments: Vec::new(),
+        template_id: None,
+        version: 1,
+    }
+}
+
+fn event(board_id: &str, task_id: &str) -> TaskEvent {
+    TaskEvent {
+        id: 0,
+        kind: TaskEventKind::Updated,
+        board_id: board_id.to_string(),
+        task_id: task_id.to_string(),
+        task: Some(task(board_id, task_id)),
+        before: None,
+        actor_id: "user-1".to_string(),
+        at: Utc::now(),
+        wip_override: None,
+    }
+}
+
+fn retitle(title: &str) -> TaskUpdate {
+    TaskUpdate {
+        title: Some(title.to_string()),
+        ..Default::default()
+    }
+}
+
+fn ids(tasks: &[Task]) -> Vec<&str> {
+    tasks.iter().map(|t| t.task_id.as_str()).collect()
+}
+
+/// Tasks on one board are invisible through every other board.
+async fn board_scoping(store: &dyn Store) {
+    store.create_board(board("board-a")).await.unwrap();
+    store.create_board(board("board-b")).await.unwrap();
+    store.create_task(task("board-a", "task-a")).await.unwrap();
+    store.create_task(task("board-b", "task-b")).await.unwrap();
+
+    assert_eq!(ids(&store.list_tasks("board-a").await.unwrap()), ["task-a"]);
+    let found = store
+        .find_tasks("board-b", &TaskFilter::default())
+        .await
+        .unwrap();
+    assert_eq!(ids(&found), ["task-b"]);
+    assert!(store.get_task("board-b", "task-a").await.unwrap().is_none());
+    assert!(store
+        .update_task("board-b", "task-a", &retitle("x"), None)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(store
+        .trash_task("board-b", "task-a", Utc::now(), None)
+        .await
+        .unwrap()
+        .is_none());
+    let task = store.get_task("board-a", "task-a").await.unwrap().unwrap();
+    assert_eq!((task.title.as_str(), task.version), ("task-a", 1));
+    assert!(task.deleted_at.is_none());
+}
+
+/// Updates bump the version and only apply while the expected version
+/// still matches.
+async fn conditional_update(store: &dyn Store) {
+    store.create_task(task("board-a", "task-a")).await.unwrap();
+
+    let updated = store
+        .update_task("board-a", "task-a", &retitle("first"), Some(1))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!((updated.title.as_str(), updated.version), ("first", 2));
+
+    let stale = store
+        .update_task("board-a", "task-a", &retitle("stale"), Some(1))
+        .await
+        .unwrap();
+    assert!(stale.is_none());
+    let stored = store.get_task("board-a", "task-a").await.unwrap().unwrap();
+    assert_eq!((stored.title.as_str(), stored.version), ("first", 2));
+
+    let unchecked = store
+        .update_task("board-a", "task-a", &retitle("second"), None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!((unchecked.title.as_str(), unchecked.version), ("second", 3));
+}
+
+/// Trashing checks the expected version like an update does.
+async fn conditional_trash(store: &dyn Store) {
+    store.create_task(task("board-a", "task-a")).await.unwrap();
+    store
+        .update_task("board-a", "task-a", &retitle("edited"), None)
+        .await
+        .unwrap();
+
+    let stale = store
+        .trash_task("board-a", "task-a", Utc::now(), Some(1))
+        .await
+        .unwrap();
+    assert!(stale.is_none());
+    assert!(store.list_trash("board-a").await.unwrap().is_empty());
+
+    let trashed = store
+        .trash_task("board-a", "task-a", Utc::now(), Some(2))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(trashed.version, 3);
+    assert!(trashed.deleted_at.is_some());
+    let again = store
+        .trash_task("board-a", "task-a", Utc::now(), None)
+        .await
+        .unwrap();
+    assert!(again.is_none());
+}
+
+/// Trashed tasks only show up through the trash methods.
+async fn trash_visibility(store: &dyn Store) {
+    store.create_task(task("board-a", "task-a")).await.unwrap();
+    store.create_task(task("board-a", "task-b")).await.unwrap();
+    store
+        .trash_task("board-a", "task-a", Utc::now(), None)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(ids(&store.list_tasks("board-a").await.unwrap()), ["task-b"]);
+    let found = store
+        .find_tasks("board-a", &TaskFilter::default())
+        .await
+        .unwrap();
+    assert_eq!(ids(&found), ["task-b"]);
+    assert!(store.get_task("board-a", "task-a").await.unwrap().is_none());
+    assert!(store
+        .update_task("board-a", "task-a", &retitle("x"), None)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(ids(&store.list_trash("board-a").await.unwrap()), ["task-a"]);
+    assert!(store.list_trash("board-b").await.unwrap().is_empty());
+    assert!(store
+        .purge_task("board-a", "task-b")
+        .await
+        .unwrap()
+        .is_none());
+
+    let restored = store
+        .restore_task("board-a", "task-a", &TaskUpdate::default())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(restored.deleted_at.is_none());
+    assert!(store.list_trash("board-a").await.unwrap().is_empty());
+    assert!(store.get_task("board-a", "task-a").await.unwrap().is_some());
+
+    store
+        .trash_task("board-a", "task-a", Utc::now() - Duration::days(2), None)
+        .await
+        .unwrap();
+    let purged = store
+        .purge_trash(Utc::now() - Duration::days(1))
+        .await
+        .unwrap();
+    assert_eq!(ids(&purged), ["task-a"]);
+    assert!(store.list_trash("board-a").await.unwrap().is_empty());
+    assert_eq!(ids(&store.list_tasks("board-a").await.unwrap()), ["task-b"]);
+}
+
+/// Board updates bump the version and only apply while the expected
+/// version still matches.
+async fn conditional_board_update(store: &dyn Store) {
+    store.create_board(board("board-a")).await.unwrap();
+    let rename = |name: &str| BoardUpdate {
+        name: Some(name.to_string()),
+        ..Default::default()
+    };
+
+    let updated = store
+        .update_board("board-a", &rename("first"), Some(1))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!((updated.name.as_str(), updated.version), ("first", 2));
+    let stale = store
+        .update_board("board-a", &rename("stale"), Some(1))
+        .await
+        .unwrap();
+    assert!(stale.is_none());
+    let stored = store.get_board("board-a").await.unwrap().unwrap();
+    assert_eq!((stored.name.as_str(), stored.version), ("first", 2));
+
+    let unchecked = store
+        .update_board("board-a", &rename("second"), None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(unchecked.version, 3);
+    assert!(store
+        .update_board("board-b", &rename("x"), None)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+/// Column moves and label removal touch every matching task on the board,
+/// trashed ones included, and return each as it was before and after.
+async fn bulk_task_changes(store: &dyn Store) {
+    let mut labelled = task("board-a", "task-a");
+    labelled.labels = vec!["bug".to_string(), "ui".to_string()];
+    store.create_task(labelled).await.unwrap();
+    let mut done = task("board-a", "task-b");
+    done.column = "done".to_string();
+    store.create_task(done).await.unwrap();
+    store.create_task(task("board-a", "task-c")).await.unwrap();
+    store.create_task(task("board-b", "task-d")).await.unwrap();
+    store
+        .trash_task("board-a", "task-c", Utc::now(), None)
+        .await
+        .unwrap();
+
+    let moved = store
+        .move_column_tasks("board-a", "todo", "doing")
+        .await
+        .unwrap();
+    let mut pairs: Vec<_> = moved
+        .iter()
+        .map(|(before, after)| {
+            assert_eq!(before.task_id, after.task_id);
+            (
+                before.task_id.as_str(),
+                before.column.as_str(),
+                after.column.as_str(),
+                after.version - before.version,
+            )
+        })
+        .collect();
+    pairs.sort();
+    assert_eq!(
+        pairs,
+        [
+            ("task-a", "todo", "doing", 1),
+            ("task-c", "todo", "doing", 1)
+        ]
+    );
+    let stored = store.get_task("board-a", "task-a").await.unwrap().unwrap();
+    assert_eq!((stored.column.as_str(), stored.version), ("doing", 2));
+    let other = store.get_task("board-b", "task-d").await.unwrap().unwrap();
+    assert_eq!(other.column, "todo");
+
+    let removed = store.remove_label("board-a", "bug").await.unwrap();
+    assert_eq!(removed.len(), 1);
+    let (before, after) = &removed[0];
+    assert_eq!(before.labels, ["bug", "ui"]);
+    assert_eq!(after.labels, ["ui"]);
+    assert_eq!(after.version, before.version + 1);
+    let stored = store.get_task("board-a", "task-a").await.unwrap().unwrap();
+    assert_eq!(
+        (stored.labels.clone(), stored.version),
+        (vec!["ui".to_string()], 3)
+    );
+    assert!(store
+        .remove_label("board-a", "bug")
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+/// Event ids start above zero and only ever increase.
+async fn event_sequencing(store: &dyn Store) {
+    let mut appended = Vec::new();
+    for (board_id, task_id) in [
+        ("board-a", "task-a"),
+        ("board-b", "task-b"),
+        ("board-a", "task-c"),
+    ] {
+        appended.push(
+            store
+                .append_event(event(board_id, task_id))
+                .await
+                .unwrap()
+                .id,
+        );
+    }
+    assert!(appended[0] > 0);
+    assert!(appended.windows(2).all(|pair| pair[0] < pair[1]));
+
+    let after: Vec<u64> = store
+        .list_events_after(appended[0], 10)
+        .await
+        .unwrap()
+        .iter()
+        .map(|e| e.id)
+        .collect();
+    assert_eq!(after, appended[1..]);
+    assert_eq!(store.list_events_after(0, 1).await.unwrap().len(), 1);
+    assert!(store
+        .list_events_after(appended[2], 10)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let on_board = store
+        .find_events(&EventFilter {
+            board_id: "board-a".to_string(),
+            task_id: None,
+            from: None,
+            to: None,
+            after: Some(appended[0]),
+            limit: 10,
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        on_board.iter().map(|e| e.id).collect::<Vec<_>>(),
+        [appended[2]]
+    );
+}
+
+/// Deleting a board takes its tasks along but leaves the event log alone.
+async fn board_deletion(store: &dyn Store) {
+    store.create_board(board("board-a")).await.unwrap();
+    store.create_task(task("board-a", "task-a")).await.unwrap();
+    let logged = store
+        .append_event(event("board-a", "task-a"))
+        .await
+        .unwrap();
+
+    let deleted = store.delete_board("board-a").await.unwrap().unwrap();
+    assert_eq!(ids(&deleted), ["task-a"]);
+    assert!(store.delete_board("board-a").await.unwrap().is_none());
+    assert!(store.list_tasks("board-a").await.unwrap().is_empty());
+    let kept: Vec<u64> = store
+        .list_events_after(0, 10)
+        .await
+        .unwrap()
+        .iter()
+        .map(|e| e.id)
+        .collect();
+    assert_eq!(kept, [logged.id]);
+}
+
+macro_rules! conformance {
+    ($backend:ident, $store:expr) => {
+        mod $backend {
+            use super::*;
+
+            #[tokio::test]
+            async fn board_scoping() {
+                super::board_scoping(&$store).await;
+            }
+
+            #[tokio::test]
+            async fn conditional_update() {
+                super::conditional_update(&$store).await;
+            }
+
+            #[tokio::test]
+            async fn conditional_trash() {
+                super::conditional_trash(&$store).await;
+            }
+
+            #[tokio::test]
+            async fn trash_visibility() {
+                super::trash_visibility(&$store).await;
+            }
+
+            #[tokio::test]
+            async fn conditional_board_update() {
+                super::conditional_board_update(&$store).await;
+            }
+
+            #[tokio::test]
+            async fn bulk_task_changes() {
+                super::bulk_task_changes(&$store).await;
+            }
+
+            #[tokio::test]
+            async fn event_sequencing() {
+                super::event_sequencing(&$store).await;
+            }
+
+            #[tokio::test]
+            async fn board_deletion() {
+                super::board_deletion(&$store).await;
+            }
+        }
+    };
+}
+
+conformance!(memory, MemoryStore::new());
+conformance!(sqlite, SqliteStore::open(":memory:").unwrap());
@@ -1,9 +1,14 @@
+import { useState } from "react"
 import KanbanBoard from "./pages/Kanban"
+import Login from "./pages/Login"
+import { getToken } from "./api/kanbanApi"
 
 function App() {
+  const [signedIn, setSignedIn] = useState(() => getToken() !== null)
+
   return(
   <>
-    <KanbanBoard/>
+    {signedIn ? <KanbanBoard/> : <Login onSignedIn={() => setSignedIn(true)}/>}
   </>
   )
 
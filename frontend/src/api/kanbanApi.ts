@@ -3,8 +3,34 @@ const API_BASE_URL = import.meta.env.VITE_API_URL || 'http://localhost:8080/api'
 export interface Task {
   _id?: string;
   taskId: string;
-  text: string;
+  title: string;
+  description?: string;
+  descriptionHtml?: string;
   column: 'todo' | 'active' | 'completed';
+  rank: string;
+  startAt?: string;
+  dueAt?: string;
+  labels: string[];
+  assignees: string[];
+  priority: 'urgent' | 'high' | 'medium' | 'low' | 'none';
+  estimate?: number;
+  blockedBy: string[];
+  version: number;
+  checklistProgress?: { completed: number; total: number };
+  commentCount?: number;
+  blocks?: string[];
+  blocked?: boolean;
+  attachments?: Attachment[];
+  templateId?: string;
+}
+
+export interface Attachment {
+  attachmentId: string;
+  filename: string;
+  contentType: string;
+  size: number;
+  uploadedBy: string;
+  uploadedAt: string;
 }
 
 export interface TasksResponse {
@@ -13,22 +39,119 @@ export interface TasksResponse {
   completed: Task[];
 }
 
+interface ColumnTasks {
+  columnId: string;
+  name: string;
+  tasks: Task[];
+  estimateTotal: number;
+}
+
+interface BoardTasksResponse {
+  columns: ColumnTasks[];
+}
+
+export interface UserProfile {
+  userId: string;
+  username: string;
+  createdAt: string;
+}
+
+interface SessionResponse {
+  token: string;
+  expiresAt: string;
+  user: UserProfile;
+}
+
+// Thrown when someone else changed the task since it was loaded.
+export class StaleTaskError extends Error {
+  constructor() {
+    super('This task was changed by someone else');
+  }
+}
+
+// Only apply the change if the task is still at the version we have.
+const ifMatch = (task: Task): Record<string, string> => ({ 'If-Match': `"${task.version}"` });
+
+const TOKEN_KEY = 'kanbanToken';
+
+export const getToken = (): string | null => localStorage.getItem(TOKEN_KEY);
+
+const authHeaders = (headers: Record<string, string> = {}): Record<string, string> => {
+  const token = getToken();
+  return token ? { ...headers, Authorization: `Bearer ${token}` } : headers;
+};
+
+// Sends the stored session token; an expired session drops back to the login form.
+async function authFetch(url: string, init: RequestInit = {}): Promise<Response> {
+  const response = await fetch(url, {
+    ...init,
+    headers: authHeaders(init.headers as Record<string, string> | undefined),
+  });
+  if (response.status === 401) {
+    localStorage.removeItem(TOKEN_KEY);
+    window.location.reload();
+  }
+  return response;
+}
+
+async function startSession(path: string, username: string, password: string): Promise<UserProfile> {
+  const response = await fetch(`${API_BASE_URL}/auth/${path}`, {
+    method: 'POST',
+    headers: {
+      'Content-Type': 'application/json',
+    },
+    body: JSON.stringify({ username, password }),
+  });
+  if (!response.ok) {
+    const body = await response.json().catch(() => null);
+    throw new Error(body?.error ?? 'Failed to sign in');
+  }
+  const session: SessionResponse = await response.json();
+  localStorage.setItem(TOKEN_KEY, session.token);
+  return session.user;
+}
+
+export const authApi = {
+  register(username: string, password: string): Promise<UserProfile> {
+    return startSession('register', username, password);
+  },
+
+  login(username: string, password: string): Promise<UserProfile> {
+    return startSession('login', username, password);
+  },
+
+  async logout(): Promise<void> {
+    await fetch(`${API_BASE_URL}/auth/logout`, {
+      method: 'POST',
+      headers: authHeaders(),
+    }).catch(() => undefined);
+    localStorage.removeItem(TOKEN_KEY);
+  },
+};
+
 export const kanbanApi = {
   async getAllTasks(): Promise<TasksResponse> {
-    const response = await fetch(`${API_BASE_URL}/tasks`);
+    const response = await authFetch(`${API_BASE_URL}/tasks`);
     if (!response.ok) {
       throw new Error('Failed to fetch tasks');
     }
-    return response.json();
+    const board: BoardTasksResponse = await response.json();
+    const tasksIn = (columnId: string) =>
+      board.columns.find((column) => column.columnId === columnId)?.tasks ?? [];
+    return {
+      todo: tasksIn('todo'),
+      active: tasksIn('active'),
+      completed: tasksIn('completed'),
+    };
   },
 
-  async createTask(text: string): Promise<Task> {
-    const response = await fetch(`${API_BASE_URL}/tasks`, {
+  async createTask(title: string): Promise<Task> {
+    const response = await authFetch(`${API_BASE_URL}/tasks`, {
       method: 'POST',
       headers: {
         'Content-Type': 'application/json',
       },
-      body: JSON.stringify({ text }),
+      body: JSON.stringify({ title }),
     });
     if (!response.ok) {
       throw new Error('Failed to create task');
@@ -36,24 +159,60 @@ export const kanbanApi = {
     return response.json();
   },
 
-  async updateTask(taskId: string, updates: { text?: string; column?: string }): Promise<Task> {
-    const response = await fetch(`${API_BASE_URL}/tasks/${taskId}`, {
+  async updateTask(task: Task, updates: { title?: string; description?: string; column?: string }): Promise<Task> {
+    const response = await authFetch(`${API_BASE_URL}/tasks/${task.taskId}`, {
       method: 'PUT',
       headers: {
         'Content-Type': 'application/json',
+        ...ifMatch(task),
       },
       body: JSON.stringify(updates),
     });
+    if (response.status === 412) {
+      throw new StaleTaskError();
+    }
     if (!response.ok) {
       throw new Error('Failed to update task');
     }
     return response.json();
   },
 
-  async deleteTask(taskId: string): Promise<void> {
-    const response = await fetch(`${API_BASE_URL}/tasks/${taskId}`, {
+  async moveTask(
+    taskId: string,
+    position: { column: string; after?: string; before?: string }
+  ): Promise<Task> {
+    const response = await authFetch(`${API_BASE_URL}/tasks/${taskId}/move`, {
+      method: 'POST',
+      headers: {
+        'Content-Type': 'application/json',
+      },
+      body: JSON.stringify(position),
+    });
+    if (!response.ok) {
+      throw new Error('Failed to move task');
+    }
+    return response.json();
+  },
+
+  // Calls onChange whenever someone changes a task on the board; returns a
+  // function that closes the connection.
+  subscribe(onChange: () => void): () => void {
+    const url = new URL(`${API_BASE_URL}/ws`, window.location.href);
+    url.protocol = url.protocol === 'https:' ? 'wss:' : 'ws:';
+    url.searchParams.set('access_token', getToken() ?? '');
+    const socket = new WebSocket(url);
+    socket.onmessage = () => onChange();
+    return () => socket.close();
+  },
+
+  async deleteTask(task: Task): Promise<void> {
+    const response = await authFetch(`${API_BASE_URL}/tasks/${task.taskId}`, {
       method: 'DELETE',
+      headers: ifMatch(task),
     });
+    if (response.status === 412) {
+      throw new StaleTaskError();
+    }
     if (!response.ok) {
       throw new Error('Failed to delete task');
     }
@@ -21,7 +21,7 @@ import {
 } from '@dnd-kit/sortable';
 import { CSS } from '@dnd-kit/utilities';
 import { Plus, X, Edit2, Check, GripVertical, Loader2 } from 'lucide-react';
-import { kanbanApi, type Task } from '../api/kanbanApi';
+import { kanbanApi, StaleTaskError, type Task } from '../api/kanbanApi';
 
 interface TasksState {
   todo: Task[];
@@ -103,7 +103,7 @@ const SortableTask: React.FC<SortableTaskProps> = ({
           <div {...listeners} {...attributes} className="mt-1 cursor-grab active:cursor-grabbing">
             <GripVertical size={18} className="text-gray-400" />
           </div>
-          <p className="text-gray-100 flex-1">{task.text}</p>
+          <p className="text-gray-100 flex-1">{task.title}</p>
           <div className="flex gap-1">
             <button
               onClick={() => onEdit(task)}
@@ -168,6 +168,12 @@ const KanbanBoard: React.FC = () => {
 
   useEffect(() => {
     loadTasks();
+    return kanbanApi.subscribe(() => {
+      kanbanApi
+        .getAllTasks()
+        .then(setTasks)
+        .catch((err) => console.error('Error refreshing tasks:', err));
+    });
   }, []);
 
   const loadTasks = async (): Promise<void> => {
@@ -202,13 +208,21 @@ const KanbanBoard: React.FC = () => {
   };
 
   const deleteTask = async (columnId: ColumnId, taskId: string): Promise<void> => {
+    const task = tasks[columnId].find(t => t.taskId === taskId);
+    if (!task) return;
+
     try {
-      await kanbanApi.deleteTask(taskId);
+      await kanbanApi.deleteTask(task);
       setTasks(prev => ({
         ...prev,
         [columnId]: prev[columnId].filter(task => task.taskId !== taskId)
       }));
     } catch (err) {
+      if (err instanceof StaleTaskError) {
+        setError(`${err.message}; the board has been refreshed.`);
+        loadTasks();
+        return;
+      }
       setError('Failed to delete task. Please try again.');
       console.error('Error deleting task:', err);
     }
@@ -216,23 +230,29 @@ const KanbanBoard: React.FC = () => {
 
   const startEdit = (task: Task): void => {
     setEditingTask(task.taskId);
-    setEditText(task.text);
+    setEditText(task.title);
   };
 
   const saveEdit = async (columnId: ColumnId, taskId: string): Promise<void> => {
-    if (!editText.trim()) return;
+    const task = tasks[columnId].find(t => t.taskId === taskId);
+    if (!editText.trim() || !task) return;
 
     try {
-      await kanbanApi.updateTask(taskId, { text: editText.trim() });
+      const updated = await kanbanApi.updateTask(task, { title: editText.trim() });
       setTasks(prev => ({
         ...prev,
         [columnId]: prev[columnId].map(task =>
-          task.taskId === taskId ? { ...task, text: editText.trim() } : task
+          task.taskId === taskId ? updated : task
         )
       }));
       setEditingTask(null);
       setEditText('');
     } catch (err) {
+      if (err instanceof StaleTaskError) {
+        setError(`${err.message}; the board has been refreshed.`);
+        loadTasks();
+        return;
+      }
       setError('Failed to update task. Please try again.');
       console.error('Error updating task:', err);
     }
@@ -322,15 +342,14 @@ const KanbanBoard: React.FC = () => {
       return;
     }
 
-    // Update the task's column in the backend
-    await kanbanApi.updateTask(active.id as string, { column: overContainer });
-
     // If within the same container, just reorder
+    let columnTasks = tasks[overContainer];
     if (activeContainer === overContainer) {
       const activeIndex = tasks[activeContainer].findIndex(task => task.taskId === active.id);
       const overIndex = tasks[overContainer].findIndex(task => task.taskId === over.id);
       
       if (activeIndex !== overIndex) {
+        columnTasks = arrayMove(columnTasks, activeIndex, overIndex);
         setTasks(prev => ({
           ...prev,
           [overContainer]: arrayMove(prev[overContainer], activeIndex, overIndex),
@@ -338,6 +357,14 @@ const KanbanBoard: React.FC = () => {
       }
     }
 
+    // Persist the task's column and its position among its new neighbours
+    const position = columnTasks.findIndex(task => task.taskId === active.id);
+    await kanbanApi.moveTask(active.id as string, {
+      column: overContainer,
+      after: columnTasks[position - 1]?.taskId,
+      before: columnTasks[position + 1]?.taskId,
+    });
+
     setActiveId(null);
   };
 
@@ -472,7 +499,7 @@ const KanbanBoard: React.FC = () => {
                   <p className="text-gray-100 flex-1">
                     {Object.values(tasks)
                       .flat()
-                      .find(task => task.taskId === activeId)?.text}
+                      .find(task => task.taskId === activeId)?.title}
                   </p>
                 </div>
               </div>
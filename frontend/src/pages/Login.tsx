@@ -0,0 +1,84 @@
+import React, { useState } from 'react';
+import { Loader2 } from 'lucide-react';
+import { authApi } from '../api/kanbanApi';
+
+interface LoginProps {
+  onSignedIn: () => void;
+}
+
+const Login: React.FC<LoginProps> = ({ onSignedIn }) => {
+  const [mode, setMode] = useState<'login' | 'register'>('login');
+  const [username, setUsername] = useState('');
+  const [password, setPassword] = useState('');
+  const [error, setError] = useState<string | null>(null);
+  const [submitting, setSubmitting] = useState(false);
+
+  const handleSubmit = async (e: React.FormEvent) => {
+    e.preventDefault();
+    setSubmitting(true);
+    setError(null);
+    try {
+      if (mode === 'login') {
+        await authApi.login(username, password);
+      } else {
+        await authApi.register(username, password);
+      }
+      onSignedIn();
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to sign in');
+    } finally {
+      setSubmitting(false);
+    }
+  };
+
+  return (
+    <div className="min-h-screen bg-gradient-to-br from-gray-900 via-gray-800 to-gray-900 flex items-center justify-center p-4">
+      <form
+        onSubmit={handleSubmit}
+        className="bg-gray-800 rounded-xl shadow-2xl p-6 w-full max-w-sm border border-gray-700 space-y-4"
+      >
+        <h1 className="text-3xl font-bold text-white">
+          {mode === 'login' ? 'Sign in' : 'Create account'}
+        </h1>
+        {error && (
+          <div className="bg-red-900/50 border border-red-500 text-red-200 px-4 py-3 rounded-lg">
+            {error}
+          </div>
+        )}
+        <input
+          type="text"
+          value={username}
+          onChange={(e) => setUsername(e.target.value)}
+          placeholder="Username"
+          autoComplete="username"
+          className="w-full px-4 py-3 bg-gray-700 border border-gray-600 rounded-lg focus:outline-none focus:ring-2 focus:ring-purple-500 text-white placeholder-gray-400"
+        />
+        <input
+          type="password"
+          value={password}
+          onChange={(e) => setPassword(e.target.value)}
+          placeholder="Password"
+          autoComplete={mode === 'login' ? 'current-password' : 'new-password'}
+          className="w-full px-4 py-3 bg-gray-700 border border-gray-600 rounded-lg focus:outline-none focus:ring-2 focus:ring-purple-500 text-white placeholder-gray-400"
+        />
+        <button
+          type="submit"
+          disabled={submitting}
+          className="w-full bg-purple-600 text-white px-6 py-3 rounded-lg hover:bg-purple-700 transition-colors flex items-center justify-center gap-2 shadow-lg disabled:opacity-60"
+        >
+          {submitting && <Loader2 className="animate-spin" size={18} />}
+          {mode === 'login' ? 'Sign in' : 'Register'}
+        </button>
+        <button
+          type="button"
+          onClick={() => setMode(mode === 'login' ? 'register' : 'login')}
+          className="w-full text-gray-400 hover:text-gray-200 text-sm"
+        >
+          {mode === 'login' ? 'Need an account? Register' : 'Already have an account? Sign in'}
+        </button>
+      </form>
+    </div>
+  );
+};
+
+export default Login;